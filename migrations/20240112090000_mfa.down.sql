@@ -0,0 +1,12 @@
+-- Add down migration script here
+
+-- Delete mfa_recovery_code table
+
+drop table if exists "mfa_recovery_code" cascade;
+
+-- Remove account role and multi-factor authentication columns from guest table
+
+alter table "guest"
+  drop column if exists role,
+  drop column if exists mfa_enabled,
+  drop column if exists mfa_secret;
@@ -0,0 +1,19 @@
+-- Add up migration script here
+
+-- Add account role and multi-factor authentication columns to guest table
+
+alter table "guest"
+  add column if not exists role varchar(20) not null default 'guest',
+  add column if not exists mfa_enabled boolean not null default false,
+  add column if not exists mfa_secret varchar(64);
+
+-- Create mfa_recovery_code table
+
+create table if not exists "mfa_recovery_code" (
+  id serial primary key not null,
+  guest_id int not null,
+  code_hash varchar(100) not null,
+  used_at timestamptz,
+  created_at timestamptz default now(),
+  foreign key (guest_id) references guest (id) on delete cascade
+);
@@ -0,0 +1,11 @@
+-- Add down migration script here
+
+-- Delete payment table
+
+drop table if exists "payment" cascade;
+
+-- Delete folio_line table
+
+drop table if exists "folio_line" cascade;
+
+-- The seeded payment statuses are kept, existing bookings still reference them
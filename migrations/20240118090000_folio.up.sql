@@ -0,0 +1,54 @@
+-- Add up migration script here
+
+-- Seed payment_status table, new bookings start as unpaid
+
+insert into "payment_status" (id, payment_status_name) values
+  (1, 'paid'),
+  (2, 'partial'),
+  (3, 'unpaid')
+on conflict (id) do nothing;
+
+select setval(pg_get_serial_sequence('payment_status', 'id'), (select max(id) from "payment_status"));
+
+-- Create folio_line table
+
+create table if not exists "folio_line" (
+  id serial primary key not null,
+  booking_id int not null,
+  line_type varchar(20) not null,
+  description varchar(255) not null,
+  quantity int not null default 1,
+  unit_price numeric(10,2) not null,
+  tax_amount numeric(10,2) not null default 0,
+  posted_by int,
+  voided_at timestamptz,
+  voided_by int,
+  void_reason varchar(255),
+  created_at timestamptz default now(),
+  updated_at timestamptz default now(),
+  check (line_type in ('room', 'minibar', 'restaurant', 'spa', 'other')),
+  check (quantity > 0),
+  foreign key (booking_id) references booking (id) on delete cascade,
+  foreign key (posted_by) references guest (id),
+  foreign key (voided_by) references guest (id)
+);
+
+create index if not exists folio_line_booking_id_idx on "folio_line" (booking_id);
+
+-- Create payment table
+
+create table if not exists "payment" (
+  id serial primary key not null,
+  booking_id int not null,
+  amount numeric(10,2) not null,
+  method varchar(20) not null,
+  reference varchar(100),
+  recorded_by int,
+  created_at timestamptz default now(),
+  updated_at timestamptz default now(),
+  check (amount > 0),
+  foreign key (booking_id) references booking (id) on delete cascade,
+  foreign key (recorded_by) references guest (id)
+);
+
+create index if not exists payment_booking_id_idx on "payment" (booking_id);
@@ -0,0 +1,17 @@
+-- Add down migration script here
+
+-- Delete invoice_line table
+
+drop table if exists "invoice_line" cascade;
+
+-- Delete invoice table
+
+drop table if exists "invoice" cascade;
+
+-- Delete invoice_number_sequence table
+
+drop table if exists "invoice_number_sequence" cascade;
+
+-- Delete property table
+
+drop table if exists "property" cascade;
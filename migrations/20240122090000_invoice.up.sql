@@ -0,0 +1,75 @@
+-- Add up migration script here
+
+-- Create property table, holds the hotel details printed on invoices
+
+create table if not exists "property" (
+  id serial primary key not null,
+  name varchar(100) not null,
+  address varchar(255) not null,
+  email_address varchar(100) not null,
+  phone_number varchar(20) not null,
+  tax_id varchar(50),
+  created_at timestamptz default now(),
+  updated_at timestamptz default now()
+);
+
+insert into "property" (id, name, address, email_address, phone_number) values
+  (1, 'Local Hotel', '1 Main Street', 'frontdesk@localhotel.com', '+000000000')
+on conflict (id) do nothing;
+
+select setval(pg_get_serial_sequence('property', 'id'), (select max(id) from "property"));
+
+-- Create invoice_number_sequence table, a single row bumped inside the invoice transaction
+-- so a rolled back invoice never leaves a gap in the numbers
+
+create table if not exists "invoice_number_sequence" (
+  id int primary key not null default 1,
+  last_number int not null default 0,
+  check (id = 1)
+);
+
+insert into "invoice_number_sequence" (id, last_number) values (1, 0)
+on conflict (id) do nothing;
+
+-- Create invoice table
+
+create table if not exists "invoice" (
+  id serial primary key not null,
+  invoice_number varchar(20) not null unique,
+  booking_id int not null,
+  guest_id int not null,
+  property_id int not null,
+  bill_to_name varchar(201) not null,
+  bill_to_email varchar(100) not null,
+  bill_to_phone varchar(20) not null,
+  net_total numeric(10,2) not null,
+  tax_total numeric(10,2) not null,
+  total numeric(10,2) not null,
+  payments_total numeric(10,2) not null,
+  balance numeric(10,2) not null,
+  issued_at timestamptz not null default now(),
+  created_at timestamptz default now(),
+  updated_at timestamptz default now(),
+  foreign key (booking_id) references booking (id),
+  foreign key (guest_id) references guest (id),
+  foreign key (property_id) references property (id)
+);
+
+create index if not exists invoice_booking_id_idx on "invoice" (booking_id);
+
+-- Create invoice_line table, a copy of the folio lines at the time the invoice was issued
+
+create table if not exists "invoice_line" (
+  id serial primary key not null,
+  invoice_id int not null,
+  line_type varchar(20) not null,
+  description varchar(255) not null,
+  quantity int not null,
+  unit_price numeric(10,2) not null,
+  net_amount numeric(10,2) not null,
+  tax_amount numeric(10,2) not null,
+  total numeric(10,2) not null,
+  foreign key (invoice_id) references invoice (id) on delete cascade
+);
+
+create index if not exists invoice_line_invoice_id_idx on "invoice_line" (invoice_id);
@@ -0,0 +1,21 @@
+-- Add down migration script here
+
+-- Remove taxable amount from invoice_line table
+
+alter table "invoice_line" drop column if exists taxable_amount;
+
+-- Remove tax lines from folio_line table
+
+delete from "folio_line" where line_type = 'tax';
+
+alter table "folio_line"
+  drop column if exists tax_rule_id,
+  drop column if exists parent_line_id;
+
+alter table "folio_line" drop constraint if exists folio_line_line_type_check;
+alter table "folio_line" add constraint folio_line_line_type_check
+  check (line_type in ('room', 'minibar', 'restaurant', 'spa', 'other'));
+
+-- Delete tax_rule table
+
+drop table if exists "tax_rule" cascade;
@@ -0,0 +1,34 @@
+-- Add up migration script here
+
+-- Create tax_rule table
+-- kind 'percentage': rate is a percentage of the net amount of the charge
+-- kind 'per_person_per_night': rate is a fixed amount per guest and night, room charges only
+
+create table if not exists "tax_rule" (
+  id serial primary key not null,
+  name varchar(100) not null,
+  kind varchar(30) not null,
+  rate numeric(10,4) not null,
+  line_types varchar(20)[] not null default '{room}',
+  exempt_children boolean not null default false,
+  active boolean not null default true,
+  created_at timestamptz default now(),
+  updated_at timestamptz default now(),
+  check (kind in ('percentage', 'per_person_per_night')),
+  check (rate >= 0)
+);
+
+-- Tax lines are stored next to the charge they were computed for
+
+alter table "folio_line" drop constraint if exists folio_line_line_type_check;
+alter table "folio_line" add constraint folio_line_line_type_check
+  check (line_type in ('room', 'minibar', 'restaurant', 'spa', 'other', 'tax'));
+
+alter table "folio_line"
+  add column if not exists parent_line_id int references folio_line (id) on delete cascade,
+  add column if not exists tax_rule_id int references tax_rule (id);
+
+-- Keep the taxable amount of tax lines on invoices
+
+alter table "invoice_line"
+  add column if not exists taxable_amount numeric(10,2);
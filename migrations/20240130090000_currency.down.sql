@@ -0,0 +1,10 @@
+-- Add down migration script here
+
+drop table if exists "exchange_rate";
+
+alter table "guest" drop column if exists preferred_currency;
+alter table "invoice" drop column if exists currency;
+alter table "payment" drop column if exists currency;
+alter table "folio_line" drop column if exists currency;
+alter table "booking" drop column if exists currency;
+alter table "property" drop column if exists base_currency;
@@ -0,0 +1,38 @@
+-- Add up migration script here
+
+-- Amounts are stored in the base currency of the property, labelled with ISO 4217 codes
+
+alter table "property"
+  add column if not exists base_currency varchar(3) not null default 'USD';
+
+alter table "booking"
+  add column if not exists currency varchar(3) not null default 'USD';
+
+alter table "folio_line"
+  add column if not exists currency varchar(3) not null default 'USD';
+
+alter table "payment"
+  add column if not exists currency varchar(3) not null default 'USD';
+
+alter table "invoice"
+  add column if not exists currency varchar(3) not null default 'USD';
+
+alter table "guest"
+  add column if not exists preferred_currency varchar(3);
+
+-- Create exchange_rate table
+-- rate is the amount of currency one unit of base_currency buys
+
+create table if not exists "exchange_rate" (
+  id serial primary key not null,
+  base_currency varchar(3) not null,
+  currency varchar(3) not null,
+  rate numeric(18,8) not null,
+  updated_by int,
+  created_at timestamptz default now(),
+  updated_at timestamptz default now(),
+  unique (base_currency, currency),
+  check (rate > 0),
+  check (base_currency <> currency),
+  foreign key (updated_by) references guest (id)
+);
@@ -0,0 +1,10 @@
+-- Add down migration script here
+
+drop table if exists "deposit_request";
+
+alter table "booking"
+  drop constraint if exists booking_status_check,
+  drop column if exists status,
+  drop column if exists rate_plan_id;
+
+drop table if exists "rate_plan";
@@ -0,0 +1,55 @@
+-- Add up migration script here
+
+-- Create rate_plan table
+-- deposit_type 'fixed': deposit_value is an amount, 'percentage': a percentage of the stay total
+
+create table if not exists "rate_plan" (
+  id serial primary key not null,
+  code varchar(30) not null unique,
+  name varchar(100) not null,
+  deposit_type varchar(20) not null default 'none',
+  deposit_value numeric(10,2) not null default 0,
+  deposit_due_hours int not null default 48,
+  active boolean not null default true,
+  created_at timestamptz default now(),
+  updated_at timestamptz default now(),
+  check (deposit_type in ('none', 'fixed', 'percentage')),
+  check (deposit_value >= 0),
+  check (deposit_type <> 'percentage' or deposit_value <= 100),
+  check (deposit_due_hours > 0)
+);
+
+insert into "rate_plan" (id, code, name, deposit_type, deposit_value) values
+  (1, 'flexible', 'Flexible, pay at the hotel', 'none', 0),
+  (2, 'advance', 'Advance purchase, 30% deposit', 'percentage', 30)
+on conflict (id) do nothing;
+
+select setval(pg_get_serial_sequence('rate_plan', 'id'), (select max(id) from "rate_plan"));
+
+-- Bookings waiting for their deposit are pending, and expire when it isn't paid in time
+
+alter table "booking"
+  add column if not exists rate_plan_id int not null default 1 references rate_plan (id),
+  add column if not exists status varchar(20) not null default 'confirmed',
+  add constraint booking_status_check check (status in ('pending', 'confirmed', 'expired'));
+
+-- Create deposit_request table, one per booking whose rate plan requires a deposit
+
+create table if not exists "deposit_request" (
+  id serial primary key not null,
+  booking_id int not null unique,
+  amount numeric(10,2) not null,
+  currency varchar(3) not null,
+  stay_total numeric(10,2) not null,
+  due_at timestamptz not null,
+  status varchar(20) not null default 'requested',
+  paid_at timestamptz,
+  created_at timestamptz default now(),
+  updated_at timestamptz default now(),
+  check (status in ('requested', 'paid', 'expired')),
+  check (amount > 0),
+  foreign key (booking_id) references booking (id) on delete cascade
+);
+
+create index if not exists deposit_request_due_at_idx on "deposit_request" (due_at)
+  where status = 'requested';
@@ -0,0 +1,5 @@
+-- Add down migration script here
+
+alter table "booking" drop column if exists room_type_id;
+
+drop table if exists "room_type";
@@ -0,0 +1,30 @@
+-- Add up migration script here
+
+-- Create room_type table, base_rate is the price of one night in the property currency
+
+create table if not exists "room_type" (
+  id serial primary key not null,
+  code varchar(30) not null unique,
+  name varchar(100) not null,
+  description varchar(255),
+  base_rate numeric(10,2) not null,
+  max_occupancy int not null,
+  active boolean not null default true,
+  created_at timestamptz default now(),
+  updated_at timestamptz default now(),
+  check (base_rate >= 0),
+  check (max_occupancy > 0)
+);
+
+insert into "room_type" (id, code, name, base_rate, max_occupancy) values
+  (1, 'standard', 'Standard room', 100, 2),
+  (2, 'deluxe', 'Deluxe room', 150, 3),
+  (3, 'suite', 'Suite', 250, 4)
+on conflict (id) do nothing;
+
+select setval(pg_get_serial_sequence('room_type', 'id'), (select max(id) from "room_type"));
+
+-- Bookings made before room types existed don't have one
+
+alter table "booking"
+  add column if not exists room_type_id int references room_type (id);
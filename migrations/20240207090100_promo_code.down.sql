@@ -0,0 +1,6 @@
+-- Add down migration script here
+
+alter table "booking" drop column if exists discount_amount;
+
+drop table if exists "promo_redemption";
+drop table if exists "promo_code";
@@ -0,0 +1,49 @@
+-- Add up migration script here
+
+-- Create promo_code table
+-- discount_type 'percentage': discount_value is a percentage of the room amount, 'fixed': an amount
+-- an empty room_type_ids applies the code to every room type
+
+create table if not exists "promo_code" (
+  id serial primary key not null,
+  code varchar(30) not null unique,
+  description varchar(255),
+  discount_type varchar(20) not null,
+  discount_value numeric(10,2) not null,
+  valid_from timestamptz,
+  valid_until timestamptz,
+  min_nights int not null default 1,
+  max_uses int,
+  max_uses_per_guest int,
+  room_type_ids int[] not null default '{}',
+  active boolean not null default true,
+  created_at timestamptz default now(),
+  updated_at timestamptz default now(),
+  check (discount_type in ('percentage', 'fixed')),
+  check (discount_value > 0),
+  check (discount_type <> 'percentage' or discount_value <= 100),
+  check (min_nights > 0),
+  check (max_uses is null or max_uses > 0),
+  check (max_uses_per_guest is null or max_uses_per_guest > 0)
+);
+
+-- Create promo_redemption table, one per booking the code was used for
+
+create table if not exists "promo_redemption" (
+  id serial primary key not null,
+  promo_code_id int not null,
+  booking_id int not null unique,
+  guest_id int not null,
+  discount_amount numeric(10,2) not null,
+  created_at timestamptz default now(),
+  foreign key (promo_code_id) references promo_code (id),
+  foreign key (booking_id) references booking (id) on delete cascade,
+  foreign key (guest_id) references guest (id)
+);
+
+create index if not exists promo_redemption_promo_code_id_idx on "promo_redemption" (promo_code_id);
+
+-- Keep the price before the discount on the booking
+
+alter table "booking"
+  add column if not exists discount_amount numeric(10,2) not null default 0;
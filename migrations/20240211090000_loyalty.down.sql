@@ -0,0 +1,11 @@
+-- Add down migration script here
+
+drop table if exists "loyalty_ledger";
+
+update "booking" set status = 'confirmed' where status in ('checked_in', 'checked_out');
+
+alter table "booking" drop constraint if exists booking_status_check;
+alter table "booking"
+  add constraint booking_status_check check (status in ('pending', 'confirmed', 'expired')),
+  drop column if exists checked_in_at,
+  drop column if exists checked_out_at;
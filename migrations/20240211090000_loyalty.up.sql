@@ -0,0 +1,32 @@
+-- Add up migration script here
+
+-- Bookings are checked in and out by the front desk, a checked out booking is a completed stay
+
+alter table "booking" drop constraint if exists booking_status_check;
+alter table "booking"
+  add constraint booking_status_check
+    check (status in ('pending', 'confirmed', 'expired', 'checked_in', 'checked_out')),
+  add column if not exists checked_in_at timestamptz,
+  add column if not exists checked_out_at timestamptz;
+
+-- Create loyalty_ledger table, the points balance of a guest is the sum of their entries
+-- entry_type 'earn': points for a completed stay, 'redeem': points spent on a booking,
+-- 'reversal': redeemed points given back when the booking is cancelled
+
+create table if not exists "loyalty_ledger" (
+  id serial primary key not null,
+  guest_id int not null,
+  booking_id int,
+  entry_type varchar(20) not null,
+  points int not null,
+  description varchar(255) not null,
+  created_at timestamptz default now(),
+  check (entry_type in ('earn', 'redeem', 'reversal')),
+  check (points <> 0),
+  foreign key (guest_id) references guest (id),
+  foreign key (booking_id) references booking (id) on delete set null
+);
+
+create index if not exists loyalty_ledger_guest_id_idx on "loyalty_ledger" (guest_id);
+create unique index if not exists loyalty_ledger_earn_booking_idx on "loyalty_ledger" (booking_id)
+  where entry_type = 'earn';
@@ -0,0 +1,4 @@
+-- Add down migration script here
+
+drop table if exists "gift_voucher_transaction";
+drop table if exists "gift_voucher";
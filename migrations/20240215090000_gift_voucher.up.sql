@@ -0,0 +1,48 @@
+-- Add up migration script here
+
+-- Create gift_voucher table, the balance is what is left to spend
+
+create table if not exists "gift_voucher" (
+  id serial primary key not null,
+  code varchar(30) not null unique,
+  initial_amount numeric(10,2) not null,
+  balance numeric(10,2) not null,
+  currency varchar(3) not null,
+  purchased_by int,
+  recipient_name varchar(201),
+  recipient_email varchar(100),
+  message varchar(500),
+  expires_at timestamptz not null,
+  created_at timestamptz default now(),
+  updated_at timestamptz default now(),
+  check (initial_amount > 0),
+  check (balance >= 0 and balance <= initial_amount),
+  foreign key (purchased_by) references guest (id)
+);
+
+-- Create gift_voucher_transaction table, the ledger of every change to a voucher balance
+-- transaction_type 'purchase': the voucher was sold, 'redemption': spent on a folio,
+-- 'refund': a redemption given back when its booking was cancelled
+
+create table if not exists "gift_voucher_transaction" (
+  id serial primary key not null,
+  gift_voucher_id int not null,
+  transaction_type varchar(20) not null,
+  amount numeric(10,2) not null,
+  booking_id int,
+  payment_id int,
+  method varchar(20),
+  performed_by int,
+  created_at timestamptz default now(),
+  check (transaction_type in ('purchase', 'redemption', 'refund')),
+  check (amount <> 0),
+  foreign key (gift_voucher_id) references gift_voucher (id),
+  foreign key (booking_id) references booking (id) on delete set null,
+  foreign key (payment_id) references payment (id) on delete set null,
+  foreign key (performed_by) references guest (id)
+);
+
+create index if not exists gift_voucher_transaction_voucher_id_idx
+  on "gift_voucher_transaction" (gift_voucher_id);
+create index if not exists gift_voucher_transaction_booking_id_idx
+  on "gift_voucher_transaction" (booking_id);
@@ -0,0 +1,5 @@
+-- Add down migration script here
+
+alter table "booking" drop column if exists reservation_group_id;
+
+drop table if exists "reservation_group";
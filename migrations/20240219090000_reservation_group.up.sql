@@ -0,0 +1,29 @@
+-- Add up migration script here
+
+-- Create reservation_group table, several room bookings made together under a lead guest
+-- billing_type 'shared': the lead guest pays for every room, 'split': each guest pays their own
+-- rooms_held is the size of the block, anyone with the code can book in it until it is full
+
+create table if not exists "reservation_group" (
+  id serial primary key not null,
+  code varchar(20) not null unique,
+  name varchar(100) not null,
+  lead_guest_id int not null,
+  billing_type varchar(10) not null default 'split',
+  room_type_id int,
+  checkin_date date not null,
+  checkout_date date not null,
+  rooms_held int not null,
+  created_at timestamptz default now(),
+  updated_at timestamptz default now(),
+  check (billing_type in ('shared', 'split')),
+  check (checkout_date > checkin_date),
+  check (rooms_held > 0),
+  foreign key (lead_guest_id) references guest (id),
+  foreign key (room_type_id) references room_type (id)
+);
+
+alter table "booking"
+  add column if not exists reservation_group_id int references reservation_group (id) on delete set null;
+
+create index if not exists booking_reservation_group_id_idx on "booking" (reservation_group_id);
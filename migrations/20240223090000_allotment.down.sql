@@ -0,0 +1,8 @@
+-- Add down migration script here
+
+drop index if exists booking_room_type_stay_idx;
+
+alter table "booking" drop column if exists allotment_block_id;
+
+drop table if exists "allotment_block";
+drop table if exists "room";
@@ -0,0 +1,50 @@
+-- Add up migration script here
+
+-- Create room table, the rooms of a room type are what can be sold of it each night
+
+create table if not exists "room" (
+  id serial primary key not null,
+  room_number varchar(10) not null unique,
+  room_type_id int not null,
+  floor int not null,
+  active boolean not null default true,
+  created_at timestamptz default now(),
+  updated_at timestamptz default now(),
+  foreign key (room_type_id) references room_type (id)
+);
+
+insert into "room" (room_number, room_type_id, floor) values
+  ('101', 1, 1), ('102', 1, 1), ('103', 1, 1), ('104', 1, 1), ('105', 1, 1),
+  ('201', 1, 2), ('202', 1, 2), ('203', 2, 2), ('204', 2, 2), ('205', 2, 2),
+  ('301', 2, 3), ('302', 3, 3), ('303', 3, 3)
+on conflict (room_number) do nothing;
+
+-- Create allotment_block table, rooms held for a corporate or event group
+-- The stay runs from start_date to end_date (the check-out of the last night)
+-- Rooms not picked up by the cutoff date are released back to general availability
+
+create table if not exists "allotment_block" (
+  id serial primary key not null,
+  code varchar(30) not null unique,
+  name varchar(100) not null,
+  room_type_id int not null,
+  start_date date not null,
+  end_date date not null,
+  rooms int not null,
+  cutoff_date date not null,
+  released_at timestamptz,
+  created_by int,
+  created_at timestamptz default now(),
+  updated_at timestamptz default now(),
+  check (end_date > start_date),
+  check (rooms > 0),
+  check (cutoff_date <= start_date),
+  foreign key (room_type_id) references room_type (id),
+  foreign key (created_by) references guest (id)
+);
+
+alter table "booking"
+  add column if not exists allotment_block_id int references allotment_block (id);
+
+create index if not exists booking_allotment_block_id_idx on "booking" (allotment_block_id);
+create index if not exists booking_room_type_stay_idx on "booking" (room_type_id, checkin_date, checkout_date);
@@ -0,0 +1,11 @@
+-- Add down migration script here
+
+drop table if exists "housekeeping_task";
+drop table if exists "room_status_change";
+
+alter table "booking" drop column if exists room_id;
+
+alter table "room"
+  drop constraint if exists room_housekeeping_status_check,
+  drop column if exists housekeeping_status,
+  drop column if exists status_updated_at;
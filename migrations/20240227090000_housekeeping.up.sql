@@ -0,0 +1,62 @@
+-- Add up migration script here
+
+-- Housekeeping status of every room, shown on the room status board
+
+alter table "room"
+  add column if not exists housekeeping_status varchar(20) not null default 'clean',
+  add column if not exists status_updated_at timestamptz default now(),
+  add constraint room_housekeeping_status_check
+    check (housekeeping_status in ('clean', 'dirty', 'inspected', 'out_of_order', 'out_of_service'));
+
+-- Room the guest stays in, set at check-in
+
+alter table "booking"
+  add column if not exists room_id int references room (id);
+
+create index if not exists booking_room_id_idx on "booking" (room_id);
+
+-- Create room_status_change table, the history of the housekeeping status of each room
+
+create table if not exists "room_status_change" (
+  id serial primary key not null,
+  room_id int not null,
+  from_status varchar(20) not null,
+  to_status varchar(20) not null,
+  booking_id int,
+  reason varchar(255),
+  changed_by int,
+  created_at timestamptz default now(),
+  foreign key (room_id) references room (id),
+  foreign key (booking_id) references booking (id) on delete set null,
+  foreign key (changed_by) references guest (id)
+);
+
+create index if not exists room_status_change_room_id_idx on "room_status_change" (room_id);
+
+-- Create housekeeping_task table, work given to the housekeeping staff
+-- task_type 'clean': make a dirty room clean, 'inspect': check a cleaned room before it is sold
+
+create table if not exists "housekeeping_task" (
+  id serial primary key not null,
+  room_id int not null,
+  task_type varchar(20) not null,
+  status varchar(20) not null default 'open',
+  assigned_to int,
+  booking_id int,
+  notes varchar(500),
+  created_by int,
+  completed_by int,
+  completed_at timestamptz,
+  created_at timestamptz default now(),
+  updated_at timestamptz default now(),
+  check (task_type in ('clean', 'inspect')),
+  check (status in ('open', 'done')),
+  foreign key (room_id) references room (id),
+  foreign key (assigned_to) references guest (id),
+  foreign key (booking_id) references booking (id) on delete set null,
+  foreign key (created_by) references guest (id),
+  foreign key (completed_by) references guest (id)
+);
+
+create index if not exists housekeeping_task_room_id_idx on "housekeeping_task" (room_id);
+create index if not exists housekeeping_task_assigned_to_idx on "housekeeping_task" (assigned_to) where status = 'open';
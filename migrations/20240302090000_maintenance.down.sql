@@ -0,0 +1,6 @@
+-- Add down migration script here
+
+alter table "booking" drop column if exists maintenance_ticket_id;
+
+drop table if exists "maintenance_attachment";
+drop table if exists "maintenance_ticket";
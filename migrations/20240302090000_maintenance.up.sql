@@ -0,0 +1,54 @@
+-- Add up migration script here
+
+-- Create maintenance_ticket table, work orders on a room
+-- Out of order tickets take the room out of inventory from out_of_order_from until the day before out_of_order_until
+
+create table if not exists "maintenance_ticket" (
+  id serial primary key not null,
+  room_id int not null,
+  title varchar(100) not null,
+  description varchar(2000),
+  priority varchar(10) not null default 'normal',
+  status varchar(20) not null default 'open',
+  assigned_to int,
+  reported_by int,
+  out_of_order boolean not null default false,
+  out_of_order_from date,
+  out_of_order_until date,
+  resolved_at timestamptz,
+  created_at timestamptz default now(),
+  updated_at timestamptz default now(),
+  check (priority in ('low', 'normal', 'high', 'urgent')),
+  check (status in ('open', 'in_progress', 'resolved', 'cancelled')),
+  check (
+    not out_of_order
+    or (out_of_order_from is not null and out_of_order_until is not null and out_of_order_until > out_of_order_from)
+  ),
+  foreign key (room_id) references room (id),
+  foreign key (assigned_to) references guest (id),
+  foreign key (reported_by) references guest (id)
+);
+
+create index if not exists maintenance_ticket_room_id_idx on "maintenance_ticket" (room_id);
+
+-- Create maintenance_attachment table, photos and documents of a ticket
+
+create table if not exists "maintenance_attachment" (
+  id serial primary key not null,
+  maintenance_ticket_id int not null,
+  file_name varchar(255) not null,
+  content_type varchar(100) not null,
+  size_bytes int not null,
+  data bytea not null,
+  uploaded_by int,
+  created_at timestamptz default now(),
+  foreign key (maintenance_ticket_id) references maintenance_ticket (id) on delete cascade,
+  foreign key (uploaded_by) references guest (id)
+);
+
+create index if not exists maintenance_attachment_ticket_id_idx on "maintenance_attachment" (maintenance_ticket_id);
+
+-- Bookings whose room was taken out of order, they need another room
+
+alter table "booking"
+  add column if not exists maintenance_ticket_id int references maintenance_ticket (id) on delete set null;
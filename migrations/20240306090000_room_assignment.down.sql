@@ -0,0 +1,12 @@
+-- Add down migration script here
+
+drop index if exists booking_room_id_stay_idx;
+
+alter table "booking"
+  drop column if exists preferred_floor,
+  drop column if exists accessible_room,
+  drop column if exists near_elevator;
+
+alter table "room"
+  drop column if exists accessible,
+  drop column if exists near_elevator;
@@ -0,0 +1,19 @@
+-- Add up migration script here
+
+-- Room features the front desk matches against the guest preferences
+
+alter table "room"
+  add column if not exists accessible boolean not null default false,
+  add column if not exists near_elevator boolean not null default false;
+
+update "room" set accessible = true where room_number in ('101', '201');
+update "room" set near_elevator = true where room_number in ('101', '102', '201', '202', '301', '302');
+
+-- Room preferences of the guest for the stay
+
+alter table "booking"
+  add column if not exists preferred_floor int,
+  add column if not exists accessible_room boolean not null default false,
+  add column if not exists near_elevator boolean not null default false;
+
+create index if not exists booking_room_id_stay_idx on "booking" (room_id, checkin_date, checkout_date);
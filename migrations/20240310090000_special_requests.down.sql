@@ -0,0 +1,6 @@
+-- Add down migration script here
+
+drop index if exists booking_checkout_date_idx;
+drop index if exists booking_checkin_date_idx;
+
+alter table "booking" drop column if exists special_requests;
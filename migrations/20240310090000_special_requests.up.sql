@@ -0,0 +1,8 @@
+-- Add up migration script here
+
+-- Requests of the guest for the stay, shown on the front desk lists
+
+alter table "booking" add column if not exists special_requests varchar(1000);
+
+create index if not exists booking_checkin_date_idx on "booking" (checkin_date);
+create index if not exists booking_checkout_date_idx on "booking" (checkout_date);
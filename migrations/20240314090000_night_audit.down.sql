@@ -0,0 +1,18 @@
+-- Add down migration script here
+
+drop table if exists "night_audit";
+
+update "booking" set status = 'expired' where status = 'no_show';
+
+alter table "booking" drop constraint if exists booking_status_check;
+
+alter table "booking"
+  add constraint booking_status_check
+    check (status in ('pending', 'confirmed', 'expired', 'checked_in', 'checked_out'));
+
+drop index if exists folio_line_room_night_idx;
+drop index if exists folio_line_business_date_idx;
+
+alter table "folio_line" drop column if exists business_date;
+
+drop table if exists "business_date";
@@ -0,0 +1,55 @@
+-- Add up migration script here
+
+-- Create business_date table, the day the books are open for, a single row moved forward by the night audit
+
+create table if not exists "business_date" (
+  id boolean primary key not null default true,
+  business_date date not null,
+  updated_at timestamptz default now(),
+  check (id)
+);
+
+insert into "business_date" (id, business_date) values (true, current_date)
+on conflict (id) do nothing;
+
+-- Lines belong to the business date they were posted on, room charges to the night they are for
+
+alter table "folio_line" add column if not exists business_date date;
+
+update "folio_line" set business_date = created_at::date where business_date is null;
+
+create index if not exists folio_line_business_date_idx on "folio_line" (business_date);
+
+-- One room charge per booking and night
+
+create unique index if not exists folio_line_room_night_idx on "folio_line" (booking_id, business_date)
+  where line_type = 'room' and posted_by is null and voided_at is null;
+
+-- Arrivals that never came are marked no_show by the night audit
+
+alter table "booking" drop constraint if exists booking_status_check;
+
+alter table "booking"
+  add constraint booking_status_check
+    check (status in ('pending', 'confirmed', 'expired', 'checked_in', 'checked_out', 'no_show'));
+
+-- Create night_audit table, the occupancy and revenue of every business date closed
+
+create table if not exists "night_audit" (
+  id serial primary key not null,
+  business_date date not null unique,
+  rooms_total int not null,
+  rooms_out_of_order int not null,
+  rooms_occupied int not null,
+  arrivals int not null,
+  departures int not null,
+  no_shows int not null,
+  room_charges_posted int not null,
+  room_revenue numeric(12, 2) not null,
+  other_revenue numeric(12, 2) not null,
+  tax_revenue numeric(12, 2) not null,
+  currency varchar(3) not null,
+  run_by int,
+  created_at timestamptz default now(),
+  foreign key (run_by) references guest (id)
+);
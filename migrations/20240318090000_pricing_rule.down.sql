@@ -0,0 +1,9 @@
+-- Add down migration script here
+
+-- Delete price_adjustment table
+
+drop table if exists "price_adjustment";
+
+-- Delete pricing_rule table
+
+drop table if exists "pricing_rule";
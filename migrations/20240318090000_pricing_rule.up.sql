@@ -0,0 +1,50 @@
+-- Add up migration script here
+
+-- Create pricing_rule table, moving the nightly rate of a room type with the occupancy of the night
+-- A rule applies when occupancy is above occupancy_above and/or below occupancy_below, in percent,
+-- and, with max_days_before_arrival, only for stays starting within that many days of the business date
+-- adjustment_percent raises (positive) or discounts (negative) the rate, never past ceiling_price or floor_price
+-- Matching rules apply one after the other, by priority then id
+
+create table if not exists "pricing_rule" (
+  id serial primary key not null,
+  name varchar(100) not null,
+  room_type_id int,
+  occupancy_above numeric(5,2),
+  occupancy_below numeric(5,2),
+  max_days_before_arrival int,
+  adjustment_percent numeric(6,2) not null,
+  floor_price numeric(10,2),
+  ceiling_price numeric(10,2),
+  priority int not null default 0,
+  active boolean not null default true,
+  created_at timestamptz default now(),
+  updated_at timestamptz default now(),
+  foreign key (room_type_id) references room_type (id),
+  check (occupancy_above is null or (occupancy_above >= 0 and occupancy_above <= 100)),
+  check (occupancy_below is null or (occupancy_below >= 0 and occupancy_below <= 100)),
+  check (max_days_before_arrival is null or max_days_before_arrival >= 0),
+  check (adjustment_percent > -100),
+  check (floor_price is null or floor_price >= 0),
+  check (ceiling_price is null or floor_price is null or ceiling_price >= floor_price)
+);
+
+-- Create price_adjustment table, the rules that changed the rate of every night of a booking
+
+create table if not exists "price_adjustment" (
+  id serial primary key not null,
+  booking_id int not null,
+  pricing_rule_id int not null,
+  room_type_id int not null,
+  night date not null,
+  occupancy_percent numeric(5,2) not null,
+  rate_before numeric(10,2) not null,
+  rate_after numeric(10,2) not null,
+  created_at timestamptz default now(),
+  foreign key (booking_id) references booking (id) on delete cascade,
+  foreign key (pricing_rule_id) references pricing_rule (id),
+  foreign key (room_type_id) references room_type (id)
+);
+
+create index if not exists price_adjustment_booking_id_idx on "price_adjustment" (booking_id);
+create index if not exists price_adjustment_night_idx on "price_adjustment" (night);
@@ -0,0 +1,5 @@
+-- Add down migration script here
+
+-- Delete stay_restriction table
+
+drop table if exists "stay_restriction";
@@ -0,0 +1,32 @@
+-- Add up migration script here
+
+-- Create stay_restriction table, the rules revenue management sets on the dates from start_date to end_date, both included
+-- Without a room type the restriction applies to every room type
+-- min_stay and max_stay: length of stays arriving on one of the dates, in nights
+-- closed_to_arrival: no stay can start on one of the dates
+-- closed_to_departure: no stay can end on one of the dates
+-- stop_sell: no stay can include one of the dates as a night
+
+create table if not exists "stay_restriction" (
+  id serial primary key not null,
+  room_type_id int,
+  start_date date not null,
+  end_date date not null,
+  min_stay int,
+  max_stay int,
+  closed_to_arrival boolean not null default false,
+  closed_to_departure boolean not null default false,
+  stop_sell boolean not null default false,
+  reason varchar(255),
+  created_by int,
+  created_at timestamptz default now(),
+  updated_at timestamptz default now(),
+  foreign key (room_type_id) references room_type (id),
+  foreign key (created_by) references guest (id),
+  check (end_date >= start_date),
+  check (min_stay is null or min_stay >= 1),
+  check (max_stay is null or max_stay >= 1),
+  check (min_stay is null or max_stay is null or max_stay >= min_stay)
+);
+
+create index if not exists stay_restriction_dates_idx on "stay_restriction" (start_date, end_date);
@@ -0,0 +1,9 @@
+-- Add down migration script here
+
+-- Delete notification table
+
+drop table if exists "notification";
+
+-- Delete waitlist_entry table
+
+drop table if exists "waitlist_entry";
@@ -0,0 +1,49 @@
+-- Add up migration script here
+
+-- Create waitlist_entry table, guests waiting for a room type sold out on their dates
+-- Entries are offered a freed room in the order they joined, the room is then held
+-- for the guest until hold_expires_at, out of availability for everyone else
+
+create table if not exists "waitlist_entry" (
+  id serial primary key not null,
+  guest_id int not null,
+  room_type_id int not null,
+  checkin_date date not null,
+  checkout_date date not null,
+  num_adults int not null default 1,
+  num_children int not null default 0,
+  status varchar(20) not null default 'waiting',
+  offered_at timestamptz,
+  hold_expires_at timestamptz,
+  booking_id int,
+  created_at timestamptz default now(),
+  updated_at timestamptz default now(),
+  foreign key (guest_id) references guest (id),
+  foreign key (room_type_id) references room_type (id),
+  foreign key (booking_id) references booking (id) on delete set null,
+  check (status in ('waiting', 'offered', 'booked', 'expired', 'cancelled')),
+  check (checkout_date > checkin_date)
+);
+
+create index if not exists waitlist_entry_status_idx on "waitlist_entry" (status, room_type_id);
+
+-- A guest waits once for the same room type and dates
+
+create unique index if not exists waitlist_entry_active_idx
+  on "waitlist_entry" (guest_id, room_type_id, checkin_date, checkout_date)
+  where status in ('waiting', 'offered');
+
+-- Create notification table, messages for the guest about their stays
+
+create table if not exists "notification" (
+  id serial primary key not null,
+  guest_id int not null,
+  kind varchar(30) not null,
+  message text not null,
+  waitlist_entry_id int,
+  created_at timestamptz default now(),
+  foreign key (guest_id) references guest (id) on delete cascade,
+  foreign key (waitlist_entry_id) references waitlist_entry (id) on delete cascade
+);
+
+create index if not exists notification_guest_id_idx on "notification" (guest_id);
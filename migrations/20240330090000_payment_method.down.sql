@@ -0,0 +1,10 @@
+-- Add down migration script here
+
+-- Delete payment_method table
+
+drop table if exists "payment_method";
+
+-- Remove the last accepted TOTP time step from guest table
+
+alter table "guest"
+  drop column if exists mfa_last_time_step;
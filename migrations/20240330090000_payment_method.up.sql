@@ -0,0 +1,24 @@
+-- Add up migration script here
+
+-- Remember the last accepted TOTP time step, a code is only accepted once
+
+alter table "guest"
+  add column if not exists mfa_last_time_step bigint;
+
+-- Create payment_method table, cards the guest keeps on file
+-- Only what identifies the card to the guest is kept, the card itself stays with the processor
+
+create table if not exists "payment_method" (
+  id serial primary key not null,
+  guest_id int not null,
+  brand varchar(20) not null,
+  last_four char(4) not null,
+  exp_month int not null,
+  exp_year int not null,
+  created_at timestamptz default now(),
+  foreign key (guest_id) references guest (id) on delete cascade,
+  check (last_four ~ '^[0-9]{4}$'),
+  check (exp_month between 1 and 12)
+);
+
+create index if not exists payment_method_guest_id_idx on "payment_method" (guest_id);
@@ -0,0 +1,6 @@
+-- Add down migration script here
+
+-- Remove the guest counts check from booking table
+
+alter table "booking"
+  drop constraint if exists booking_guests_check;
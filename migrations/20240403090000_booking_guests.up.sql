@@ -0,0 +1,7 @@
+-- Add up migration script here
+
+-- Every booking has at least one adult and no negative number of children,
+-- rows saved before the check are left as they are
+
+alter table "booking"
+  add constraint booking_guests_check check (num_adults >= 1 and num_children >= 0) not valid;
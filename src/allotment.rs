@@ -0,0 +1,130 @@
+use chrono::NaiveDate;
+use sqlx::{PgConnection, Pool, Postgres};
+
+use crate::{
+    availability::block_rooms_remaining,
+    business_date::current_business_date,
+    models::{AllotmentBlock, BOOKING_STATUS_EXPIRED, BOOKING_STATUS_NO_SHOW},
+    response::{BlockNightPickup, BlockPickup},
+};
+
+// Why a room couldn't be picked up from an allotment block
+pub enum BlockError {
+    Rejected(String),
+    Database(sqlx::Error),
+}
+
+impl From<sqlx::Error> for BlockError {
+    fn from(e: sqlx::Error) -> Self {
+        BlockError::Database(e)
+    }
+}
+
+// Take a room from the block, the block row stays locked until the booking is saved
+pub async fn pick_up_from_block(
+    conn: &mut PgConnection,
+    code: &str,
+    checkin_date: NaiveDate,
+    checkout_date: NaiveDate,
+    room_type_id: Option<i32>,
+) -> Result<AllotmentBlock, BlockError> {
+    let block = sqlx::query_as!(
+        AllotmentBlock,
+        "select * from allotment_block where code = $1 for update",
+        code.trim().to_ascii_uppercase()
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| BlockError::Rejected(format!("Block code {} not found", code)))?;
+
+    if block.released_at.is_some() || current_business_date(&mut *conn).await? > block.cutoff_date {
+        return Err(BlockError::Rejected(format!(
+            "Rooms of {} could only be picked up until {}",
+            block.name, block.cutoff_date
+        )));
+    }
+
+    if checkin_date < block.start_date || checkout_date > block.end_date {
+        return Err(BlockError::Rejected(format!(
+            "The rooms of {} are held from {} to {}",
+            block.name, block.start_date, block.end_date
+        )));
+    }
+
+    if room_type_id.is_some_and(|room_type_id| room_type_id != block.room_type_id) {
+        return Err(BlockError::Rejected(format!(
+            "The block of {} only holds room type {}",
+            block.name, block.room_type_id
+        )));
+    }
+
+    if block_rooms_remaining(conn, block.id, checkin_date, checkout_date).await? < 1 {
+        return Err(BlockError::Rejected(format!(
+            "Every room held for {} has been picked up for these dates",
+            block.name
+        )));
+    }
+
+    Ok(block)
+}
+
+// Give the rooms not picked up back to general availability once the cutoff date has passed
+pub async fn release_blocks_past_cutoff(db: &Pool<Postgres>) -> Result<Vec<i32>, sqlx::Error> {
+    sqlx::query_scalar!(
+        "update allotment_block set released_at = now(), updated_at = now()
+        where released_at is null and cutoff_date < (select business_date from business_date)
+        returning id"
+    )
+    .fetch_all(db)
+    .await
+}
+
+// Rooms held and picked up for every night of the block, expired bookings and no-shows gave theirs back
+pub async fn pickup_report(
+    db: &Pool<Postgres>,
+    block: AllotmentBlock,
+) -> Result<BlockPickup, sqlx::Error> {
+    let picked_up = sqlx::query!(
+        "select night::date as \"night!\", count(booking.id) as \"picked_up!\"
+        from generate_series($2::date, $3::date - 1, interval '1 day') as night
+        left join booking on booking.allotment_block_id = $1
+            and booking.status not in ($4, $5)
+            and booking.checkin_date <= night::date
+            and booking.checkout_date > night::date
+        group by night
+        order by night",
+        block.id,
+        block.start_date,
+        block.end_date,
+        BOOKING_STATUS_EXPIRED,
+        BOOKING_STATUS_NO_SHOW
+    )
+    .fetch_all(db)
+    .await?;
+
+    let nights: Vec<BlockNightPickup> = picked_up
+        .into_iter()
+        .map(|night| BlockNightPickup {
+            night: night.night,
+            held: block.rooms,
+            picked_up: night.picked_up,
+            remaining: (i64::from(block.rooms) - night.picked_up).max(0),
+        })
+        .collect();
+
+    let room_nights_held = i64::from(block.rooms) * nights.len() as i64;
+    let room_nights_picked_up: i64 = nights.iter().map(|night| night.picked_up).sum();
+    let pickup_percent = if room_nights_held > 0 {
+        (room_nights_picked_up * 100 / room_nights_held) as i32
+    } else {
+        0
+    };
+
+    Ok(BlockPickup {
+        block,
+        nights,
+        room_nights_held,
+        room_nights_picked_up,
+        pickup_percent,
+    })
+}
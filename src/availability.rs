@@ -0,0 +1,156 @@
+use chrono::NaiveDate;
+use sqlx::PgConnection;
+
+use crate::{
+    maintenance::OPEN_TICKET_STATUSES,
+    models::{BOOKING_STATUS_EXPIRED, BOOKING_STATUS_NO_SHOW},
+    waitlist::WAITLIST_STATUS_OFFERED,
+};
+
+// Rooms of the type that can be sold on a night, and rooms already taken that night
+pub struct NightOccupancy {
+    pub night: NaiveDate,
+    pub sellable: i64,
+    pub sold: i64,
+}
+
+// Occupancy of the room type for every night of the stay
+// Rooms out of order for maintenance can't be sold on the nights they are being worked on
+// Blocks that weren't released keep all their rooms, whether picked up or not
+// Groups of the room type keep the rooms of their block no one has booked yet
+// Expired bookings and no-shows gave their rooms back
+// Rooms offered to the waitlist are held for the guest until the offer expires
+// A booking being moved is left out, so its own nights don't count against it
+pub async fn night_occupancy(
+    conn: &mut PgConnection,
+    room_type_id: i32,
+    checkin_date: NaiveDate,
+    checkout_date: NaiveDate,
+    except_booking_id: Option<i32>,
+) -> Result<Vec<NightOccupancy>, sqlx::Error> {
+    sqlx::query_as!(
+        NightOccupancy,
+        "select
+            night::date as \"night!\",
+            (
+                select count(*) from room
+                where room_type_id = $1
+                    and active = true
+                    and not exists (
+                        select 1 from maintenance_ticket
+                        where maintenance_ticket.room_id = room.id
+                            and maintenance_ticket.out_of_order = true
+                            and maintenance_ticket.status = any($4)
+                            and maintenance_ticket.out_of_order_from <= night::date
+                            and maintenance_ticket.out_of_order_until > night::date
+                    )
+            ) as \"sellable!\",
+            (
+                select count(*) from booking
+                left join allotment_block on allotment_block.id = booking.allotment_block_id
+                where booking.room_type_id = $1
+                    and booking.status not in ($5, $6)
+                    and booking.checkin_date <= night::date
+                    and booking.checkout_date > night::date
+                    and (allotment_block.id is null or allotment_block.released_at is not null)
+                    and ($8::int is null or booking.id <> $8)
+            )
+            + (
+                select coalesce(sum(rooms), 0) from allotment_block
+                where room_type_id = $1
+                    and released_at is null
+                    and start_date <= night::date
+                    and end_date > night::date
+            )
+            + (
+                select coalesce(sum(greatest(reservation_group.rooms_held - (
+                    select count(*) from booking
+                    where booking.reservation_group_id = reservation_group.id
+                        and booking.room_type_id = reservation_group.room_type_id
+                        and booking.status <> $5
+                ), 0)), 0)::bigint
+                from reservation_group
+                where reservation_group.room_type_id = $1
+                    and reservation_group.checkin_date <= night::date
+                    and reservation_group.checkout_date > night::date
+            )
+            + (
+                select count(*) from waitlist_entry
+                where room_type_id = $1
+                    and status = $7
+                    and hold_expires_at > now()
+                    and checkin_date <= night::date
+                    and checkout_date > night::date
+            ) as \"sold!\"
+        from generate_series($2::date, $3::date - 1, interval '1 day') as night
+        order by night",
+        room_type_id,
+        checkin_date,
+        checkout_date,
+        OPEN_TICKET_STATUSES as &[&str],
+        BOOKING_STATUS_EXPIRED,
+        BOOKING_STATUS_NO_SHOW,
+        WAITLIST_STATUS_OFFERED,
+        except_booking_id
+    )
+    .fetch_all(conn)
+    .await
+}
+
+// Rooms left on the fullest night of the stay
+pub fn rooms_left(nights: &[NightOccupancy]) -> i64 {
+    nights
+        .iter()
+        .map(|night| night.sellable - night.sold)
+        .min()
+        .unwrap_or(0)
+        .max(0)
+}
+
+// Rooms of the type that can still be sold for every night of the stay
+pub async fn available_rooms(
+    conn: &mut PgConnection,
+    room_type_id: i32,
+    checkin_date: NaiveDate,
+    checkout_date: NaiveDate,
+) -> Result<i64, sqlx::Error> {
+    let nights = night_occupancy(conn, room_type_id, checkin_date, checkout_date, None).await?;
+
+    Ok(rooms_left(&nights))
+}
+
+// Rooms of the block not picked up yet for every night of the stay
+pub async fn block_rooms_remaining(
+    conn: &mut PgConnection,
+    allotment_block_id: i32,
+    checkin_date: NaiveDate,
+    checkout_date: NaiveDate,
+) -> Result<i64, sqlx::Error> {
+    let picked_up = sqlx::query_scalar!(
+        "select coalesce(max(picked_up), 0) as \"picked_up!\" from (
+            select count(booking.id) as picked_up
+            from generate_series($2::date, $3::date - 1, interval '1 day') as night
+            left join booking on booking.allotment_block_id = $1
+                and booking.status not in ($4, $5)
+                and booking.checkin_date <= night::date
+                and booking.checkout_date > night::date
+            group by night
+        ) as nights",
+        allotment_block_id,
+        checkin_date,
+        checkout_date,
+        BOOKING_STATUS_EXPIRED,
+        BOOKING_STATUS_NO_SHOW
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let rooms = sqlx::query_scalar!(
+        "select rooms from allotment_block where id = $1",
+        allotment_block_id
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok((i64::from(rooms) - picked_up).max(0))
+}
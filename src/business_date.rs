@@ -0,0 +1,15 @@
+use chrono::NaiveDate;
+use sqlx::PgExecutor;
+
+// Day the books are open for, the night audit moves it forward once the day is closed
+//
+// Use it rather than the clock for anything that depends on the hotel day, e.g.
+// who is arriving or departing, a guest checking in past midnight still belongs
+// to the day before until the audit has run.
+pub async fn current_business_date<'e>(
+    executor: impl PgExecutor<'e>,
+) -> Result<NaiveDate, sqlx::Error> {
+    sqlx::query_scalar!("select business_date from business_date")
+        .fetch_one(executor)
+        .await
+}
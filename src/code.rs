@@ -0,0 +1,15 @@
+use rand_core::{OsRng, RngCore};
+
+// Unambiguous characters only, no 0/O or 1/I, so codes can be read out at the front desk
+const CODE_ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+
+// Generate `len` random characters for a code guests read out or type in
+pub fn random_code(len: usize) -> String {
+    let mut bytes = vec![0u8; len];
+    OsRng.fill_bytes(&mut bytes);
+
+    bytes
+        .iter()
+        .map(|byte| CODE_ALPHABET[*byte as usize % CODE_ALPHABET.len()] as char)
+        .collect()
+}
@@ -4,6 +4,41 @@ pub struct Config {
     pub jwt_secret: String,
     pub jwt_expires_in: String,
     pub jwt_maxage: i32,
+    pub log_json: bool,
+    pub rate_limit: RateLimitConfig,
+    // How often bookings with an overdue deposit are expired
+    pub deposit_expiry_interval_secs: u64,
+    // How often allotment blocks past their cutoff date are released
+    pub allotment_release_interval_secs: u64,
+    // How often the business date is checked against the calendar, a day left open is audited
+    pub night_audit_interval_secs: u64,
+    // How often freed rooms are offered to the waitlist, offers not taken up in time are expired
+    pub waitlist_interval_secs: u64,
+    // How long a freed room is held for the guest it was offered to
+    pub waitlist_hold_hours: i64,
+    pub loyalty: LoyaltyConfig,
+    // How long a gift voucher can be spent after it is sold
+    pub gift_voucher_validity_days: i64,
+}
+
+// Points earned per unit of the property currency spent, and needed to take one unit off a booking
+#[derive(Debug, Clone)]
+pub struct LoyaltyConfig {
+    pub earn_points_per_unit: u32,
+    pub redeem_points_per_unit: u32,
+}
+
+// Limits applied to the auth endpoints, every value can be overridden from the env
+#[derive(Debug, Clone)]
+pub struct RateLimitConfig {
+    pub ip_max_requests: u32,
+    pub ip_window_secs: u64,
+    pub email_max_requests: u32,
+    pub email_window_secs: u64,
+    pub login_max_failures: u32,
+    pub login_lockout_secs: u64,
+    // Failures older than this don't count towards the lockout anymore
+    pub login_failure_window_secs: u64,
 }
 
 impl Config {
@@ -14,12 +49,49 @@ impl Config {
         let jwt_secret = std::env::var("JWT_SECRET").expect("JWT_SECRET must be set");
         let jwt_expires_in = std::env::var("JWT_EXPIRED_IN").expect("JWT_EXPIRED_IN must set");
         let jwt_maxage = std::env::var("JWT_MAXAGE").expect("JWT_MAXAGE must be set");
+        // Optional, set `LOG_FORMAT=json` to emit one json object per log line
+        let log_format = std::env::var("LOG_FORMAT").unwrap_or_default();
+        // Points are divided by it to get their value, so it can't be zero
+        let redeem_points_per_unit = env_or("LOYALTY_REDEEM_POINTS_PER_UNIT", 100);
+        if redeem_points_per_unit == 0 {
+            panic!("LOYALTY_REDEEM_POINTS_PER_UNIT must be greater than 0");
+        }
 
         Config {
             database_url,
             jwt_secret,
             jwt_expires_in,
             jwt_maxage: jwt_maxage.parse::<i32>().unwrap(),
+            log_json: log_format.eq_ignore_ascii_case("json"),
+            rate_limit: RateLimitConfig {
+                ip_max_requests: env_or("RATE_LIMIT_IP_MAX_REQUESTS", 20),
+                ip_window_secs: env_or("RATE_LIMIT_IP_WINDOW_SECS", 60),
+                email_max_requests: env_or("RATE_LIMIT_EMAIL_MAX_REQUESTS", 5),
+                email_window_secs: env_or("RATE_LIMIT_EMAIL_WINDOW_SECS", 60),
+                login_max_failures: env_or("LOGIN_MAX_FAILURES", 5),
+                login_lockout_secs: env_or("LOGIN_LOCKOUT_SECS", 900),
+                login_failure_window_secs: env_or("LOGIN_FAILURE_WINDOW_SECS", 900),
+            },
+            deposit_expiry_interval_secs: env_or("DEPOSIT_EXPIRY_INTERVAL_SECS", 60),
+            allotment_release_interval_secs: env_or("ALLOTMENT_RELEASE_INTERVAL_SECS", 300),
+            night_audit_interval_secs: env_or("NIGHT_AUDIT_INTERVAL_SECS", 600),
+            waitlist_interval_secs: env_or("WAITLIST_INTERVAL_SECS", 60),
+            waitlist_hold_hours: env_or("WAITLIST_HOLD_HOURS", 24),
+            gift_voucher_validity_days: env_or("GIFT_VOUCHER_VALIDITY_DAYS", 365),
+            loyalty: LoyaltyConfig {
+                earn_points_per_unit: env_or("LOYALTY_EARN_POINTS_PER_UNIT", 1),
+                redeem_points_per_unit,
+            },
         }
     }
 }
+
+// Read an optional env variable, falling back to `default` when it is missing
+fn env_or<T: std::str::FromStr>(name: &str, default: T) -> T {
+    match std::env::var(name) {
+        Ok(value) => value
+            .parse::<T>()
+            .unwrap_or_else(|_| panic!("{} has an invalid value", name)),
+        Err(_) => default,
+    }
+}
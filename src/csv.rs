@@ -0,0 +1,59 @@
+// Minimal CSV writer for the exports, following RFC 4180
+//
+// Fields are quoted only when they hold a separator, a quote or a line break,
+// quotes inside a field are doubled and every record ends with CRLF.
+//
+// Fields a spreadsheet would read as a formula are prefixed with `'` and quoted,
+// so guest names or requests can't run anything when the export is opened.
+// Numbers are left as they are, a negative amount or a `+` phone number is no formula.
+
+pub struct CsvWriter {
+    out: String,
+}
+
+impl CsvWriter {
+    // Start the document with its header record
+    pub fn new(header: &[&str]) -> CsvWriter {
+        let mut writer = CsvWriter { out: String::new() };
+        writer.record(header);
+        writer
+    }
+
+    pub fn record<S: AsRef<str>>(&mut self, fields: &[S]) {
+        for (i, field) in fields.iter().enumerate() {
+            if i > 0 {
+                self.out.push(',');
+            }
+            push_field(&mut self.out, field.as_ref());
+        }
+        self.out.push_str("\r\n");
+    }
+
+    pub fn finish(self) -> String {
+        self.out
+    }
+}
+
+// Characters a spreadsheet starts a formula with
+const FORMULA_PREFIXES: [char; 6] = ['=', '+', '-', '@', '\t', '\r'];
+
+fn push_field(out: &mut String, field: &str) {
+    let is_formula = field.starts_with(FORMULA_PREFIXES) && !is_number(field);
+    if !is_formula && !field.contains([',', '"', '\r', '\n']) {
+        out.push_str(field);
+        return;
+    }
+
+    out.push('"');
+    if is_formula {
+        out.push('\'');
+    }
+    out.push_str(&field.replace('"', "\"\""));
+    out.push('"');
+}
+
+fn is_number(field: &str) -> bool {
+    let digits = field.strip_prefix(['-', '+']).unwrap_or(field);
+    digits.chars().any(|c| c.is_ascii_digit())
+        && digits.chars().all(|c| c.is_ascii_digit() || c == '.')
+}
@@ -0,0 +1,43 @@
+use bigdecimal::BigDecimal;
+use sqlx::PgExecutor;
+
+use crate::models::DEFAULT_PROPERTY_ID;
+
+// Currencies are ISO 4217 codes, e.g. `EUR`
+pub fn is_currency_code(code: &str) -> bool {
+    code.len() == 3 && code.chars().all(|c| c.is_ascii_uppercase())
+}
+
+// Currency charges and payments of the property are posted in
+pub async fn base_currency<'e>(executor: impl PgExecutor<'e>) -> Result<String, sqlx::Error> {
+    sqlx::query_scalar!(
+        "select base_currency from property where id = $1",
+        DEFAULT_PROPERTY_ID
+    )
+    .fetch_one(executor)
+    .await
+}
+
+// Rate to convert amounts from the base currency, `None` when no rate is configured
+pub async fn exchange_rate<'e>(
+    executor: impl PgExecutor<'e>,
+    base_currency: &str,
+    currency: &str,
+) -> Result<Option<BigDecimal>, sqlx::Error> {
+    if base_currency == currency {
+        return Ok(Some(BigDecimal::from(1)));
+    }
+
+    sqlx::query_scalar!(
+        "select rate from exchange_rate where base_currency = $1 and currency = $2",
+        base_currency,
+        currency
+    )
+    .fetch_optional(executor)
+    .await
+}
+
+// Converted amounts are rounded to cents like every stored amount
+pub fn convert(amount: &BigDecimal, rate: &BigDecimal) -> BigDecimal {
+    (amount * rate).round(2).with_scale(2)
+}
@@ -0,0 +1,161 @@
+use bigdecimal::{BigDecimal, Zero};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use sqlx::{PgConnection, Pool, Postgres};
+
+use crate::{
+    gift_voucher::refund_booking_redemptions,
+    loyalty::reverse_redemptions,
+    models::{
+        DepositRequest, RatePlan, BOOKING_STATUS_CONFIRMED, BOOKING_STATUS_EXPIRED,
+        BOOKING_STATUS_PENDING,
+    },
+};
+
+// Ways a rate plan can ask for a deposit
+pub const DEPOSIT_TYPES: &[&str] = &["none", "fixed", "percentage"];
+
+// Deposit the rate plan requires for a stay, `None` when nothing is due at booking time
+pub fn deposit_amount(plan: &RatePlan, stay_total: &BigDecimal) -> Option<BigDecimal> {
+    let amount = match plan.deposit_type.as_str() {
+        "fixed" => plan.deposit_value.clone().min(stay_total.clone()),
+        "percentage" => stay_total * &plan.deposit_value / BigDecimal::from(100),
+        _ => return None,
+    };
+
+    let amount = amount.round(2).with_scale(2);
+    (amount > BigDecimal::zero()).then_some(amount)
+}
+
+// The deposit is due within the hours of the rate plan, and at the latest when the stay begins
+pub fn deposit_due_at(plan: &RatePlan, checkin_date: NaiveDate) -> DateTime<Utc> {
+    let now = Utc::now();
+    let due_at = now + Duration::hours(plan.deposit_due_hours.into());
+    let checkin_at = checkin_date.and_hms_opt(0, 0, 0).unwrap().and_utc();
+
+    if checkin_at > now {
+        due_at.min(checkin_at)
+    } else {
+        due_at
+    }
+}
+
+// Ask for the deposit of a new booking, inside the transaction creating it
+pub async fn create_deposit_request(
+    conn: &mut PgConnection,
+    booking_id: i32,
+    amount: &BigDecimal,
+    currency: &str,
+    stay_total: &BigDecimal,
+    due_at: DateTime<Utc>,
+) -> Result<DepositRequest, sqlx::Error> {
+    sqlx::query_as!(
+        DepositRequest,
+        "insert into deposit_request (booking_id, amount, currency, stay_total, due_at)
+        values ($1, $2, $3, $4, $5)
+        returning *",
+        booking_id,
+        amount,
+        currency,
+        stay_total.with_scale(2),
+        due_at
+    )
+    .fetch_one(conn)
+    .await
+}
+
+// Follow a booking moved to new dates, a deposit already paid keeps its amount
+pub async fn revise_deposit_request(
+    conn: &mut PgConnection,
+    booking_id: i32,
+    amount: Option<&BigDecimal>,
+    stay_total: &BigDecimal,
+) -> Result<Option<DepositRequest>, sqlx::Error> {
+    sqlx::query_as!(
+        DepositRequest,
+        "update deposit_request set
+        amount = case when status = 'requested' then coalesce($2, amount) else amount end,
+        stay_total = $3,
+        updated_at = now()
+        where booking_id = $1
+        returning *",
+        booking_id,
+        amount,
+        stay_total.with_scale(2)
+    )
+    .fetch_optional(conn)
+    .await
+}
+
+// Confirm the booking once the payments cover the deposit, returns the settled request
+pub async fn settle_deposit(
+    db: &Pool<Postgres>,
+    booking_id: i32,
+    payments_total: &BigDecimal,
+) -> Result<Option<DepositRequest>, sqlx::Error> {
+    let mut tx = db.begin().await?;
+
+    let deposit = sqlx::query_as!(
+        DepositRequest,
+        "update deposit_request set
+        status = 'paid',
+        paid_at = now(),
+        updated_at = now()
+        where booking_id = $1 and status = 'requested' and amount <= $2
+        returning *",
+        booking_id,
+        payments_total
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    if deposit.is_some() {
+        sqlx::query!(
+            "update booking set status = $1, updated_at = now() where id = $2 and status = $3",
+            BOOKING_STATUS_CONFIRMED,
+            booking_id,
+            BOOKING_STATUS_PENDING
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(deposit)
+}
+
+// Expire the pending bookings whose deposit is overdue, returns their ids
+pub async fn expire_overdue_deposits(db: &Pool<Postgres>) -> Result<Vec<i32>, sqlx::Error> {
+    let mut tx = db.begin().await?;
+
+    let booking_ids = sqlx::query_scalar!(
+        "update deposit_request set
+        status = 'expired',
+        updated_at = now()
+        where status = 'requested' and due_at <= now()
+        returning booking_id"
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    if !booking_ids.is_empty() {
+        sqlx::query!(
+            "update booking set status = $1, updated_at = now() where id = any($2) and status = $3",
+            BOOKING_STATUS_EXPIRED,
+            &booking_ids,
+            BOOKING_STATUS_PENDING
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    // Points and voucher balance spent on the expired bookings go back to the guest
+    for booking_id in &booking_ids {
+        reverse_redemptions(&mut tx, *booking_id).await?;
+        refund_booking_redemptions(&mut tx, *booking_id).await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(booking_ids)
+}
@@ -0,0 +1,229 @@
+use bigdecimal::{BigDecimal, Zero};
+use sqlx::{PgConnection, Pool, Postgres};
+
+use crate::{
+    models::{
+        FolioLine, Payment, PAYMENT_STATUS_PAID, PAYMENT_STATUS_PARTIAL, PAYMENT_STATUS_UNPAID,
+    },
+    response::Folio,
+    tax::{active_tax_rules, compute_taxes, Taxable},
+};
+
+// Charge types staff can post, tax lines are only posted by the tax rules
+pub const LINE_TYPES: &[&str] = &["room", "minibar", "restaurant", "spa", "other"];
+
+// Accepted ways to pay
+pub const PAYMENT_METHODS: &[&str] = &["cash", "card", "bank_transfer", "gift_voucher"];
+
+// Paying with a gift voucher spends its balance, see gift_voucher.rs
+pub const PAYMENT_METHOD_GIFT_VOUCHER: &str = "gift_voucher";
+
+// A charge to post on the folio of a booking
+pub struct NewCharge {
+    pub booking_id: i32,
+    pub line_type: String,
+    pub description: String,
+    pub quantity: i32,
+    pub unit_price: BigDecimal,
+    // Tax entered by hand, when missing the tax rules compute it
+    pub tax_amount: Option<BigDecimal>,
+    pub posted_by: Option<i32>,
+}
+
+// Post a charge together with the tax lines the active rules compute for it
+//
+// For room charges the quantity is the number of nights, which per person
+// per night taxes are computed on. Lines are dated with the current business date.
+pub async fn post_charge(
+    conn: &mut PgConnection,
+    charge: NewCharge,
+) -> Result<(FolioLine, Vec<FolioLine>), sqlx::Error> {
+    // Lines are posted in the currency of the booking
+    let booking = sqlx::query!(
+        "select num_adults, num_children, currency from booking where id = $1",
+        charge.booking_id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let line = sqlx::query_as!(
+        FolioLine,
+        "insert into folio_line
+            (
+                booking_id,
+                line_type,
+                description,
+                quantity,
+                unit_price,
+                tax_amount,
+                posted_by,
+                currency,
+                business_date
+            )
+        values ($1, $2, $3, $4, $5, $6, $7, $8, (select business_date from business_date))
+        returning *",
+        charge.booking_id,
+        charge.line_type,
+        charge.description,
+        charge.quantity,
+        charge.unit_price,
+        charge.tax_amount.clone().unwrap_or_else(BigDecimal::zero),
+        charge.posted_by,
+        booking.currency
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let mut tax_lines = Vec::new();
+    if charge.tax_amount.is_none() {
+        let rules = active_tax_rules(&mut *conn).await?;
+        let net_amount = line.net_amount();
+        let taxes = compute_taxes(
+            &rules,
+            &Taxable {
+                line_type: &line.line_type,
+                net_amount: &net_amount,
+                nights: line.quantity,
+                num_adults: booking.num_adults,
+                num_children: booking.num_children,
+            },
+        );
+
+        for tax in taxes {
+            let tax_line = sqlx::query_as!(
+                FolioLine,
+                "insert into folio_line
+                    (
+                        booking_id,
+                        line_type,
+                        description,
+                        quantity,
+                        unit_price,
+                        posted_by,
+                        parent_line_id,
+                        tax_rule_id,
+                        currency,
+                        business_date
+                    )
+                values ($1, 'tax', $2, 1, $3, $4, $5, $6, $7, (select business_date from business_date))
+                returning *",
+                charge.booking_id,
+                tax.name,
+                tax.amount,
+                charge.posted_by,
+                line.id,
+                tax.tax_rule_id,
+                booking.currency
+            )
+            .fetch_one(&mut *conn)
+            .await?;
+            tax_lines.push(tax_line);
+        }
+    }
+
+    Ok((line, tax_lines))
+}
+
+// Void a line and the tax lines computed for it, returns the voided lines
+pub async fn void_line(
+    db: &Pool<Postgres>,
+    line_id: i32,
+    voided_by: i32,
+    reason: &str,
+) -> Result<Vec<FolioLine>, sqlx::Error> {
+    sqlx::query_as!(
+        FolioLine,
+        "update folio_line set
+        voided_at = now(),
+        voided_by = $1,
+        void_reason = $2,
+        updated_at = now()
+        where (id = $3 or parent_line_id = $3) and voided_at is null
+        returning *",
+        voided_by,
+        reason,
+        line_id
+    )
+    .fetch_all(db)
+    .await
+}
+
+// Load every line and payment of the booking and compute the balance, voided lines are listed but not counted
+pub async fn load_folio(conn: &mut PgConnection, booking_id: i32) -> Result<Folio, sqlx::Error> {
+    let currency = sqlx::query_scalar!("select currency from booking where id = $1", booking_id)
+        .fetch_one(&mut *conn)
+        .await?;
+
+    let lines = sqlx::query_as!(
+        FolioLine,
+        "select * from folio_line where booking_id = $1 order by id",
+        booking_id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let payments = sqlx::query_as!(
+        Payment,
+        "select * from payment where booking_id = $1 order by id",
+        booking_id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut net_total = BigDecimal::zero();
+    let mut tax_total = BigDecimal::zero();
+    for line in lines.iter().filter(|line| line.voided_at.is_none()) {
+        if line.is_tax() {
+            tax_total += line.net_amount();
+        } else {
+            net_total += line.net_amount();
+            tax_total += &line.tax_amount;
+        }
+    }
+
+    let payments_total = payments
+        .iter()
+        .fold(BigDecimal::zero(), |total, payment| total + &payment.amount);
+
+    let charges_total = &net_total + &tax_total;
+    let balance = &charges_total - &payments_total;
+
+    // Every amount is stored with two decimals, keep the totals the same way
+    Ok(Folio {
+        booking_id,
+        currency,
+        lines,
+        payments,
+        net_total: net_total.with_scale(2),
+        tax_total: tax_total.with_scale(2),
+        charges_total: charges_total.with_scale(2),
+        payments_total: payments_total.with_scale(2),
+        balance: balance.with_scale(2),
+    })
+}
+
+// Keep the payment status of the booking in line with its folio balance
+pub async fn refresh_payment_status(
+    db: &Pool<Postgres>,
+    booking_id: i32,
+) -> Result<Folio, sqlx::Error> {
+    let folio = load_folio(&mut *db.acquire().await?, booking_id).await?;
+
+    let payment_status_id = if folio.payments_total.is_zero() {
+        PAYMENT_STATUS_UNPAID
+    } else if folio.balance <= BigDecimal::zero() {
+        PAYMENT_STATUS_PAID
+    } else {
+        PAYMENT_STATUS_PARTIAL
+    };
+
+    sqlx::query!(
+        "update booking set payment_status_id = $1, updated_at = now() where id = $2",
+        payment_status_id,
+        booking_id
+    )
+    .execute(db)
+    .await?;
+
+    Ok(folio)
+}
@@ -0,0 +1,135 @@
+use chrono::NaiveDate;
+use sqlx::{Pool, Postgres};
+
+use crate::{
+    csv::CsvWriter,
+    folio::load_folio,
+    models::{
+        BOOKING_STATUS_CHECKED_IN, BOOKING_STATUS_CHECKED_OUT, BOOKING_STATUS_CONFIRMED,
+        BOOKING_STATUS_NO_SHOW, BOOKING_STATUS_PENDING,
+    },
+    response::FrontDeskEntry,
+};
+
+// Daily lists of the front desk, as named in the url
+//
+// - arrivals: due to check in on the date, arrived or not
+// - departures: due to check out on the date
+// - in-house: staying the night of the date
+// - no-shows: due on the date and not checked in, marked by the night audit once the day is closed
+pub const FRONT_DESK_LISTS: &[&str] = &["arrivals", "departures", "in-house", "no-shows"];
+
+pub async fn front_desk_list(
+    db: &Pool<Postgres>,
+    list: &str,
+    date: NaiveDate,
+) -> Result<Vec<FrontDeskEntry>, sqlx::Error> {
+    let rows = sqlx::query!(
+        "select
+            b.id,
+            b.status,
+            g.first_name,
+            g.last_name,
+            g.email_address,
+            g.phone_number,
+            r.room_number as \"room_number?\",
+            rt.name as \"room_type?\",
+            b.checkin_date,
+            b.checkout_date,
+            b.num_adults,
+            b.num_children,
+            b.currency,
+            b.special_requests
+        from booking b
+        join guest g on g.id = b.guest_id
+        left join room r on r.id = b.room_id
+        left join room_type rt on rt.id = b.room_type_id
+        where case $1
+            when 'arrivals' then b.checkin_date = $2 and b.status in ($3, $4, $5)
+            when 'departures' then b.checkout_date = $2 and b.status in ($5, $6)
+            when 'in-house' then b.checkin_date <= $2
+                and (
+                    b.status = $5
+                    or (b.status = $6 and b.checkout_date > $2 and b.checked_out_at::date > $2)
+                )
+            when 'no-shows' then b.checkin_date = $2 and b.status in ($3, $4, $7)
+            else false
+        end
+        order by r.room_number nulls last, g.last_name, b.id",
+        list,
+        date,
+        BOOKING_STATUS_PENDING,
+        BOOKING_STATUS_CONFIRMED,
+        BOOKING_STATUS_CHECKED_IN,
+        BOOKING_STATUS_CHECKED_OUT,
+        BOOKING_STATUS_NO_SHOW
+    )
+    .fetch_all(db)
+    .await?;
+
+    let mut entries = Vec::with_capacity(rows.len());
+    let mut conn = db.acquire().await?;
+    for row in rows {
+        let folio = load_folio(&mut conn, row.id).await?;
+
+        entries.push(FrontDeskEntry {
+            booking_id: row.id,
+            status: row.status,
+            guest_name: format!("{} {}", row.first_name, row.last_name),
+            email_address: row.email_address,
+            phone_number: row.phone_number,
+            room_number: row.room_number,
+            room_type: row.room_type,
+            checkin_date: row.checkin_date,
+            checkout_date: row.checkout_date,
+            num_adults: row.num_adults,
+            num_children: row.num_children,
+            balance_due: folio.balance,
+            currency: row.currency,
+            special_requests: row.special_requests,
+        });
+    }
+
+    Ok(entries)
+}
+
+// Render a list for the night auditor, one booking per record
+pub fn front_desk_csv(entries: &[FrontDeskEntry]) -> String {
+    let mut csv = CsvWriter::new(&[
+        "booking_id",
+        "status",
+        "guest_name",
+        "email_address",
+        "phone_number",
+        "room_number",
+        "room_type",
+        "checkin_date",
+        "checkout_date",
+        "adults",
+        "children",
+        "balance_due",
+        "currency",
+        "special_requests",
+    ]);
+
+    for entry in entries {
+        csv.record(&[
+            entry.booking_id.to_string(),
+            entry.status.clone(),
+            entry.guest_name.clone(),
+            entry.email_address.clone(),
+            entry.phone_number.clone(),
+            entry.room_number.clone().unwrap_or_default(),
+            entry.room_type.clone().unwrap_or_default(),
+            entry.checkin_date.to_string(),
+            entry.checkout_date.to_string(),
+            entry.num_adults.to_string(),
+            entry.num_children.to_string(),
+            entry.balance_due.to_string(),
+            entry.currency.clone(),
+            entry.special_requests.clone().unwrap_or_default(),
+        ]);
+    }
+
+    csv.finish()
+}
@@ -0,0 +1,219 @@
+use bigdecimal::BigDecimal;
+use chrono::{Duration, Utc};
+use sqlx::{PgConnection, Pool, Postgres};
+
+use crate::{
+    code::random_code,
+    models::{GiftVoucher, GiftVoucherTransaction},
+};
+
+// Why a voucher couldn't be spent
+pub enum VoucherError {
+    Rejected(String),
+    Database(sqlx::Error),
+}
+
+impl From<sqlx::Error> for VoucherError {
+    fn from(e: sqlx::Error) -> Self {
+        VoucherError::Database(e)
+    }
+}
+
+// A voucher being sold
+pub struct NewVoucher {
+    pub amount: BigDecimal,
+    pub currency: String,
+    pub purchased_by: Option<i32>,
+    pub recipient_name: Option<String>,
+    pub recipient_email: Option<String>,
+    pub message: Option<String>,
+    pub method: String,
+    pub performed_by: Option<i32>,
+    pub validity_days: i64,
+}
+
+// Generate a random voucher code formatted as `GV-XXXX-XXXX-XXXX`
+pub fn generate_code() -> String {
+    let chars = random_code(12);
+
+    format!("GV-{}-{}-{}", &chars[..4], &chars[4..8], &chars[8..])
+}
+
+// Codes are looked up case insensitively and without surrounding spaces
+pub fn normalize_code(code: &str) -> String {
+    code.trim().to_ascii_uppercase()
+}
+
+// Create the voucher with its purchase in the ledger
+pub async fn issue_voucher(
+    db: &Pool<Postgres>,
+    voucher: NewVoucher,
+) -> Result<GiftVoucher, sqlx::Error> {
+    let mut tx = db.begin().await?;
+
+    let amount = voucher.amount.with_scale(2);
+    let gift_voucher = sqlx::query_as!(
+        GiftVoucher,
+        "insert into gift_voucher
+            (
+                code,
+                initial_amount,
+                balance,
+                currency,
+                purchased_by,
+                recipient_name,
+                recipient_email,
+                message,
+                expires_at
+            )
+        values ($1, $2, $2, $3, $4, $5, $6, $7, $8)
+        returning *",
+        generate_code(),
+        amount,
+        voucher.currency,
+        voucher.purchased_by,
+        voucher.recipient_name,
+        voucher.recipient_email,
+        voucher.message,
+        Utc::now() + Duration::days(voucher.validity_days)
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    sqlx::query!(
+        "insert into gift_voucher_transaction
+            (gift_voucher_id, transaction_type, amount, method, performed_by)
+        values ($1, 'purchase', $2, $3, $4)",
+        gift_voucher.id,
+        amount,
+        voucher.method,
+        voucher.performed_by
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(gift_voucher)
+}
+
+// Spend part or all of the voucher on the folio payment, inside the transaction recording it
+pub async fn redeem_voucher(
+    conn: &mut PgConnection,
+    code: &str,
+    amount: &BigDecimal,
+    currency: &str,
+    booking_id: i32,
+    payment_id: i32,
+    performed_by: i32,
+) -> Result<GiftVoucher, VoucherError> {
+    let voucher = sqlx::query_as!(
+        GiftVoucher,
+        "select * from gift_voucher where code = $1 for update",
+        normalize_code(code)
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| VoucherError::Rejected(format!("Gift voucher {} not found", code)))?;
+
+    if voucher.expires_at < Utc::now() {
+        return Err(VoucherError::Rejected(format!(
+            "Gift voucher {} expired on {}",
+            voucher.code,
+            voucher.expires_at.format("%Y-%m-%d")
+        )));
+    }
+
+    if voucher.currency != currency {
+        return Err(VoucherError::Rejected(format!(
+            "Gift voucher {} is in {} and can't pay a booking in {}",
+            voucher.code, voucher.currency, currency
+        )));
+    }
+
+    if &voucher.balance < amount {
+        return Err(VoucherError::Rejected(format!(
+            "Gift voucher {} only has {} left",
+            voucher.code,
+            voucher.balance.with_scale(2)
+        )));
+    }
+
+    let voucher = sqlx::query_as!(
+        GiftVoucher,
+        "update gift_voucher set balance = balance - $1, updated_at = now() where id = $2 returning *",
+        amount,
+        voucher.id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    sqlx::query!(
+        "insert into gift_voucher_transaction
+            (gift_voucher_id, transaction_type, amount, booking_id, payment_id, performed_by)
+        values ($1, 'redemption', $2, $3, $4, $5)",
+        voucher.id,
+        -amount,
+        booking_id,
+        payment_id,
+        performed_by
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(voucher)
+}
+
+// Put back on their vouchers what was spent on a booking that is being deleted or expired
+pub async fn refund_booking_redemptions(
+    conn: &mut PgConnection,
+    booking_id: i32,
+) -> Result<(), sqlx::Error> {
+    let refunds = sqlx::query!(
+        "select gift_voucher_id, -sum(amount) as \"amount!\"
+        from gift_voucher_transaction
+        where booking_id = $1 and transaction_type in ('redemption', 'refund')
+        group by gift_voucher_id
+        having sum(amount) < 0",
+        booking_id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    for refund in refunds {
+        sqlx::query!(
+            "update gift_voucher set balance = balance + $1, updated_at = now() where id = $2",
+            refund.amount,
+            refund.gift_voucher_id
+        )
+        .execute(&mut *conn)
+        .await?;
+
+        sqlx::query!(
+            "insert into gift_voucher_transaction
+                (gift_voucher_id, transaction_type, amount, booking_id)
+            values ($1, 'refund', $2, $3)",
+            refund.gift_voucher_id,
+            refund.amount,
+            booking_id
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    Ok(())
+}
+
+// Ledger of the voucher, oldest first
+pub async fn voucher_transactions(
+    db: &Pool<Postgres>,
+    gift_voucher_id: i32,
+) -> Result<Vec<GiftVoucherTransaction>, sqlx::Error> {
+    sqlx::query_as!(
+        GiftVoucherTransaction,
+        "select * from gift_voucher_transaction where gift_voucher_id = $1 order by id",
+        gift_voucher_id
+    )
+    .fetch_all(db)
+    .await
+}
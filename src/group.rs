@@ -0,0 +1,145 @@
+use bigdecimal::{BigDecimal, Zero};
+use chrono::NaiveDate;
+use sqlx::{PgConnection, Pool, Postgres};
+
+use crate::{
+    availability::night_occupancy,
+    code::random_code,
+    folio::load_folio,
+    models::{Booking, ReservationGroup, BOOKING_STATUS_EXPIRED},
+    response::{GroupBilling, GroupBookingBalance},
+};
+
+pub const BILLING_TYPES: &[&str] = &["shared", "split"];
+
+pub const BILLING_TYPE_SHARED: &str = "shared";
+pub const BILLING_TYPE_SPLIT: &str = "split";
+
+// Why a room couldn't be picked up from a group block
+pub enum GroupError {
+    Rejected(String),
+    Database(sqlx::Error),
+}
+
+impl From<sqlx::Error> for GroupError {
+    fn from(e: sqlx::Error) -> Self {
+        GroupError::Database(e)
+    }
+}
+
+// Generate a random group code formatted as `GRP-XXXXXX`
+pub fn generate_code() -> String {
+    format!("GRP-{}", random_code(6))
+}
+
+// Rooms of the block already taken, expired bookings gave theirs back
+pub async fn rooms_booked(conn: &mut PgConnection, group_id: i32) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar!(
+        "select count(*) as \"count!\" from booking
+        where reservation_group_id = $1 and status <> $2",
+        group_id,
+        BOOKING_STATUS_EXPIRED
+    )
+    .fetch_one(conn)
+    .await
+}
+
+// Whether the rooms held for the group fit in its room type on every night of the stay,
+// groups without a room type don't hold rooms of any type
+pub async fn hold_fits(
+    conn: &mut PgConnection,
+    group: &ReservationGroup,
+) -> Result<bool, sqlx::Error> {
+    let Some(room_type_id) = group.room_type_id else {
+        return Ok(true);
+    };
+
+    let nights = night_occupancy(
+        conn,
+        room_type_id,
+        group.checkin_date,
+        group.checkout_date,
+        None,
+    )
+    .await?;
+
+    Ok(nights.iter().all(|night| night.sold <= night.sellable))
+}
+
+// Take a room from the block of the group, the group row stays locked until the booking is saved
+pub async fn pick_up_room(
+    conn: &mut PgConnection,
+    code: &str,
+    checkin_date: NaiveDate,
+    checkout_date: NaiveDate,
+    room_type_id: Option<i32>,
+) -> Result<ReservationGroup, GroupError> {
+    let group = sqlx::query_as!(
+        ReservationGroup,
+        "select * from reservation_group where code = $1 for update",
+        code.trim().to_ascii_uppercase()
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| GroupError::Rejected(format!("Group code {} not found", code)))?;
+
+    if checkin_date < group.checkin_date || checkout_date > group.checkout_date {
+        return Err(GroupError::Rejected(format!(
+            "The rooms of {} are held from {} to {}",
+            group.name, group.checkin_date, group.checkout_date
+        )));
+    }
+
+    if let (Some(held), Some(requested)) = (group.room_type_id, room_type_id) {
+        if held != requested {
+            return Err(GroupError::Rejected(format!(
+                "The block of {} only holds room type {}",
+                group.name, held
+            )));
+        }
+    }
+
+    if rooms_booked(conn, group.id).await? >= i64::from(group.rooms_held) {
+        return Err(GroupError::Rejected(format!(
+            "Every room held for {} has been picked up",
+            group.name
+        )));
+    }
+
+    Ok(group)
+}
+
+// Balances of the group bookings, with shared billing the lead guest pays for all of them
+pub async fn group_billing(
+    db: &Pool<Postgres>,
+    group: &ReservationGroup,
+    bookings: &[Booking],
+) -> Result<GroupBilling, sqlx::Error> {
+    let mut balances = Vec::with_capacity(bookings.len());
+    let mut balance_total = BigDecimal::zero();
+    let mut lead_balance_due = BigDecimal::zero();
+
+    let mut conn = db.acquire().await?;
+    for booking in bookings {
+        let folio = load_folio(&mut conn, booking.id).await?;
+
+        balance_total += &folio.balance;
+        if group.billing_type == BILLING_TYPE_SHARED || booking.guest_id == group.lead_guest_id {
+            lead_balance_due += &folio.balance;
+        }
+
+        balances.push(GroupBookingBalance {
+            booking_id: booking.id,
+            guest_id: booking.guest_id,
+            status: booking.status.clone(),
+            balance: folio.balance,
+        });
+    }
+
+    Ok(GroupBilling {
+        billing_type: group.billing_type.clone(),
+        bookings: balances,
+        balance_total: balance_total.with_scale(2),
+        lead_balance_due: lead_balance_due.with_scale(2),
+    })
+}
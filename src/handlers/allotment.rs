@@ -0,0 +1,228 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Extension, Json,
+};
+
+use crate::{
+    allotment::pickup_report,
+    availability::available_rooms,
+    models::{AllotmentBlock, Guest},
+    schema::CreateAllotmentBlockSchema,
+    AppState,
+};
+
+use super::util::{bad_request, database_error};
+
+// Handler for admins to list the allotment blocks, latest stays first
+pub async fn list_allotment_blocks_handler(
+    State(data): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let blocks = sqlx::query_as!(
+        AllotmentBlock,
+        "select * from allotment_block order by start_date desc, id desc"
+    )
+    .fetch_all(&data.db)
+    .await
+    .map_err(database_error)?;
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "results": blocks.len(),
+        "allotment_blocks": blocks
+    });
+
+    Ok(Json(json_response))
+}
+
+// Handler for admins to hold rooms for a corporate or event group, out of general availability
+pub async fn create_allotment_block_handler(
+    State(data): State<Arc<AppState>>,
+    Extension(admin): Extension<Guest>,
+    Json(body): Json<CreateAllotmentBlockSchema>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let code = body.code.trim().to_uppercase();
+    if code.is_empty() || body.name.trim().is_empty() {
+        return Err(bad_request(
+            "Block code and name can't be empty".to_string(),
+        ));
+    }
+
+    if body.end_date <= body.start_date {
+        return Err(bad_request(
+            "End date must be after the start date".to_string(),
+        ));
+    }
+
+    if body.cutoff_date > body.start_date {
+        return Err(bad_request(
+            "Cutoff date can't be after the start date".to_string(),
+        ));
+    }
+
+    if body.rooms <= 0 {
+        return Err(bad_request("Rooms held must be positive".to_string()));
+    }
+
+    let mut tx = data.db.begin().await.map_err(database_error)?;
+
+    // Lock the room type so the rooms can't be sold while they are being held
+    let room_type_name = sqlx::query_scalar!(
+        "select name from room_type where id = $1 for update",
+        body.room_type_id
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(database_error)?
+    .ok_or_else(|| {
+        bad_request(format!(
+            "Room type with ID: {} not found",
+            body.room_type_id
+        ))
+    })?;
+
+    let available = available_rooms(&mut tx, body.room_type_id, body.start_date, body.end_date)
+        .await
+        .map_err(database_error)?;
+    if available < i64::from(body.rooms) {
+        let error_response = serde_json::json!({
+            "status": "fail",
+            "message": format!(
+                "Only {} {} rooms are available for every night from {} to {}",
+                available, room_type_name, body.start_date, body.end_date
+            )
+        });
+        return Err((StatusCode::CONFLICT, Json(error_response)));
+    }
+
+    let block = sqlx::query_as!(
+        AllotmentBlock,
+        "insert into allotment_block
+            (code, name, room_type_id, start_date, end_date, rooms, cutoff_date, created_by)
+        values ($1, $2, $3, $4, $5, $6, $7, $8)
+        returning *",
+        code,
+        body.name.trim(),
+        body.room_type_id,
+        body.start_date,
+        body.end_date,
+        body.rooms,
+        body.cutoff_date,
+        admin.id
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        let is_duplicate = e
+            .as_database_error()
+            .and_then(|e| e.code())
+            .is_some_and(|code| code == "23505");
+
+        if is_duplicate {
+            let error_response = serde_json::json!({
+                "status": "fail",
+                "message": format!("Allotment block with code: {} already exists", code),
+            });
+            return (StatusCode::CONFLICT, Json(error_response));
+        }
+
+        database_error(e)
+    })?;
+
+    tx.commit().await.map_err(database_error)?;
+
+    tracing::info!(
+        allotment_block_id = block.id,
+        rooms = block.rooms,
+        "allotment block created"
+    );
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "data": serde_json::json!({
+            "allotment_block": block
+        })
+    });
+
+    Ok((StatusCode::CREATED, Json(json_response)))
+}
+
+// Handler for admins to see how much of a block has been picked up, night by night
+pub async fn allotment_pickup_handler(
+    State(data): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let block = find_block(&data, id).await?;
+
+    let pickup = pickup_report(&data.db, block)
+        .await
+        .map_err(database_error)?;
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "data": pickup
+    });
+
+    Ok(Json(json_response))
+}
+
+// Handler for admins to release a block before its cutoff date
+pub async fn release_allotment_block_handler(
+    State(data): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    find_block(&data, id).await?;
+
+    let block = sqlx::query_as!(
+        AllotmentBlock,
+        "update allotment_block set released_at = now(), updated_at = now()
+        where id = $1 and released_at is null
+        returning *",
+        id
+    )
+    .fetch_optional(&data.db)
+    .await
+    .map_err(database_error)?
+    .ok_or_else(|| {
+        let error_response = serde_json::json!({
+            "status": "fail",
+            "message": format!("Allotment block with ID: {} is already released", id)
+        });
+        (StatusCode::CONFLICT, Json(error_response))
+    })?;
+
+    tracing::info!(allotment_block_id = id, "allotment block released");
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "data": serde_json::json!({
+            "allotment_block": block
+        })
+    });
+
+    Ok(Json(json_response))
+}
+
+async fn find_block(
+    data: &AppState,
+    id: i32,
+) -> Result<AllotmentBlock, (StatusCode, Json<serde_json::Value>)> {
+    sqlx::query_as!(
+        AllotmentBlock,
+        "select * from allotment_block where id = $1",
+        id
+    )
+    .fetch_optional(&data.db)
+    .await
+    .map_err(database_error)?
+    .ok_or_else(|| {
+        let error_response = serde_json::json!({
+            "status": "fail",
+            "message": format!("Allotment block with ID: {} not found", id)
+        });
+        (StatusCode::NOT_FOUND, Json(error_response))
+    })
+}
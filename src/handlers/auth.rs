@@ -13,9 +13,11 @@ use rand_core::OsRng;
 use serde_json::json;
 
 use crate::{
+    currency::{base_currency, exchange_rate, is_currency_code},
     models::Guest,
     response::FilteredGuest,
-    schema::{LoginGuestSchema, RegisterGuestSchema, TokenClaims},
+    schema::{LoginGuestSchema, RegisterGuestSchema, TokenClaims, UpdateGuestSchema},
+    telemetry::record_login,
     AppState,
 };
 
@@ -111,6 +113,8 @@ pub async fn register_guest_handler(
             (StatusCode::INTERNAL_SERVER_ERROR, Json(error_response))
     })?;
 
+    tracing::info!(guest_id = guest.id, "guest registered");
+
     // Construct a json response of success containing the guest data
     let guest_response = serde_json::json!({"status": "success", "data": serde_json::json!({
         "guest": filter_guest_record(&guest)
@@ -141,6 +145,8 @@ pub async fn login_guest_handler(
         (StatusCode::INTERNAL_SERVER_ERROR, Json(error_response))
     })?
     .ok_or_else(|| {
+        tracing::warn!("login attempt for an unknown email address");
+        record_login("failure");
         let error_response = serde_json::json!({
             "status": "fail",
             "message": "Invalid email or password"
@@ -158,6 +164,11 @@ pub async fn login_guest_handler(
 
     // Return an error_response if the passwords don't match
     if !is_valid {
+        tracing::warn!(
+            guest_id = guest.id,
+            "login attempt with an invalid password"
+        );
+        record_login("failure");
         let error_response = serde_json::json!({
             "status": "fail",
             "message": "Invalid email and password"
@@ -165,23 +176,79 @@ pub async fn login_guest_handler(
         return Err((StatusCode::BAD_REQUEST, Json(error_response)));
     }
 
+    // Guests with a second factor only get a short lived challenge token at this point
+    if guest.mfa_enabled {
+        let mfa_token = encode_token(&data, &guest, chrono::Duration::minutes(5), true);
+
+        tracing::info!(
+            guest_id = guest.id,
+            "password accepted, waiting for second factor"
+        );
+
+        let json_response = serde_json::json!({
+            "status": "success",
+            "mfa_required": true,
+            "mfa_token": mfa_token,
+        });
+        return Ok(Json(json_response).into_response());
+    }
+
+    // Staff and guests keeping payment methods on file are asked to enroll a second factor
+    let has_payment_methods = sqlx::query_scalar!(
+        "select exists(select 1 from payment_method where guest_id = $1) as \"exists!\"",
+        guest.id
+    )
+    .fetch_one(&data.db)
+    .await
+    .map_err(|e| {
+        let error_response = serde_json::json!({
+            "status": "error",
+            "message": format!("Database error: {}", e),
+        });
+        (StatusCode::INTERNAL_SERVER_ERROR, Json(error_response))
+    })?;
+
+    Ok(login_response(&data, &guest, guest.requires_mfa(has_payment_methods)).into_response())
+}
+
+// Construct a signed token for the guest, `mfa_pending` marks a challenge token
+pub(crate) fn encode_token(
+    data: &AppState,
+    guest: &Guest,
+    expires_in: chrono::Duration,
+    mfa_pending: bool,
+) -> String {
     // Set up TokenClaims
     let now = chrono::Utc::now();
     let iat = now.timestamp() as usize;
-    let exp = (now + chrono::Duration::minutes(60)).timestamp() as usize;
+    let exp = (now + expires_in).timestamp() as usize;
     let claims: TokenClaims = TokenClaims {
         sub: guest.id.to_string(),
         iat,
         exp,
+        mfa_pending,
     };
 
     // Construct a token with token claims
-    let token = encode(
+    encode(
         &Header::default(),
         &claims,
         &EncodingKey::from_secret(data.env.jwt_secret.as_ref()),
     )
-    .unwrap();
+    .unwrap()
+}
+
+// Issue the full token once every factor has been verified
+// `mfa_enrollment_required` tells the client to send the guest through the enrollment next
+pub(crate) fn login_response(
+    data: &AppState,
+    guest: &Guest,
+    mfa_enrollment_required: bool,
+) -> Response<String> {
+    let token = encode_token(data, guest, chrono::Duration::minutes(60), false);
+
+    tracing::info!(guest_id = guest.id, "guest logged in");
+    record_login("success");
 
     // Store the newly created token in a cookie
     // Configure settings of the cookie
@@ -192,14 +259,19 @@ pub async fn login_guest_handler(
         .http_only(true);
 
     // Construct a response to return to client
-    let mut response = Response::new(json!({"status": "success", "token": token}).to_string());
+    let json_response = json!({
+        "status": "success",
+        "token": token,
+        "mfa_enrollment_required": mfa_enrollment_required,
+    });
+    let mut response = Response::new(json_response.to_string());
 
     // Append the cookie to the response
     response
         .headers_mut()
         .insert(header::SET_COOKIE, cookie.to_string().parse().unwrap());
 
-    Ok(response)
+    response
 }
 
 // Handler to log out the guest
@@ -236,6 +308,59 @@ pub async fn get_me_handler(
     Ok(Json(json_response))
 }
 
+// Handler for the guest to update their preferences, e.g. the currency quotes are shown in
+pub async fn update_me_handler(
+    State(data): State<Arc<AppState>>,
+    Extension(guest): Extension<Guest>,
+    Json(body): Json<UpdateGuestSchema>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let database_error = |e: sqlx::Error| {
+        let error_response = serde_json::json!({
+            "status": "error",
+            "message": format!("Database error: {}", e),
+        });
+        (StatusCode::INTERNAL_SERVER_ERROR, Json(error_response))
+    };
+
+    if let Some(currency) = &body.preferred_currency {
+        let base_currency = base_currency(&data.db).await.map_err(database_error)?;
+        let rate = if is_currency_code(currency) {
+            exchange_rate(&data.db, &base_currency, currency)
+                .await
+                .map_err(database_error)?
+        } else {
+            None
+        };
+
+        if rate.is_none() {
+            let error_response = serde_json::json!({
+                "status": "fail",
+                "message": format!("Currency {} is not supported", currency),
+            });
+            return Err((StatusCode::BAD_REQUEST, Json(error_response)));
+        }
+    }
+
+    let guest = sqlx::query_as!(
+        Guest,
+        "update guest set preferred_currency = $1, updated_at = now() where id = $2 returning *",
+        body.preferred_currency,
+        guest.id
+    )
+    .fetch_one(&data.db)
+    .await
+    .map_err(database_error)?;
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "data": serde_json::json!({
+            "guest": filter_guest_record(&guest)
+        })
+    });
+
+    Ok(Json(json_response))
+}
+
 // Util function to filter the guest record to hide sensitive data
 fn filter_guest_record(guest: &Guest) -> FilteredGuest {
     FilteredGuest {
@@ -245,6 +370,9 @@ fn filter_guest_record(guest: &Guest) -> FilteredGuest {
         email_address: guest.email_address.to_owned(),
         verified: guest.verified,
         phone_number: guest.phone_number.to_owned(),
+        role: guest.role.to_owned(),
+        mfa_enabled: guest.mfa_enabled,
+        preferred_currency: guest.preferred_currency.to_owned(),
         created_at: guest.created_at.unwrap(),
         updated_at: guest.updated_at.unwrap(),
     }
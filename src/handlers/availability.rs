@@ -0,0 +1,110 @@
+use std::sync::Arc;
+
+use crate::{
+    availability::available_rooms,
+    models::RoomType,
+    pricing::{price_stay, stay_amount},
+    restriction::stay_restriction_violations,
+    schema::AvailabilityOptions,
+    AppState,
+};
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+
+use super::util::{bad_request, database_error};
+
+// Handler to search the room types that can still be booked for a stay
+pub async fn availability_handler(
+    State(data): State<Arc<AppState>>,
+    Query(opts): Query<AvailabilityOptions>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    if opts.checkout_date <= opts.checkin_date {
+        return Err(bad_request(
+            "Check-out date must be after the check-in date".to_string(),
+        ));
+    }
+
+    let nights = (opts.checkout_date - opts.checkin_date).num_days();
+    let guests = opts.num_adults.unwrap_or(1) + opts.num_children.unwrap_or(0);
+
+    let room_types = sqlx::query_as!(
+        RoomType,
+        "select * from room_type where active = true and max_occupancy >= $1 order by id",
+        guests
+    )
+    .fetch_all(&data.db)
+    .await
+    .map_err(database_error)?;
+
+    let mut conn = data.db.acquire().await.map_err(database_error)?;
+
+    let mut results = Vec::with_capacity(room_types.len());
+    let mut restricted = Vec::new();
+    let mut sold_out = Vec::new();
+    for room_type in room_types {
+        // Room types the restrictions keep from being booked for the stay are listed apart, with why
+        let violations = stay_restriction_violations(
+            &mut *conn,
+            Some(room_type.id),
+            opts.checkin_date,
+            opts.checkout_date,
+        )
+        .await
+        .map_err(database_error)?;
+        if !violations.is_empty() {
+            restricted.push(serde_json::json!({
+                "room_type": room_type,
+                "violations": violations,
+            }));
+            continue;
+        }
+
+        let available = available_rooms(
+            &mut conn,
+            room_type.id,
+            opts.checkin_date,
+            opts.checkout_date,
+        )
+        .await
+        .map_err(database_error)?;
+
+        // Guests can join the waitlist for the room types sold out
+        if available < 1 {
+            sold_out.push(room_type);
+            continue;
+        }
+
+        // Priced like a booking made now, with the pricing rules in force
+        let nightly_rates = price_stay(
+            &mut conn,
+            &room_type,
+            opts.checkin_date,
+            opts.checkout_date,
+            None,
+        )
+        .await
+        .map_err(database_error)?;
+
+        results.push(serde_json::json!({
+            "room_type": room_type,
+            "available": available,
+            "nights": nights,
+            "room_amount": stay_amount(&nightly_rates),
+            "nightly_rates": nightly_rates,
+        }));
+    }
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "results": results.len(),
+        "availability": results,
+        "restricted": restricted,
+        "sold_out": sold_out
+    });
+
+    Ok(Json(json_response))
+}
@@ -7,13 +7,41 @@ use axum::{
     Extension, Json,
 };
 use axum_macros::debug_handler;
+use bigdecimal::{BigDecimal, Zero};
+use chrono::NaiveDate;
+use serde::Serialize;
+use sqlx::{PgConnection, PgExecutor};
 
 use crate::{
-    models::{Booking, Guest},
-    schema::{CreateBookingSchema, FilterOptions, UpdateBookingSchema},
+    allotment::{pick_up_from_block, BlockError},
+    availability::{available_rooms, night_occupancy, rooms_left},
+    deposit::{create_deposit_request, deposit_amount, deposit_due_at, revise_deposit_request},
+    gift_voucher::refund_booking_redemptions,
+    group::{pick_up_room, GroupError},
+    loyalty::{lock_points_balance, points_value, redeem_points, reverse_redemptions},
+    models::{
+        Booking, DepositRequest, Guest, RatePlan, ReservationGroup, RoomType,
+        BOOKING_STATUS_CONFIRMED, BOOKING_STATUS_EXPIRED, BOOKING_STATUS_PENDING,
+        DEFAULT_RATE_PLAN_ID, PAYMENT_STATUS_UNPAID,
+    },
+    pricing::{log_price_adjustments, price_stay, quote_in_currency, quote_stay, stay_amount},
+    promo::{apply_promo_code, record_redemption, PromoError, PromoStay},
+    response::{NightlyRate, Quote},
+    restriction::stay_restriction_violations,
+    room_assignment::room_still_free,
+    schema::{CreateBookingSchema, FilterOptions, QuoteOptions, UpdateBookingSchema},
+    telemetry::{record_booking_cancelled, record_booking_created},
+    waitlist::{held_for_guest, mark_booked},
     AppState,
 };
 
+use super::{
+    util::{bad_request, booking_not_found, database_error},
+    waitlist::notify_waitlist,
+};
+
+const MAX_SPECIAL_REQUESTS_LEN: usize = 1000;
+
 // Handler to get all the bookings of the guest
 pub async fn booking_list_handler(
     Extension(guest): Extension<Guest>,
@@ -74,8 +102,18 @@ pub async fn get_booking_handler(
 
     match query_result {
         Ok(booking) => {
+            let deposit_request = sqlx::query_as!(
+                DepositRequest,
+                "select * from deposit_request where booking_id = $1",
+                booking.id
+            )
+            .fetch_optional(&data.db)
+            .await
+            .map_err(database_error)?;
+
             let booking_response = serde_json::json!({"status": "success", "data": serde_json::json!({
-                "booking": booking
+                "booking": booking,
+                "deposit_request": deposit_request
             })});
 
             return Ok(Json(booking_response));
@@ -90,15 +128,371 @@ pub async fn get_booking_handler(
     }
 }
 
+// Handler to price one of the guest's bookings, in the property currency and the currency asked for
+pub async fn booking_quote_handler(
+    State(data): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Extension(guest): Extension<Guest>,
+    opts: Option<Query<QuoteOptions>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let Query(opts) = opts.unwrap_or_default();
+
+    let booking = sqlx::query_as!(
+        Booking,
+        "select * from booking where id = $1 and guest_id = $2",
+        id,
+        &guest.id
+    )
+    .fetch_optional(&data.db)
+    .await
+    .map_err(database_error)?
+    .ok_or_else(|| booking_not_found(id))?;
+
+    let quote = quote_stay(
+        &data.db,
+        booking.checkin_date,
+        booking.checkout_date,
+        booking.num_adults,
+        booking.num_children,
+        &booking.booking_amount,
+    )
+    .await
+    .map_err(database_error)?;
+
+    let display_quote = match opts.currency.or(guest.preferred_currency) {
+        Some(currency) if currency != quote.currency => Some(
+            quote_in_currency(&data.db, &quote, &currency)
+                .await
+                .map_err(database_error)?
+                .ok_or_else(|| bad_request(format!("Currency {} is not supported", currency)))?,
+        ),
+        _ => None,
+    };
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "data": serde_json::json!({
+            "quote": quote,
+            "display_quote": display_quote
+        })
+    });
+
+    Ok(Json(json_response))
+}
+
 // Handler to create a booking for the guest
 #[debug_handler]
 pub async fn create_booking_handler(
     Extension(guest): Extension<Guest>,
     State(data): State<Arc<AppState>>,
-    Json(body): Json<CreateBookingSchema>,
+    Json(mut body): Json<CreateBookingSchema>,
 ) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let mut tx = data.db.begin().await.map_err(database_error)?;
+
+    // Rooms picked up with a group code are taken from the block held for the group
+    let group = match &body.group_code {
+        Some(code) => Some(
+            pick_up_room(
+                &mut tx,
+                code,
+                body.checkin_date,
+                body.checkout_date,
+                body.room_type_id,
+            )
+            .await
+            .map_err(|e| match e {
+                GroupError::Rejected(message) => bad_request(message),
+                GroupError::Database(e) => database_error(e),
+            })?,
+        ),
+        None => None,
+    };
+
+    if let Some(group) = &group {
+        body.room_type_id = body.room_type_id.or(group.room_type_id);
+    }
+
+    // Rooms picked up with a block code come out of the allotment held for the event
+    let block = match &body.block_code {
+        Some(code) => Some(
+            pick_up_from_block(
+                &mut tx,
+                code,
+                body.checkin_date,
+                body.checkout_date,
+                body.room_type_id,
+            )
+            .await
+            .map_err(|e| match e {
+                BlockError::Rejected(message) => bad_request(message),
+                BlockError::Database(e) => database_error(e),
+            })?,
+        ),
+        None => None,
+    };
+
+    if let Some(block) = &block {
+        body.room_type_id = Some(block.room_type_id);
+    }
+
+    let booked = book_room(
+        &data,
+        &mut tx,
+        &guest,
+        &body,
+        group.as_ref(),
+        block.as_ref().map(|block| block.id),
+    )
+    .await?;
+
+    tx.commit().await.map_err(database_error)?;
+
+    tracing::info!(
+        booking_id = booked.booking.id,
+        status = booked.booking.status,
+        "booking created"
+    );
+    record_booking_created(&booked.booking.booking_amount);
+
+    let booking_response = serde_json::json!({"status": "success", "data": booked});
+
+    Ok((StatusCode::CREATED, Json(booking_response)))
+}
+
+// A booking just made, with how it was priced
+#[derive(Serialize)]
+pub(super) struct BookedRoom {
+    pub booking: Booking,
+    pub quote: Quote,
+    pub display_quote: Option<Quote>,
+    pub deposit_request: Option<DepositRequest>,
+    // Rate of every night when priced from the room type
+    pub nightly_rates: Vec<NightlyRate>,
+}
+
+// Price and save one room booking in the transaction of the caller
+pub(super) async fn book_room(
+    data: &AppState,
+    tx: &mut PgConnection,
+    guest: &Guest,
+    body: &CreateBookingSchema,
+    group: Option<&ReservationGroup>,
+    allotment_block_id: Option<i32>,
+) -> Result<BookedRoom, (StatusCode, Json<serde_json::Value>)> {
+    let reservation_group_id = group.map(|group| group.id);
+
+    if body.checkout_date <= body.checkin_date {
+        return Err(bad_request(
+            "Check-out date must be after the check-in date".to_string(),
+        ));
+    }
+
+    validate_special_requests(body.special_requests.as_deref())?;
+    validate_guests(body.num_adults, body.num_children)?;
+
+    let nights = (body.checkout_date - body.checkin_date).num_days() as i32;
+
+    // Stays are priced from their room type, the guest never sets the amount
+    let room_type_id = body
+        .room_type_id
+        .ok_or_else(|| bad_request("A room type is required".to_string()))?;
+    let room_type = sqlx::query_as!(
+        RoomType,
+        "select * from room_type where id = $1 and active = true",
+        room_type_id
+    )
+    .fetch_optional(&data.db)
+    .await
+    .map_err(database_error)?
+    .ok_or_else(|| bad_request(format!("Room type with ID: {} not found", room_type_id)))?;
+
+    // Restrictions apply to every stay but the rooms of blocks staff set up,
+    // groups are made by guests themselves
+    if allotment_block_id.is_none() {
+        ensure_stay_allowed(
+            &mut *tx,
+            body.room_type_id,
+            body.checkin_date,
+            body.checkout_date,
+        )
+        .await?;
+    }
+
+    // Rooms of a block keep the base rate staff agreed with the organiser,
+    // the others, group rooms included, are priced night by night with the pricing rules
+    let nightly_rates = if allotment_block_id.is_none() {
+        price_stay(
+            &mut *tx,
+            &room_type,
+            body.checkin_date,
+            body.checkout_date,
+            None,
+        )
+        .await
+        .map_err(database_error)?
+    } else {
+        Vec::new()
+    };
+
+    let room_amount = if nightly_rates.is_empty() {
+        &room_type.base_rate * BigDecimal::from(nights)
+    } else {
+        stay_amount(&nightly_rates)
+    };
+
+    // A room offered to the guest from the waitlist is already held for them
+    let waitlist_hold = if reservation_group_id.is_none() && allotment_block_id.is_none() {
+        held_for_guest(
+            &mut *tx,
+            guest.id,
+            room_type.id,
+            body.checkin_date,
+            body.checkout_date,
+        )
+        .await
+        .map_err(database_error)?
+    } else {
+        None
+    };
+
+    if body.num_adults + body.num_children > room_type.max_occupancy {
+        return Err(bad_request(format!(
+            "A {} sleeps at most {} guests",
+            room_type.name, room_type.max_occupancy
+        )));
+    }
+
+    // Rooms picked up from a block, held by the group or held from the waitlist were
+    // already taken, the others come out of general availability
+    let held_by_group = group.is_some_and(|group| group.room_type_id == Some(room_type.id));
+    if allotment_block_id.is_none() && !held_by_group && waitlist_hold.is_none() {
+        sqlx::query!(
+            "select id from room_type where id = $1 for update",
+            room_type.id
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(database_error)?;
+
+        let available = available_rooms(
+            &mut *tx,
+            room_type.id,
+            body.checkin_date,
+            body.checkout_date,
+        )
+        .await
+        .map_err(database_error)?;
+        if available < 1 {
+            let error_response = serde_json::json!({
+                "status": "fail",
+                "message": format!(
+                    "No {} is available from {} to {}",
+                    room_type.name, body.checkin_date, body.checkout_date
+                )
+            });
+            return Err((StatusCode::CONFLICT, Json(error_response)));
+        }
+    }
+
+    let rate_plan = sqlx::query_as!(
+        RatePlan,
+        "select * from rate_plan where id = $1 and active = true",
+        body.rate_plan_id.unwrap_or(DEFAULT_RATE_PLAN_ID)
+    )
+    .fetch_optional(&data.db)
+    .await
+    .map_err(database_error)?
+    .ok_or_else(|| bad_request("Rate plan not found or no longer offered".to_string()))?;
+
+    // The promo code discount comes off the room amount, before taxes
+    let promo = match &body.promo_code {
+        Some(code) => Some(
+            apply_promo_code(
+                &mut *tx,
+                code,
+                &PromoStay {
+                    guest_id: guest.id,
+                    room_type_id: body.room_type_id,
+                    nights,
+                    room_amount: &room_amount,
+                },
+            )
+            .await
+            .map_err(|e| match e {
+                PromoError::Rejected(message) => bad_request(message),
+                PromoError::Database(e) => database_error(e),
+            })?,
+        ),
+        None => None,
+    };
+
+    let promo_discount = promo
+        .as_ref()
+        .map_or_else(BigDecimal::zero, |(_, discount)| discount.clone());
+
+    // Loyalty points pay for what is left of the room amount after the promo code
+    let points_discount = match body.redeem_points {
+        Some(points) => {
+            if points <= 0 {
+                return Err(bad_request("Points to redeem must be positive".to_string()));
+            }
+
+            let balance = lock_points_balance(&mut *tx, guest.id)
+                .await
+                .map_err(database_error)?;
+            if i64::from(points) > balance {
+                return Err(bad_request(format!(
+                    "Not enough loyalty points, your balance is {}",
+                    balance
+                )));
+            }
+
+            let value = points_value(points, data.env.loyalty.redeem_points_per_unit);
+            if value > &room_amount - &promo_discount {
+                return Err(bad_request(
+                    "The points are worth more than the booking amount".to_string(),
+                ));
+            }
+            value
+        }
+        None => BigDecimal::zero(),
+    };
+
+    let discount_amount = (&promo_discount + &points_discount).with_scale(2);
+    let booking_amount = (&room_amount - &discount_amount).with_scale(2);
+
+    // Taxes are added on top of the booking amount and posted with the room charge
+    let quote = quote_stay(
+        &data.db,
+        body.checkin_date,
+        body.checkout_date,
+        body.num_adults,
+        body.num_children,
+        &booking_amount,
+    )
+    .await
+    .map_err(database_error)?;
+
+    // Rate plans asking for a deposit keep the booking pending until it is paid
+    let deposit = deposit_amount(&rate_plan, &quote.total);
+    let status = if deposit.is_some() {
+        BOOKING_STATUS_PENDING
+    } else {
+        BOOKING_STATUS_CONFIRMED
+    };
+
+    // Guests with a preferred currency also see the quote converted, when a rate is configured
+    let display_quote = match &guest.preferred_currency {
+        Some(currency) if currency != &quote.currency => {
+            quote_in_currency(&data.db, &quote, currency)
+                .await
+                .map_err(database_error)?
+        }
+        _ => None,
+    };
+
     // Execute a SQL query to insert a new booking
-    let query_result = sqlx::query_as!(
+    let booking = sqlx::query_as!(
         Booking,
         "insert into booking 
             (
@@ -108,36 +502,89 @@ pub async fn create_booking_handler(
                 checkout_date, 
                 num_adults, 
                 num_children, 
-                booking_amount
+                booking_amount,
+                currency,
+                rate_plan_id,
+                status,
+                room_type_id,
+                discount_amount,
+                reservation_group_id,
+                allotment_block_id,
+                preferred_floor,
+                accessible_room,
+                near_elevator,
+                special_requests
             ) 
-        values ($1, $2, $3, $4, $5, $6, $7)
+        values ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18)
         returning *",
         &guest.id,
-        3,
+        PAYMENT_STATUS_UNPAID,
         &body.checkin_date,
         &body.checkout_date,
         &body.num_adults,
         &body.num_children,
-        &body.booking_amount
+        &booking_amount,
+        &quote.currency,
+        rate_plan.id,
+        status,
+        body.room_type_id,
+        discount_amount,
+        reservation_group_id,
+        allotment_block_id,
+        body.preferences.preferred_floor,
+        body.preferences.accessible_room.unwrap_or(false),
+        body.preferences.near_elevator.unwrap_or(false),
+        body.special_requests
     )
-    .fetch_one(&data.db)
-    .await;
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(database_error)?;
 
-    match query_result {
-        Ok(booking) => {
-            let booking_response = serde_json::json!({"status": "success", "data": serde_json::json!({
-                "booking": booking
-            })});
+    if let Some(entry) = &waitlist_hold {
+        mark_booked(&mut *tx, entry.id, booking.id)
+            .await
+            .map_err(database_error)?;
+    }
 
-            return Ok((StatusCode::CREATED, Json(booking_response)));
-        }
-        Err(e) => {
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(serde_json::json!({"status": "error", "message": format!("{:?}", e)})),
-            ));
-        }
+    log_price_adjustments(&mut *tx, booking.id, room_type.id, &nightly_rates)
+        .await
+        .map_err(database_error)?;
+
+    if let Some((promo, discount)) = &promo {
+        record_redemption(&mut *tx, promo, booking.id, guest.id, discount)
+            .await
+            .map_err(database_error)?;
     }
+
+    if let Some(points) = body.redeem_points {
+        redeem_points(&mut *tx, guest.id, booking.id, points)
+            .await
+            .map_err(database_error)?;
+    }
+
+    let deposit_request = match deposit {
+        Some(amount) => Some(
+            create_deposit_request(
+                &mut *tx,
+                booking.id,
+                &amount,
+                &booking.currency,
+                &quote.total,
+                deposit_due_at(&rate_plan, booking.checkin_date),
+            )
+            .await
+            .map_err(database_error)?,
+        ),
+        None => None,
+    };
+
+    Ok(BookedRoom {
+        booking,
+        quote,
+        display_quote,
+        deposit_request,
+        nightly_rates,
+    })
 }
 
 // Handler to update a booking for the guest
@@ -147,63 +594,333 @@ pub async fn update_booking_handler(
     Extension(guest): Extension<Guest>,
     Json(body): Json<UpdateBookingSchema>,
 ) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
-    let query_result = sqlx::query_as!(
+    validate_special_requests(body.special_requests.as_deref())?;
+
+    let mut tx = data.db.begin().await.map_err(database_error)?;
+
+    let booking = sqlx::query_as!(
         Booking,
-        "select * from booking where id = $1 and guest_id = $2",
+        "select * from booking where id = $1 and guest_id = $2 for update",
         id,
         &guest.id
     )
-    .fetch_one(&data.db)
-    .await;
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(database_error)?
+    .ok_or_else(|| booking_not_found(id))?;
 
-    if query_result.is_err() {
+    if booking.status == BOOKING_STATUS_EXPIRED {
         let error_response = serde_json::json!({
             "status": "fail",
-            "message": format!("Booking with ID: {} not found", id)
+            "message": format!("Booking with ID: {} has expired", id)
         });
-        return Err((StatusCode::NOT_FOUND, Json(error_response)));
+        return Err((StatusCode::CONFLICT, Json(error_response)));
     }
 
-    let now = chrono::Utc::now();
-    let booking = query_result.unwrap();
+    let checkin_date = body.checkin_date.unwrap_or(booking.checkin_date);
+    let checkout_date = body.checkout_date.unwrap_or(booking.checkout_date);
+    let num_adults = body.num_adults.unwrap_or(booking.num_adults);
+    let num_children = body.num_children.unwrap_or(booking.num_children);
+    validate_guests(num_adults, num_children)?;
 
-    let query_result = sqlx::query_as!(
+    // Taxes and the deposit depend on the dates and on the guests staying
+    let stay_changed = checkin_date != booking.checkin_date
+        || checkout_date != booking.checkout_date
+        || num_adults != booking.num_adults
+        || num_children != booking.num_children;
+
+    // Stays that started or ended keep their dates and guests
+    if stay_changed
+        && ![BOOKING_STATUS_PENDING, BOOKING_STATUS_CONFIRMED].contains(&booking.status.as_str())
+    {
+        let error_response = serde_json::json!({
+            "status": "fail",
+            "message": format!(
+                "Booking with ID: {} is {}, its dates and guests can't be changed",
+                id, booking.status
+            )
+        });
+        return Err((StatusCode::CONFLICT, Json(error_response)));
+    }
+
+    let booking = sqlx::query_as!(
         Booking,
         "update booking set 
-        checkin_date = $1, 
-        checkout_date = $2, 
-        num_adults = $3, 
-        num_children = $4, 
-        booking_amount = $5, 
-        updated_at = $6 
-        where id = $7 and guest_id = $8 
+        num_adults = $1, 
+        num_children = $2, 
+        preferred_floor = $3,
+        accessible_room = $4,
+        near_elevator = $5,
+        special_requests = $6,
+        updated_at = now() 
+        where id = $7
         returning *",
-        body.checkin_date.unwrap_or(booking.checkin_date),
-        body.checkout_date.unwrap_or(booking.checkout_date),
-        body.num_adults.unwrap_or(booking.num_adults),
-        body.num_children.unwrap_or(booking.num_children),
-        body.booking_amount.unwrap_or(booking.booking_amount),
-        now,
-        id,
-        &guest.id
+        num_adults,
+        num_children,
+        body.preferences.preferred_floor.or(booking.preferred_floor),
+        body.preferences
+            .accessible_room
+            .unwrap_or(booking.accessible_room),
+        body.preferences
+            .near_elevator
+            .unwrap_or(booking.near_elevator),
+        body.special_requests.or(booking.special_requests),
+        id
     )
-    .fetch_one(&data.db)
-    .await;
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(database_error)?;
 
-    match query_result {
-        Ok(booking) => {
-            let booking_response = serde_json::json!({"status": "success","data": serde_json::json!({
-                "booking": booking
-            })});
-            return Ok(Json(booking_response));
+    let booking = if stay_changed {
+        move_stay(&data, &mut tx, &booking, checkin_date, checkout_date).await?
+    } else {
+        booking
+    };
+
+    tx.commit().await.map_err(database_error)?;
+
+    let booking_response = serde_json::json!({"status": "success","data": serde_json::json!({
+        "booking": booking
+    })});
+    Ok(Json(booking_response))
+}
+
+// Move a booking to new dates, or price it again after its guests changed, in the
+// transaction of the caller
+//
+// New dates are held to the restrictions and availability like a new booking, the stay
+// is priced again with the pricing rules and its taxes and deposit follow the new amount.
+// Rooms picked up from a block were taken with the block, they keep its base rate.
+pub(super) async fn move_stay(
+    data: &AppState,
+    tx: &mut PgConnection,
+    booking: &Booking,
+    checkin_date: NaiveDate,
+    checkout_date: NaiveDate,
+) -> Result<Booking, (StatusCode, Json<serde_json::Value>)> {
+    if checkout_date <= checkin_date {
+        return Err(bad_request(
+            "Check-out date must be after the check-in date".to_string(),
+        ));
+    }
+
+    let from_block = booking.allotment_block_id.is_some();
+    let dates_changed =
+        checkin_date != booking.checkin_date || checkout_date != booking.checkout_date;
+    if dates_changed && !from_block {
+        ensure_stay_allowed(&mut *tx, booking.room_type_id, checkin_date, checkout_date).await?;
+    }
+
+    // The room type row is locked until the booking is saved, like for a new booking
+    let room_type = match booking.room_type_id {
+        Some(room_type_id) => Some(
+            sqlx::query_as!(
+                RoomType,
+                "select * from room_type where id = $1 for update",
+                room_type_id
+            )
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(database_error)?,
+        ),
+        None => None,
+    };
+
+    if let Some(room_type) = &room_type {
+        if booking.num_adults + booking.num_children > room_type.max_occupancy {
+            return Err(bad_request(format!(
+                "A {} sleeps at most {} guests",
+                room_type.name, room_type.max_occupancy
+            )));
+        }
+    }
+
+    let nightly_rates = match &room_type {
+        Some(room_type) if !from_block => {
+            let nights = night_occupancy(
+                &mut *tx,
+                room_type.id,
+                checkin_date,
+                checkout_date,
+                Some(booking.id),
+            )
+            .await
+            .map_err(database_error)?;
+            if rooms_left(&nights) < 1 {
+                let error_response = serde_json::json!({
+                    "status": "fail",
+                    "message": format!(
+                        "No {} is available from {} to {}",
+                        room_type.name, checkin_date, checkout_date
+                    )
+                });
+                return Err((StatusCode::CONFLICT, Json(error_response)));
+            }
+
+            price_stay(
+                &mut *tx,
+                room_type,
+                checkin_date,
+                checkout_date,
+                Some(booking.id),
+            )
+            .await
+            .map_err(database_error)?
         }
-        Err(err) => {
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(serde_json::json!({"status": "error", "message": format!("{:?}", err)})),
-            ))
+        _ => Vec::new(),
+    };
+
+    // The discount given when booking still comes off the new room amount
+    let booking_amount = match &room_type {
+        Some(room_type) => {
+            let room_amount = if nightly_rates.is_empty() {
+                &room_type.base_rate * BigDecimal::from((checkout_date - checkin_date).num_days())
+            } else {
+                stay_amount(&nightly_rates)
+            };
+            (room_amount - &booking.discount_amount)
+                .max(BigDecimal::zero())
+                .with_scale(2)
         }
+        None => booking.booking_amount.clone(),
+    };
+
+    let quote = quote_stay(
+        &data.db,
+        checkin_date,
+        checkout_date,
+        booking.num_adults,
+        booking.num_children,
+        &booking_amount,
+    )
+    .await
+    .map_err(database_error)?;
+
+    let rate_plan = sqlx::query_as!(
+        RatePlan,
+        "select * from rate_plan where id = $1",
+        booking.rate_plan_id
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(database_error)?;
+
+    revise_deposit_request(
+        &mut *tx,
+        booking.id,
+        deposit_amount(&rate_plan, &quote.total).as_ref(),
+        &quote.total,
+    )
+    .await
+    .map_err(database_error)?;
+
+    let moved = sqlx::query_as!(
+        Booking,
+        "update booking set
+        checkin_date = $1,
+        checkout_date = $2,
+        booking_amount = $3,
+        updated_at = now()
+        where id = $4
+        returning *",
+        checkin_date,
+        checkout_date,
+        booking_amount,
+        booking.id
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(database_error)?;
+
+    if let Some(room_type) = &room_type {
+        log_price_adjustments(&mut *tx, moved.id, room_type.id, &nightly_rates)
+            .await
+            .map_err(database_error)?;
+    }
+
+    // A room given before arrival is let go when the new dates clash with another stay,
+    // one is picked again later
+    if ![BOOKING_STATUS_PENDING, BOOKING_STATUS_CONFIRMED].contains(&moved.status.as_str())
+        || room_still_free(&mut *tx, &moved)
+            .await
+            .map_err(database_error)?
+    {
+        return Ok(moved);
+    }
+
+    let moved = sqlx::query_as!(
+        Booking,
+        "update booking set room_id = null, updated_at = now() where id = $1 returning *",
+        moved.id
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(database_error)?;
+
+    tracing::info!(booking_id = moved.id, "assigned room released");
+
+    Ok(moved)
+}
+
+// Guest counts taxes are charged on, a room is never booked without an adult
+fn validate_guests(
+    num_adults: i32,
+    num_children: i32,
+) -> Result<(), (StatusCode, Json<serde_json::Value>)> {
+    if num_adults < 1 {
+        return Err(bad_request("A stay needs at least one adult".to_string()));
+    }
+
+    if num_children < 0 {
+        return Err(bad_request(
+            "Number of children can't be negative".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+// Special requests are kept to what fits on the front desk lists
+fn validate_special_requests(
+    special_requests: Option<&str>,
+) -> Result<(), (StatusCode, Json<serde_json::Value>)> {
+    if special_requests.is_some_and(|requests| requests.chars().count() > MAX_SPECIAL_REQUESTS_LEN)
+    {
+        return Err(bad_request(format!(
+            "Special requests can't be longer than {} characters",
+            MAX_SPECIAL_REQUESTS_LEN
+        )));
+    }
+
+    Ok(())
+}
+
+// Reject a stay breaking the restrictions set on its dates, every broken rule is listed
+pub(super) async fn ensure_stay_allowed<'e>(
+    executor: impl PgExecutor<'e>,
+    room_type_id: Option<i32>,
+    checkin_date: NaiveDate,
+    checkout_date: NaiveDate,
+) -> Result<(), (StatusCode, Json<serde_json::Value>)> {
+    let violations =
+        stay_restriction_violations(executor, room_type_id, checkin_date, checkout_date)
+            .await
+            .map_err(database_error)?;
+
+    if violations.is_empty() {
+        return Ok(());
     }
+
+    let messages: Vec<&str> = violations
+        .iter()
+        .map(|violation| violation.message.as_str())
+        .collect();
+    let error_response = serde_json::json!({
+        "status": "fail",
+        "message": format!("The stay can't be booked: {}", messages.join("; ")),
+        "violations": violations
+    });
+    Err((StatusCode::BAD_REQUEST, Json(error_response)))
 }
 
 // Handler to delete a booking for the guest
@@ -212,23 +929,79 @@ pub async fn delete_booking_handler(
     State(data): State<Arc<AppState>>,
     Extension(guest): Extension<Guest>,
 ) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
-    let rows_affected = sqlx::query!(
-        "delete from booking where id = $1 and guest_id = $2",
+    let mut tx = data.db.begin().await.map_err(database_error)?;
+
+    let status = sqlx::query_scalar!(
+        "select status from booking where id = $1 and guest_id = $2 for update",
         id,
         &guest.id
     )
-    .execute(&data.db)
+    .fetch_optional(&mut *tx)
     .await
-    .unwrap()
-    .rows_affected();
+    .map_err(database_error)?
+    .ok_or_else(|| booking_not_found(id))?;
 
-    if rows_affected == 0 {
+    // Stays that started are kept with their folio and payments
+    if ![BOOKING_STATUS_PENDING, BOOKING_STATUS_CONFIRMED].contains(&status.as_str()) {
         let error_response = serde_json::json!({
             "status": "fail",
-            "message": format!("Booking with ID: {} not found", id),
+            "message": format!("Booking with ID: {} is {}, it can't be cancelled", id, status)
         });
-        return Err((StatusCode::NOT_FOUND, Json(error_response)));
+        return Err((StatusCode::CONFLICT, Json(error_response)));
     }
 
+    let deleted_amount = cancel_booking(&mut tx, id).await?;
+
+    tx.commit().await.map_err(database_error)?;
+
+    tracing::info!(booking_id = id, "booking cancelled");
+    record_booking_cancelled(&deleted_amount);
+
+    // The room given back may be what a guest on the waitlist is waiting for
+    notify_waitlist(&data).await;
+
     Ok(StatusCode::NO_CONTENT)
 }
+
+// Delete the booking in the transaction of the caller, giving back the points and voucher balance spent on it
+pub(super) async fn cancel_booking(
+    tx: &mut PgConnection,
+    id: i32,
+) -> Result<BigDecimal, (StatusCode, Json<serde_json::Value>)> {
+    reverse_redemptions(&mut *tx, id)
+        .await
+        .map_err(database_error)?;
+    refund_booking_redemptions(&mut *tx, id)
+        .await
+        .map_err(database_error)?;
+
+    let deleted_amount = sqlx::query_scalar!(
+        "delete from booking where id = $1 returning booking_amount",
+        id
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| {
+        // Invoiced bookings are kept for the accounting records
+        let is_referenced = e
+            .as_database_error()
+            .and_then(|e| e.code())
+            .is_some_and(|code| code == "23503");
+
+        if is_referenced {
+            let error_response = serde_json::json!({
+                "status": "fail",
+                "message": format!("Booking with ID: {} has been invoiced and can't be deleted", id),
+            });
+            return (StatusCode::CONFLICT, Json(error_response));
+        }
+
+        let error_response = serde_json::json!({
+            "status": "error",
+            "message": format!("Database error: {}", e),
+        });
+        (StatusCode::INTERNAL_SERVER_ERROR, Json(error_response))
+    })?;
+
+    deleted_amount.ok_or_else(|| booking_not_found(id))
+}
@@ -0,0 +1,125 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Extension, Json,
+};
+use bigdecimal::{BigDecimal, Zero};
+
+use crate::{
+    currency::{base_currency, is_currency_code},
+    models::{ExchangeRate, Guest},
+    schema::ExchangeRateSchema,
+    AppState,
+};
+
+use super::util::{bad_request, database_error};
+
+// Handler for admins to list the rates from the property currency
+pub async fn list_exchange_rates_handler(
+    State(data): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let base_currency = base_currency(&data.db).await.map_err(database_error)?;
+
+    let rates = sqlx::query_as!(
+        ExchangeRate,
+        "select * from exchange_rate where base_currency = $1 order by currency",
+        base_currency
+    )
+    .fetch_all(&data.db)
+    .await
+    .map_err(database_error)?;
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "base_currency": base_currency,
+        "results": rates.len(),
+        "exchange_rates": rates
+    });
+
+    Ok(Json(json_response))
+}
+
+// Handler for admins to set the rate of a currency, replacing the previous one
+pub async fn put_exchange_rate_handler(
+    State(data): State<Arc<AppState>>,
+    Path(currency): Path<String>,
+    Extension(admin): Extension<Guest>,
+    Json(body): Json<ExchangeRateSchema>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    if !is_currency_code(&currency) {
+        return Err(bad_request(
+            "Currency must be an ISO 4217 code, e.g. EUR".to_string(),
+        ));
+    }
+
+    if body.rate <= BigDecimal::zero() {
+        return Err(bad_request("Exchange rate must be positive".to_string()));
+    }
+
+    let base_currency = base_currency(&data.db).await.map_err(database_error)?;
+    if currency == base_currency {
+        return Err(bad_request(format!(
+            "{} is the property currency",
+            base_currency
+        )));
+    }
+
+    let rate = sqlx::query_as!(
+        ExchangeRate,
+        "insert into exchange_rate (base_currency, currency, rate, updated_by)
+        values ($1, $2, $3, $4)
+        on conflict (base_currency, currency) do update set
+        rate = excluded.rate,
+        updated_by = excluded.updated_by,
+        updated_at = now()
+        returning *",
+        base_currency,
+        currency,
+        body.rate,
+        admin.id
+    )
+    .fetch_one(&data.db)
+    .await
+    .map_err(database_error)?;
+
+    tracing::info!(currency = rate.currency, "exchange rate updated");
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "data": serde_json::json!({
+            "exchange_rate": rate
+        })
+    });
+
+    Ok(Json(json_response))
+}
+
+// Handler for admins to stop supporting a currency
+pub async fn delete_exchange_rate_handler(
+    State(data): State<Arc<AppState>>,
+    Path(currency): Path<String>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let base_currency = base_currency(&data.db).await.map_err(database_error)?;
+
+    let deleted = sqlx::query!(
+        "delete from exchange_rate where base_currency = $1 and currency = $2",
+        base_currency,
+        currency
+    )
+    .execute(&data.db)
+    .await
+    .map_err(database_error)?;
+
+    if deleted.rows_affected() == 0 {
+        let error_response = serde_json::json!({
+            "status": "fail",
+            "message": format!("Exchange rate for {} not found", currency)
+        });
+        return Err((StatusCode::NOT_FOUND, Json(error_response)));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,317 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Extension, Json,
+};
+use bigdecimal::{BigDecimal, Zero};
+
+use crate::{
+    deposit::settle_deposit,
+    folio::{
+        load_folio, post_charge, refresh_payment_status, void_line, NewCharge, LINE_TYPES,
+        PAYMENT_METHODS, PAYMENT_METHOD_GIFT_VOUCHER,
+    },
+    gift_voucher::{redeem_voucher, VoucherError},
+    models::{FolioLine, Guest, Payment},
+    schema::{PostChargeSchema, RecordPaymentSchema, VoidChargeSchema},
+    AppState,
+};
+
+use super::util::{
+    bad_request, booking_not_found, database_error, ensure_booking_exists,
+    ensure_guest_booking_exists,
+};
+
+// Handler to get the folio of one of the guest's bookings
+pub async fn get_folio_handler(
+    State(data): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Extension(guest): Extension<Guest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    ensure_guest_booking_exists(&data, id, guest.id).await?;
+
+    let mut conn = data.db.acquire().await.map_err(database_error)?;
+    let folio = load_folio(&mut conn, id).await.map_err(database_error)?;
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "data": serde_json::json!({
+            "folio": folio
+        })
+    });
+
+    Ok(Json(json_response))
+}
+
+// Handler for staff to get the folio of any booking
+pub async fn staff_get_folio_handler(
+    State(data): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    ensure_booking_exists(&data, id).await?;
+
+    let mut conn = data.db.acquire().await.map_err(database_error)?;
+    let folio = load_folio(&mut conn, id).await.map_err(database_error)?;
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "data": serde_json::json!({
+            "folio": folio
+        })
+    });
+
+    Ok(Json(json_response))
+}
+
+// Handler for staff to post a charge on the folio of a booking
+pub async fn post_charge_handler(
+    State(data): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Extension(staff): Extension<Guest>,
+    Json(body): Json<PostChargeSchema>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    if !LINE_TYPES.contains(&body.line_type.as_str()) {
+        return Err(bad_request(format!(
+            "Invalid line type, expected one of: {}",
+            LINE_TYPES.join(", ")
+        )));
+    }
+
+    let quantity = body.quantity.unwrap_or(1);
+    if quantity <= 0 {
+        return Err(bad_request("Quantity must be positive".to_string()));
+    }
+
+    let negative_tax = body
+        .tax_amount
+        .as_ref()
+        .is_some_and(|tax_amount| tax_amount < &BigDecimal::zero());
+    if body.unit_price < BigDecimal::zero() || negative_tax {
+        return Err(bad_request(
+            "Unit price and tax amount can't be negative".to_string(),
+        ));
+    }
+
+    ensure_booking_exists(&data, id).await?;
+
+    let mut tx = data.db.begin().await.map_err(database_error)?;
+
+    let (line, tax_lines) = post_charge(
+        &mut tx,
+        NewCharge {
+            booking_id: id,
+            line_type: body.line_type,
+            description: body.description,
+            quantity,
+            unit_price: body.unit_price,
+            tax_amount: body.tax_amount,
+            posted_by: Some(staff.id),
+        },
+    )
+    .await
+    .map_err(database_error)?;
+
+    tx.commit().await.map_err(database_error)?;
+
+    tracing::info!(booking_id = id, folio_line_id = line.id, "charge posted");
+
+    let folio = refresh_payment_status(&data.db, id)
+        .await
+        .map_err(database_error)?;
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "data": serde_json::json!({
+            "line": line,
+            "tax_lines": tax_lines,
+            "balance": folio.balance,
+        })
+    });
+
+    Ok((StatusCode::CREATED, Json(json_response)))
+}
+
+// Handler for staff to void a charge, the line is kept for the audit trail
+pub async fn void_charge_handler(
+    State(data): State<Arc<AppState>>,
+    Path((id, line_id)): Path<(i32, i32)>,
+    Extension(staff): Extension<Guest>,
+    Json(body): Json<VoidChargeSchema>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let line = sqlx::query_as!(
+        FolioLine,
+        "select * from folio_line where id = $1 and booking_id = $2",
+        line_id,
+        id
+    )
+    .fetch_optional(&data.db)
+    .await
+    .map_err(database_error)?
+    .ok_or_else(|| {
+        let error_response = serde_json::json!({
+            "status": "fail",
+            "message": format!("Folio line with ID: {} not found", line_id)
+        });
+        (StatusCode::NOT_FOUND, Json(error_response))
+    })?;
+
+    let already_voided = || {
+        let error_response = serde_json::json!({
+            "status": "fail",
+            "message": format!("Folio line with ID: {} is already voided", line_id)
+        });
+        (StatusCode::CONFLICT, Json(error_response))
+    };
+
+    if line.voided_at.is_some() {
+        return Err(already_voided());
+    }
+
+    // Voiding a charge also voids the tax computed for it
+    let (voided, tax_lines): (Vec<FolioLine>, Vec<FolioLine>) =
+        void_line(&data.db, line_id, staff.id, &body.reason)
+            .await
+            .map_err(database_error)?
+            .into_iter()
+            .partition(|voided| voided.id == line_id);
+
+    // Only the update that voided the line counts, a concurrent void got there first otherwise
+    let Some(voided) = voided.into_iter().next() else {
+        return Err(already_voided());
+    };
+
+    tracing::info!(
+        booking_id = id,
+        folio_line_id = line_id,
+        voided_tax_lines = tax_lines.len(),
+        "charge voided"
+    );
+
+    let folio = refresh_payment_status(&data.db, id)
+        .await
+        .map_err(database_error)?;
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "data": serde_json::json!({
+            "line": voided,
+            "tax_lines": tax_lines,
+            "balance": folio.balance,
+        })
+    });
+
+    Ok(Json(json_response))
+}
+
+// Handler for staff to record a payment against the folio of a booking
+pub async fn record_payment_handler(
+    State(data): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Extension(staff): Extension<Guest>,
+    Json(body): Json<RecordPaymentSchema>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    if !PAYMENT_METHODS.contains(&body.method.as_str()) {
+        return Err(bad_request(format!(
+            "Invalid payment method, expected one of: {}",
+            PAYMENT_METHODS.join(", ")
+        )));
+    }
+
+    if body.amount <= BigDecimal::zero() {
+        return Err(bad_request("Payment amount must be positive".to_string()));
+    }
+
+    let voucher_code = match (body.method.as_str(), &body.voucher_code) {
+        (PAYMENT_METHOD_GIFT_VOUCHER, Some(code)) => Some(code.clone()),
+        (PAYMENT_METHOD_GIFT_VOUCHER, None) => {
+            return Err(bad_request(
+                "A voucher code is required to pay with a gift voucher".to_string(),
+            ))
+        }
+        _ => None,
+    };
+
+    let currency = sqlx::query_scalar!("select currency from booking where id = $1", id)
+        .fetch_optional(&data.db)
+        .await
+        .map_err(database_error)?
+        .ok_or_else(|| booking_not_found(id))?;
+
+    // Foreign currency payments are converted before they are recorded
+    if body.currency.as_ref().is_some_and(|c| c != &currency) {
+        return Err(bad_request(format!(
+            "Payments for this booking are recorded in {}",
+            currency
+        )));
+    }
+
+    let mut tx = data.db.begin().await.map_err(database_error)?;
+
+    let payment = sqlx::query_as!(
+        Payment,
+        "insert into payment (booking_id, amount, method, reference, recorded_by, currency)
+        values ($1, $2, $3, $4, $5, $6)
+        returning *",
+        id,
+        body.amount,
+        body.method,
+        voucher_code.clone().or(body.reference),
+        staff.id,
+        currency
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(database_error)?;
+
+    // The voucher balance and the payment are recorded together or not at all
+    let gift_voucher = match voucher_code {
+        Some(code) => Some(
+            redeem_voucher(
+                &mut tx,
+                &code,
+                &body.amount,
+                &currency,
+                id,
+                payment.id,
+                staff.id,
+            )
+            .await
+            .map_err(|e| match e {
+                VoucherError::Rejected(message) => bad_request(message),
+                VoucherError::Database(e) => database_error(e),
+            })?,
+        ),
+        None => None,
+    };
+
+    tx.commit().await.map_err(database_error)?;
+
+    tracing::info!(booking_id = id, payment_id = payment.id, "payment recorded");
+
+    let folio = refresh_payment_status(&data.db, id)
+        .await
+        .map_err(database_error)?;
+
+    // Paying the deposit confirms a pending booking
+    let deposit_request = settle_deposit(&data.db, id, &folio.payments_total)
+        .await
+        .map_err(database_error)?;
+    if deposit_request.is_some() {
+        tracing::info!(booking_id = id, "deposit paid, booking confirmed");
+    }
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "data": serde_json::json!({
+            "payment": payment,
+            "balance": folio.balance,
+            "deposit_request": deposit_request,
+            "gift_voucher": gift_voucher,
+        })
+    });
+
+    Ok((StatusCode::CREATED, Json(json_response)))
+}
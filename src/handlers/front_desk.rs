@@ -0,0 +1,388 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use bigdecimal::{BigDecimal, Zero};
+
+use crate::{
+    business_date::current_business_date,
+    folio::load_folio,
+    front_desk::{front_desk_csv, front_desk_list, FRONT_DESK_LISTS},
+    housekeeping::{release_room, release_room_after_check_out, RoomError},
+    invoice::issue_invoice,
+    loyalty::earn_points,
+    models::{
+        Booking, BOOKING_STATUS_CHECKED_IN, BOOKING_STATUS_CHECKED_OUT, BOOKING_STATUS_CONFIRMED,
+        BOOKING_STATUS_PENDING,
+    },
+    room_assignment::{assign_room, suggest_rooms},
+    schema::{AssignRoomSchema, CheckInSchema, FrontDeskListOptions},
+    AppState,
+};
+
+use super::util::{bad_request, booking_not_found, database_error};
+
+// Handler for staff to check the guest in, the deposit and the balance due must have been paid
+pub async fn check_in_handler(
+    State(data): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    body: Option<Json<CheckInSchema>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let room_id = body.and_then(|Json(body)| body.room_id);
+
+    let mut tx = data.db.begin().await.map_err(database_error)?;
+
+    let booking = sqlx::query_as!(
+        Booking,
+        "select * from booking where id = $1 for update",
+        id
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(database_error)?
+    .ok_or_else(|| booking_not_found(id))?;
+
+    if booking.status != BOOKING_STATUS_CONFIRMED {
+        return Err(invalid_transition(&data, id, "checked in").await);
+    }
+
+    // The rest of a stay quoted with a deposit is due at check-in, like anything already on the folio
+    let folio = load_folio(&mut tx, id).await.map_err(database_error)?;
+    let stay_total = sqlx::query_scalar!(
+        "select stay_total from deposit_request where booking_id = $1",
+        id
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(database_error)?;
+
+    let quote_due = stay_total
+        .map(|stay_total| stay_total - &folio.payments_total)
+        .unwrap_or_else(BigDecimal::zero);
+    let balance_due = quote_due.max(folio.balance.clone()).with_scale(2);
+    if balance_due > BigDecimal::zero() {
+        let error_response = serde_json::json!({
+            "status": "fail",
+            "message": format!(
+                "A balance of {} {} is due before check-in",
+                balance_due, folio.currency
+            ),
+            "balance_due": balance_due
+        });
+        return Err((StatusCode::CONFLICT, Json(error_response)));
+    }
+
+    // The room given to the guest must be ready and free, the best one is picked unless the desk chose it
+    let room = if room_id.is_some() || booking.room_type_id.is_some() {
+        Some(
+            assign_room(&mut tx, &booking, room_id, true)
+                .await
+                .map_err(room_error)?,
+        )
+    } else {
+        None
+    };
+    let room_id = room.as_ref().map(|room| room.id);
+
+    let booking = sqlx::query_as!(
+        Booking,
+        "update booking set
+        status = $1,
+        checked_in_at = now(),
+        room_id = coalesce($2, room_id),
+        maintenance_ticket_id = case when $2::int is null then maintenance_ticket_id end,
+        updated_at = now()
+        where id = $3
+        returning *",
+        BOOKING_STATUS_CHECKED_IN,
+        room_id,
+        id
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(database_error)?;
+
+    tx.commit().await.map_err(database_error)?;
+
+    tracing::info!(
+        booking_id = id,
+        room_id = booking.room_id,
+        "guest checked in"
+    );
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "data": serde_json::json!({
+            "booking": booking,
+            "room": room
+        })
+    });
+
+    Ok(Json(json_response))
+}
+
+// Handler for staff to get the arrivals, departures, in-house guests or no-shows of a day
+pub async fn front_desk_list_handler(
+    State(data): State<Arc<AppState>>,
+    Path(list): Path<String>,
+    opts: Option<Query<FrontDeskListOptions>>,
+) -> Result<Response, (StatusCode, Json<serde_json::Value>)> {
+    let Query(opts) = opts.unwrap_or_default();
+
+    if !FRONT_DESK_LISTS.contains(&list.as_str()) {
+        let error_response = serde_json::json!({
+            "status": "fail",
+            "message": format!(
+                "Unknown list {}, expected one of: {}",
+                list,
+                FRONT_DESK_LISTS.join(", ")
+            )
+        });
+        return Err((StatusCode::NOT_FOUND, Json(error_response)));
+    }
+
+    let date = match opts.date {
+        Some(date) => date,
+        None => current_business_date(&data.db)
+            .await
+            .map_err(database_error)?,
+    };
+
+    let entries = front_desk_list(&data.db, &list, date)
+        .await
+        .map_err(database_error)?;
+
+    match opts.format.as_deref().unwrap_or("json") {
+        "json" => {
+            let json_response = serde_json::json!({
+                "status": "success",
+                "list": list,
+                "date": date,
+                "results": entries.len(),
+                "entries": entries
+            });
+            Ok(Json(json_response).into_response())
+        }
+        "csv" => Ok((
+            [
+                (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+                (
+                    header::CONTENT_DISPOSITION,
+                    format!("attachment; filename=\"{}-{}.csv\"", list, date),
+                ),
+            ],
+            front_desk_csv(&entries),
+        )
+            .into_response()),
+        _ => Err(bad_request(
+            "Invalid format, expected one of: json, csv".to_string(),
+        )),
+    }
+}
+
+// Handler for staff to see the rooms the booking can be given, best first
+pub async fn room_suggestions_handler(
+    State(data): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let mut conn = data.db.acquire().await.map_err(database_error)?;
+
+    let booking = sqlx::query_as!(Booking, "select * from booking where id = $1", id)
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(database_error)?
+        .ok_or_else(|| booking_not_found(id))?;
+
+    let suggestions = suggest_rooms(&mut conn, &booking)
+        .await
+        .map_err(database_error)?;
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "results": suggestions.len(),
+        "suggestions": suggestions
+    });
+
+    Ok(Json(json_response))
+}
+
+// Handler for staff to give the booking a room ahead of arrival or move an in-house guest,
+// the best room is picked unless one is given
+pub async fn assign_room_handler(
+    State(data): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    body: Option<Json<AssignRoomSchema>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let room_id = body.and_then(|Json(body)| body.room_id);
+
+    let mut tx = data.db.begin().await.map_err(database_error)?;
+
+    let booking = sqlx::query_as!(
+        Booking,
+        "select * from booking where id = $1 for update",
+        id
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(database_error)?
+    .ok_or_else(|| booking_not_found(id))?;
+
+    // A guest already in the house can only move to a room ready for them
+    let in_house = booking.status == BOOKING_STATUS_CHECKED_IN;
+    if !in_house
+        && ![BOOKING_STATUS_PENDING, BOOKING_STATUS_CONFIRMED].contains(&booking.status.as_str())
+    {
+        return Err(invalid_transition(&data, id, "given a room").await);
+    }
+
+    let room = assign_room(&mut tx, &booking, room_id, in_house)
+        .await
+        .map_err(room_error)?;
+
+    let updated = sqlx::query_as!(
+        Booking,
+        "update booking set
+        room_id = $1,
+        maintenance_ticket_id = null,
+        updated_at = now()
+        where id = $2
+        returning *",
+        room.id,
+        id
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(database_error)?;
+
+    // The room left behind needs cleaning
+    let housekeeping_task = match booking.room_id.filter(|old| in_house && *old != room.id) {
+        Some(old_room_id) => Some(
+            release_room(
+                &mut tx,
+                old_room_id,
+                id,
+                &format!("Guest moved to room {}", room.room_number),
+            )
+            .await
+            .map_err(database_error)?,
+        ),
+        None => None,
+    };
+
+    tx.commit().await.map_err(database_error)?;
+
+    tracing::info!(
+        booking_id = id,
+        room_id = room.id,
+        moved_from = booking.room_id,
+        "room assigned"
+    );
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "data": serde_json::json!({
+            "booking": updated,
+            "room": room,
+            "housekeeping_task": housekeeping_task
+        })
+    });
+
+    Ok(Json(json_response))
+}
+
+// Handler for staff to check the guest out, completing the stay
+pub async fn check_out_handler(
+    State(data): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let mut tx = data.db.begin().await.map_err(database_error)?;
+
+    let booking = sqlx::query_as!(
+        Booking,
+        "update booking set
+        status = $1,
+        checked_out_at = now(),
+        updated_at = now()
+        where id = $2 and status = $3
+        returning *",
+        BOOKING_STATUS_CHECKED_OUT,
+        id,
+        BOOKING_STATUS_CHECKED_IN
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(database_error)?;
+
+    let Some(booking) = booking else {
+        return Err(invalid_transition(&data, id, "checked out").await);
+    };
+
+    // Completed stays earn loyalty points
+    let loyalty_entry = earn_points(&mut tx, &booking, data.env.loyalty.earn_points_per_unit)
+        .await
+        .map_err(database_error)?;
+
+    // The room is left dirty for housekeeping
+    let housekeeping_task = release_room_after_check_out(&mut tx, &booking)
+        .await
+        .map_err(database_error)?;
+
+    // The guest leaves with the invoice of the stay
+    let invoice = issue_invoice(&mut tx, id).await.map_err(database_error)?;
+
+    tx.commit().await.map_err(database_error)?;
+
+    tracing::info!(booking_id = id, "guest checked out");
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "data": serde_json::json!({
+            "booking": booking,
+            "loyalty_entry": loyalty_entry,
+            "housekeeping_task": housekeeping_task,
+            "invoice": invoice
+        })
+    });
+
+    Ok(Json(json_response))
+}
+
+// Util function to explain why the booking can't move to the next status
+async fn invalid_transition(
+    data: &AppState,
+    id: i32,
+    action: &str,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let status = sqlx::query_scalar!("select status from booking where id = $1", id)
+        .fetch_optional(&data.db)
+        .await;
+
+    match status {
+        Ok(Some(status)) => {
+            let error_response = serde_json::json!({
+                "status": "fail",
+                "message": format!("Booking with ID: {} is {} and can't be {}", id, status, action)
+            });
+            (StatusCode::CONFLICT, Json(error_response))
+        }
+        Ok(None) => booking_not_found(id),
+        Err(e) => database_error(e),
+    }
+}
+
+pub(super) fn room_error(e: RoomError) -> (StatusCode, Json<serde_json::Value>) {
+    match e {
+        RoomError::Rejected(message) => {
+            let error_response = serde_json::json!({
+                "status": "fail",
+                "message": message
+            });
+            (StatusCode::CONFLICT, Json(error_response))
+        }
+        RoomError::Database(e) => database_error(e),
+    }
+}
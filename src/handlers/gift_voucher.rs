@@ -0,0 +1,140 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Extension, Json,
+};
+use bigdecimal::{BigDecimal, Zero};
+
+use crate::{
+    currency::base_currency,
+    folio::{PAYMENT_METHODS, PAYMENT_METHOD_GIFT_VOUCHER},
+    gift_voucher::{issue_voucher, normalize_code, voucher_transactions, NewVoucher},
+    models::{GiftVoucher, Guest},
+    schema::PurchaseGiftVoucherSchema,
+    AppState,
+};
+
+use super::util::{bad_request, database_error};
+
+// Handler for guests to list the gift vouchers they bought
+pub async fn list_gift_vouchers_handler(
+    State(data): State<Arc<AppState>>,
+    Extension(guest): Extension<Guest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let gift_vouchers = sqlx::query_as!(
+        GiftVoucher,
+        "select * from gift_voucher where purchased_by = $1 order by id desc",
+        guest.id
+    )
+    .fetch_all(&data.db)
+    .await
+    .map_err(database_error)?;
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "results": gift_vouchers.len(),
+        "gift_vouchers": gift_vouchers
+    });
+
+    Ok(Json(json_response))
+}
+
+// Handler for staff to sell a gift voucher at the front desk, in the property currency
+// Vouchers are only issued once the desk has taken the payment for them
+pub async fn staff_sell_gift_voucher_handler(
+    State(data): State<Arc<AppState>>,
+    Extension(staff): Extension<Guest>,
+    Json(body): Json<PurchaseGiftVoucherSchema>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    // A voucher can't be bought with another voucher
+    let methods: Vec<&str> = PAYMENT_METHODS
+        .iter()
+        .copied()
+        .filter(|method| *method != PAYMENT_METHOD_GIFT_VOUCHER)
+        .collect();
+
+    let method = match &body.method {
+        Some(method) if methods.contains(&method.as_str()) => method.clone(),
+        _ => {
+            return Err(bad_request(format!(
+                "Invalid payment method, expected one of: {}",
+                methods.join(", ")
+            )))
+        }
+    };
+
+    if body.amount <= BigDecimal::zero() {
+        return Err(bad_request(
+            "Gift voucher amount must be positive".to_string(),
+        ));
+    }
+
+    let currency = base_currency(&data.db).await.map_err(database_error)?;
+
+    let gift_voucher = issue_voucher(
+        &data.db,
+        NewVoucher {
+            amount: body.amount,
+            currency,
+            purchased_by: Some(staff.id),
+            recipient_name: body.recipient_name,
+            recipient_email: body.recipient_email,
+            message: body.message,
+            method,
+            performed_by: Some(staff.id),
+            validity_days: data.env.gift_voucher_validity_days,
+        },
+    )
+    .await
+    .map_err(database_error)?;
+
+    tracing::info!(gift_voucher_id = gift_voucher.id, "gift voucher sold");
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "data": serde_json::json!({
+            "gift_voucher": gift_voucher
+        })
+    });
+
+    Ok((StatusCode::CREATED, Json(json_response)))
+}
+
+// Handler for staff to look up a gift voucher with its ledger
+pub async fn staff_get_gift_voucher_handler(
+    State(data): State<Arc<AppState>>,
+    Path(code): Path<String>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let gift_voucher = sqlx::query_as!(
+        GiftVoucher,
+        "select * from gift_voucher where code = $1",
+        normalize_code(&code)
+    )
+    .fetch_optional(&data.db)
+    .await
+    .map_err(database_error)?
+    .ok_or_else(|| {
+        let error_response = serde_json::json!({
+            "status": "fail",
+            "message": format!("Gift voucher {} not found", code)
+        });
+        (StatusCode::NOT_FOUND, Json(error_response))
+    })?;
+
+    let transactions = voucher_transactions(&data.db, gift_voucher.id)
+        .await
+        .map_err(database_error)?;
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "data": serde_json::json!({
+            "gift_voucher": gift_voucher,
+            "transactions": transactions,
+        })
+    });
+
+    Ok(Json(json_response))
+}
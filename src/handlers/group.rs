@@ -0,0 +1,480 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Extension, Json,
+};
+use sqlx::PgConnection;
+
+use crate::{
+    group::{
+        generate_code, group_billing, hold_fits, rooms_booked, BILLING_TYPES, BILLING_TYPE_SPLIT,
+    },
+    models::{
+        Booking, Guest, ReservationGroup, BOOKING_STATUS_CONFIRMED, BOOKING_STATUS_EXPIRED,
+        BOOKING_STATUS_PENDING,
+    },
+    schema::{CreateBookingSchema, CreateGroupSchema, UpdateGroupSchema},
+    telemetry::{record_booking_cancelled, record_booking_created},
+    AppState,
+};
+
+use super::{
+    booking::{book_room, cancel_booking, move_stay},
+    util::{bad_request, database_error},
+    waitlist::notify_waitlist,
+};
+
+// Handler for a guest to book several rooms together, as the lead guest of a new group
+pub async fn create_group_handler(
+    Extension(guest): Extension<Guest>,
+    State(data): State<Arc<AppState>>,
+    Json(body): Json<CreateGroupSchema>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let billing_type = body.billing_type.as_deref().unwrap_or(BILLING_TYPE_SPLIT);
+    if !BILLING_TYPES.contains(&billing_type) {
+        return Err(bad_request(format!(
+            "Invalid billing type, expected one of: {}",
+            BILLING_TYPES.join(", ")
+        )));
+    }
+
+    if body.name.trim().is_empty() {
+        return Err(bad_request("Group name can't be empty".to_string()));
+    }
+
+    if body.rooms.is_empty() {
+        return Err(bad_request(
+            "A group needs at least one room booked by the lead guest".to_string(),
+        ));
+    }
+
+    if body.checkout_date <= body.checkin_date {
+        return Err(bad_request(
+            "Check-out date must be after the check-in date".to_string(),
+        ));
+    }
+
+    // The rooms of the lead guest are part of the block
+    let rooms_held = body.rooms_held.unwrap_or(body.rooms.len() as i32);
+    if rooms_held < body.rooms.len() as i32 {
+        return Err(bad_request(format!(
+            "The block must hold at least the {} rooms booked now",
+            body.rooms.len()
+        )));
+    }
+
+    if let Some(room_type_id) = body.room_type_id {
+        let room_type_exists: bool = sqlx::query_scalar!(
+            "select exists(select 1 from room_type where id = $1 and active = true) as \"exists!\"",
+            room_type_id
+        )
+        .fetch_one(&data.db)
+        .await
+        .map_err(database_error)?;
+
+        if !room_type_exists {
+            return Err(bad_request(format!(
+                "Room type with ID: {} not found",
+                room_type_id
+            )));
+        }
+    }
+
+    let mut tx = data.db.begin().await.map_err(database_error)?;
+
+    let group = sqlx::query_as!(
+        ReservationGroup,
+        "insert into reservation_group
+            (code, name, lead_guest_id, billing_type, room_type_id, checkin_date, checkout_date, rooms_held)
+        values ($1, $2, $3, $4, $5, $6, $7, $8)
+        returning *",
+        generate_code(),
+        body.name.trim(),
+        guest.id,
+        billing_type,
+        body.room_type_id,
+        body.checkin_date,
+        body.checkout_date,
+        rooms_held
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(database_error)?;
+
+    // Every room is booked or none is
+    let mut booked = Vec::with_capacity(body.rooms.len());
+    for room in body.rooms {
+        let room = CreateBookingSchema {
+            checkin_date: group.checkin_date,
+            checkout_date: group.checkout_date,
+            num_adults: room.num_adults,
+            num_children: room.num_children,
+            room_type_id: room.room_type_id.or(group.room_type_id),
+            rate_plan_id: body.rate_plan_id,
+            promo_code: None,
+            redeem_points: None,
+            group_code: None,
+            block_code: None,
+            preferences: Default::default(),
+            special_requests: None,
+        };
+        booked.push(book_room(&data, &mut tx, &guest, &room, Some(&group), None).await?);
+    }
+
+    ensure_hold_fits(&mut tx, &group).await?;
+
+    tx.commit().await.map_err(database_error)?;
+
+    tracing::info!(
+        reservation_group_id = group.id,
+        rooms = booked.len(),
+        "reservation group created"
+    );
+    for room in &booked {
+        record_booking_created(&room.booking.booking_amount);
+    }
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "data": serde_json::json!({
+            "group": group,
+            "bookings": booked
+        })
+    });
+
+    Ok((StatusCode::CREATED, Json(json_response)))
+}
+
+// Handler to list the groups the guest leads
+pub async fn group_list_handler(
+    Extension(guest): Extension<Guest>,
+    State(data): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let groups = sqlx::query_as!(
+        ReservationGroup,
+        "select * from reservation_group where lead_guest_id = $1 order by id desc",
+        guest.id
+    )
+    .fetch_all(&data.db)
+    .await
+    .map_err(database_error)?;
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "results": groups.len(),
+        "groups": groups
+    });
+
+    Ok(Json(json_response))
+}
+
+// Handler for the lead guest to get the group with every booking in it and who pays what
+pub async fn get_group_handler(
+    Extension(guest): Extension<Guest>,
+    State(data): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let group = sqlx::query_as!(
+        ReservationGroup,
+        "select * from reservation_group where id = $1 and lead_guest_id = $2",
+        id,
+        guest.id
+    )
+    .fetch_optional(&data.db)
+    .await
+    .map_err(database_error)?
+    .ok_or_else(|| group_not_found(id))?;
+
+    let bookings = group_bookings(&data, group.id).await?;
+    let billing = group_billing(&data.db, &group, &bookings)
+        .await
+        .map_err(database_error)?;
+
+    let mut conn = data.db.acquire().await.map_err(database_error)?;
+    let booked = rooms_booked(&mut conn, group.id)
+        .await
+        .map_err(database_error)?;
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "data": serde_json::json!({
+            "group": group,
+            "rooms_remaining": (i64::from(group.rooms_held) - booked).max(0),
+            "bookings": bookings,
+            "billing": billing
+        })
+    });
+
+    Ok(Json(json_response))
+}
+
+// Handler for the lead guest to modify the group, new dates apply to every booking in it
+pub async fn update_group_handler(
+    Extension(guest): Extension<Guest>,
+    State(data): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Json(body): Json<UpdateGroupSchema>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    if let Some(billing_type) = &body.billing_type {
+        if !BILLING_TYPES.contains(&billing_type.as_str()) {
+            return Err(bad_request(format!(
+                "Invalid billing type, expected one of: {}",
+                BILLING_TYPES.join(", ")
+            )));
+        }
+    }
+
+    let mut tx = data.db.begin().await.map_err(database_error)?;
+
+    let group = sqlx::query_as!(
+        ReservationGroup,
+        "select * from reservation_group where id = $1 and lead_guest_id = $2 for update",
+        id,
+        guest.id
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(database_error)?
+    .ok_or_else(|| group_not_found(id))?;
+
+    let checkin_date = body.checkin_date.unwrap_or(group.checkin_date);
+    let checkout_date = body.checkout_date.unwrap_or(group.checkout_date);
+    if checkout_date <= checkin_date {
+        return Err(bad_request(
+            "Check-out date must be after the check-in date".to_string(),
+        ));
+    }
+
+    let booked = rooms_booked(&mut tx, group.id)
+        .await
+        .map_err(database_error)?;
+    let rooms_held = body.rooms_held.unwrap_or(group.rooms_held);
+    if i64::from(rooms_held) < booked {
+        return Err(bad_request(format!(
+            "{} rooms are already booked in the block",
+            booked
+        )));
+    }
+
+    let held_more = rooms_held > group.rooms_held;
+    let dates_changed = checkin_date != group.checkin_date || checkout_date != group.checkout_date;
+    if dates_changed {
+        // Stays that already started can't be moved
+        let started: bool = sqlx::query_scalar!(
+            "select exists(
+                select 1 from booking
+                where reservation_group_id = $1 and status not in ($2, $3, $4)
+            ) as \"exists!\"",
+            group.id,
+            BOOKING_STATUS_PENDING,
+            BOOKING_STATUS_CONFIRMED,
+            BOOKING_STATUS_EXPIRED
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(database_error)?;
+
+        if started {
+            let error_response = serde_json::json!({
+                "status": "fail",
+                "message": format!("Guests of group with ID: {} have already checked in", id)
+            });
+            return Err((StatusCode::CONFLICT, Json(error_response)));
+        }
+    }
+
+    let group = sqlx::query_as!(
+        ReservationGroup,
+        "update reservation_group set
+        name = $1,
+        billing_type = $2,
+        rooms_held = $3,
+        checkin_date = $4,
+        checkout_date = $5,
+        updated_at = now()
+        where id = $6
+        returning *",
+        body.name.unwrap_or(group.name),
+        body.billing_type.unwrap_or(group.billing_type),
+        rooms_held,
+        checkin_date,
+        checkout_date,
+        group.id
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(database_error)?;
+
+    // The rooms held move with the group before its bookings
+    if dates_changed {
+        // Every booking is moved like a single one, the group moves whole or not at all
+        let bookings = sqlx::query_as!(
+            Booking,
+            "select * from booking
+            where reservation_group_id = $1 and status in ($2, $3)
+            order by id
+            for update",
+            group.id,
+            BOOKING_STATUS_PENDING,
+            BOOKING_STATUS_CONFIRMED
+        )
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(database_error)?;
+
+        for booking in &bookings {
+            move_stay(&data, &mut tx, booking, checkin_date, checkout_date).await?;
+        }
+    }
+
+    if dates_changed || held_more {
+        ensure_hold_fits(&mut tx, &group).await?;
+    }
+
+    tx.commit().await.map_err(database_error)?;
+
+    let bookings = group_bookings(&data, group.id).await?;
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "data": serde_json::json!({
+            "group": group,
+            "bookings": bookings
+        })
+    });
+
+    Ok(Json(json_response))
+}
+
+// Handler for the lead guest to cancel the group with every booking in it
+pub async fn cancel_group_handler(
+    Extension(guest): Extension<Guest>,
+    State(data): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let mut tx = data.db.begin().await.map_err(database_error)?;
+
+    sqlx::query!(
+        "select id from reservation_group where id = $1 and lead_guest_id = $2 for update",
+        id,
+        guest.id
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(database_error)?
+    .ok_or_else(|| group_not_found(id))?;
+
+    let bookings = sqlx::query!(
+        "select id, status from booking where reservation_group_id = $1 order by id for update",
+        id
+    )
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(database_error)?;
+
+    // Stays that started are kept for the folio, so the group can't be cancelled anymore
+    if let Some(started) = bookings.iter().find(|booking| {
+        ![
+            BOOKING_STATUS_PENDING,
+            BOOKING_STATUS_CONFIRMED,
+            BOOKING_STATUS_EXPIRED,
+        ]
+        .contains(&booking.status.as_str())
+    }) {
+        let error_response = serde_json::json!({
+            "status": "fail",
+            "message": format!(
+                "Booking with ID: {} of the group is {}, the group can't be cancelled",
+                started.id, started.status
+            )
+        });
+        return Err((StatusCode::CONFLICT, Json(error_response)));
+    }
+
+    // Expired bookings gave their room back already, they are kept without the group
+    let booking_ids: Vec<i32> = bookings
+        .iter()
+        .filter(|booking| booking.status != BOOKING_STATUS_EXPIRED)
+        .map(|booking| booking.id)
+        .collect();
+
+    let mut deleted_amounts = Vec::with_capacity(booking_ids.len());
+    for booking_id in &booking_ids {
+        deleted_amounts.push(cancel_booking(&mut tx, *booking_id).await?);
+    }
+
+    sqlx::query!("delete from reservation_group where id = $1", id)
+        .execute(&mut *tx)
+        .await
+        .map_err(database_error)?;
+
+    tx.commit().await.map_err(database_error)?;
+
+    tracing::info!(
+        reservation_group_id = id,
+        bookings = booking_ids.len(),
+        "reservation group cancelled"
+    );
+    for deleted_amount in &deleted_amounts {
+        record_booking_cancelled(deleted_amount);
+    }
+
+    notify_waitlist(&data).await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn group_bookings(
+    data: &AppState,
+    group_id: i32,
+) -> Result<Vec<Booking>, (StatusCode, Json<serde_json::Value>)> {
+    sqlx::query_as!(
+        Booking,
+        "select * from booking where reservation_group_id = $1 order by id",
+        group_id
+    )
+    .fetch_all(&data.db)
+    .await
+    .map_err(database_error)
+}
+
+// Refuse a group whose rooms held no longer fit in its room type,
+// the room type stays locked until the caller commits
+async fn ensure_hold_fits(
+    tx: &mut PgConnection,
+    group: &ReservationGroup,
+) -> Result<(), (StatusCode, Json<serde_json::Value>)> {
+    if let Some(room_type_id) = group.room_type_id {
+        sqlx::query!(
+            "select id from room_type where id = $1 for update",
+            room_type_id
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(database_error)?;
+    }
+
+    if !hold_fits(&mut *tx, group).await.map_err(database_error)? {
+        let error_response = serde_json::json!({
+            "status": "fail",
+            "message": format!(
+                "Not enough rooms are left to hold {} rooms from {} to {}",
+                group.rooms_held, group.checkin_date, group.checkout_date
+            )
+        });
+        return Err((StatusCode::CONFLICT, Json(error_response)));
+    }
+
+    Ok(())
+}
+
+fn group_not_found(id: i32) -> (StatusCode, Json<serde_json::Value>) {
+    let error_response = serde_json::json!({
+        "status": "fail",
+        "message": format!("Group with ID: {} not found", id)
+    });
+    (StatusCode::NOT_FOUND, Json(error_response))
+}
@@ -1,6 +1,15 @@
-use axum::{http::StatusCode, response::IntoResponse, Json};
+use std::{collections::HashSet, sync::Arc};
 
-// Configure health check handler
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+
+use crate::{telemetry::record_pool_metrics, AppState, MIGRATOR};
+
+// Configure liveness handler, only tells that the process is up and serving requests
 pub async fn health_check_handler() -> impl IntoResponse {
     const MESSAGE: &str = "Local Hotel API is alive and well";
 
@@ -12,6 +21,88 @@ pub async fn health_check_handler() -> impl IntoResponse {
     Json(json_resp)
 }
 
+// Configure readiness handler, checks every component the API depends on
+pub async fn readiness_handler(State(data): State<Arc<AppState>>) -> impl IntoResponse {
+    // Check that the database answers a trivial query
+    let database = match sqlx::query("select 1").execute(&data.db).await {
+        Ok(_) => serde_json::json!({
+            "status": "up",
+            "pool_size": data.db.size(),
+            "idle_connections": data.db.num_idle(),
+        }),
+        Err(e) => serde_json::json!({
+            "status": "down",
+            "message": format!("Database error: {}", e),
+        }),
+    };
+    let database_ready = database["status"] == "up";
+
+    // Check that every embedded migration has been applied successfully
+    let applied: Result<Vec<i64>, sqlx::Error> =
+        sqlx::query_scalar("select version from _sqlx_migrations where success = true")
+            .fetch_all(&data.db)
+            .await;
+
+    let migrations = match applied {
+        Ok(applied) => {
+            let applied: HashSet<i64> = applied.into_iter().collect();
+            let pending: Vec<i64> = MIGRATOR
+                .iter()
+                .filter(|migration| !migration.migration_type.is_down_migration())
+                .filter(|migration| !applied.contains(&migration.version))
+                .map(|migration| migration.version)
+                .collect();
+
+            serde_json::json!({
+                "status": if pending.is_empty() { "up" } else { "pending" },
+                "pending": pending,
+            })
+        }
+        Err(e) => serde_json::json!({
+            "status": "down",
+            "message": format!("Database error: {}", e),
+        }),
+    };
+    let migrations_ready = migrations["status"] == "up";
+
+    // Check that every background worker has beaten recently
+    let workers = data.heartbeats.statuses();
+    let workers_ready = workers.iter().all(|worker| worker.healthy);
+
+    let ready = database_ready && migrations_ready && workers_ready;
+
+    let json_resp = serde_json::json!({
+        "status": if ready { "success" } else { "fail" },
+        "ready": ready,
+        "components": {
+            "database": database,
+            "migrations": migrations,
+            "workers": {
+                "status": if workers_ready { "up" } else { "down" },
+                "workers": workers,
+            },
+        }
+    });
+
+    let status_code = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    (status_code, Json(json_resp))
+}
+
+// Configure metrics handler, renders every metric in prometheus text format for admins
+pub async fn metrics_handler(State(data): State<Arc<AppState>>) -> impl IntoResponse {
+    record_pool_metrics(&data.db);
+
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        data.metrics.render(),
+    )
+}
+
 // Configure not found handler
 pub async fn handler_404() -> impl IntoResponse {
     const MESSAGE: &str = "Content not found";
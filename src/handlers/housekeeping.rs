@@ -0,0 +1,437 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Extension, Json,
+};
+
+use crate::{
+    housekeeping::{
+        complete_tasks, create_task, set_room_status, NewTask, StatusChange, ROOM_STATUSES,
+        ROOM_STATUS_CLEAN, ROOM_STATUS_DIRTY, ROOM_STATUS_INSPECTED, TASK_TYPES, TASK_TYPE_CLEAN,
+        TASK_TYPE_INSPECT,
+    },
+    models::{Guest, HousekeepingTask, Room, RoomStatusChange, BOOKING_STATUS_CHECKED_IN},
+    response::RoomBoardEntry,
+    schema::{
+        CreateHousekeepingTaskSchema, RoomBoardOptions, SetRoomStatusSchema,
+        UpdateHousekeepingTaskSchema,
+    },
+    AppState,
+};
+
+use super::util::{bad_request, database_error};
+
+// Handler for the room status board, every room with its status and who stays in it
+pub async fn room_board_handler(
+    State(data): State<Arc<AppState>>,
+    opts: Option<Query<RoomBoardOptions>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let Query(opts) = opts.unwrap_or_default();
+
+    let rooms = sqlx::query_as!(
+        RoomBoardEntry,
+        "select
+            room.id as room_id,
+            room.room_number,
+            room.floor,
+            room_type.code as room_type,
+            room.housekeeping_status,
+            room.status_updated_at,
+            booking.id as \"booking_id?\",
+            (
+                select count(*) from housekeeping_task
+                where housekeeping_task.room_id = room.id and housekeeping_task.status = 'open'
+            ) as \"open_tasks!\"
+        from room
+        join room_type on room_type.id = room.room_type_id
+        left join booking on booking.room_id = room.id and booking.status = $1
+        where room.active = true
+            and ($2::varchar is null or room.housekeeping_status = $2)
+            and ($3::int is null or room.floor = $3)
+        order by room.room_number",
+        BOOKING_STATUS_CHECKED_IN,
+        opts.status,
+        opts.floor
+    )
+    .fetch_all(&data.db)
+    .await
+    .map_err(database_error)?;
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "results": rooms.len(),
+        "rooms": rooms
+    });
+
+    Ok(Json(json_response))
+}
+
+// Handler for housekeepers to list their open tasks, with the ones nobody was given yet
+pub async fn my_housekeeping_tasks_handler(
+    State(data): State<Arc<AppState>>,
+    Extension(housekeeper): Extension<Guest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let tasks = sqlx::query_as!(
+        HousekeepingTask,
+        "select * from housekeeping_task
+        where status = 'open' and (assigned_to = $1 or assigned_to is null)
+        order by assigned_to nulls last, id",
+        housekeeper.id
+    )
+    .fetch_all(&data.db)
+    .await
+    .map_err(database_error)?;
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "results": tasks.len(),
+        "tasks": tasks
+    });
+
+    Ok(Json(json_response))
+}
+
+// Handler for housekeepers to mark a dirty room cleaned, it then waits for an inspection
+pub async fn room_cleaned_handler(
+    State(data): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Extension(housekeeper): Extension<Guest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let mut tx = data.db.begin().await.map_err(database_error)?;
+
+    let room = lock_room(&mut tx, id).await?;
+    if room.housekeeping_status != ROOM_STATUS_DIRTY {
+        return Err(invalid_room_status(&room, "cleaned"));
+    }
+
+    let room = set_room_status(
+        &mut tx,
+        id,
+        ROOM_STATUS_CLEAN,
+        StatusChange {
+            booking_id: None,
+            reason: None,
+            changed_by: Some(housekeeper.id),
+        },
+    )
+    .await
+    .map_err(database_error)?;
+
+    let completed = complete_tasks(&mut tx, id, TASK_TYPE_CLEAN, housekeeper.id)
+        .await
+        .map_err(database_error)?;
+
+    let inspection = create_task(
+        &mut tx,
+        NewTask {
+            room_id: id,
+            task_type: TASK_TYPE_INSPECT,
+            assigned_to: None,
+            booking_id: None,
+            notes: None,
+            created_by: Some(housekeeper.id),
+        },
+    )
+    .await
+    .map_err(database_error)?;
+
+    tx.commit().await.map_err(database_error)?;
+
+    tracing::info!(room_id = id, "room cleaned");
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "data": serde_json::json!({
+            "room": room,
+            "completed_tasks": completed,
+            "inspection_task": inspection
+        })
+    });
+
+    Ok(Json(json_response))
+}
+
+// Handler for housekeepers to mark a cleaned room inspected
+pub async fn room_inspected_handler(
+    State(data): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Extension(housekeeper): Extension<Guest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let mut tx = data.db.begin().await.map_err(database_error)?;
+
+    let room = lock_room(&mut tx, id).await?;
+    if room.housekeeping_status != ROOM_STATUS_CLEAN {
+        return Err(invalid_room_status(&room, "inspected"));
+    }
+
+    let room = set_room_status(
+        &mut tx,
+        id,
+        ROOM_STATUS_INSPECTED,
+        StatusChange {
+            booking_id: None,
+            reason: None,
+            changed_by: Some(housekeeper.id),
+        },
+    )
+    .await
+    .map_err(database_error)?;
+
+    let completed = complete_tasks(&mut tx, id, TASK_TYPE_INSPECT, housekeeper.id)
+        .await
+        .map_err(database_error)?;
+
+    tx.commit().await.map_err(database_error)?;
+
+    tracing::info!(room_id = id, "room inspected");
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "data": serde_json::json!({
+            "room": room,
+            "completed_tasks": completed
+        })
+    });
+
+    Ok(Json(json_response))
+}
+
+// Handler for staff to set any status on a room, e.g. take it out of service
+pub async fn set_room_status_handler(
+    State(data): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Extension(staff): Extension<Guest>,
+    Json(body): Json<SetRoomStatusSchema>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    if !ROOM_STATUSES.contains(&body.status.as_str()) {
+        return Err(bad_request(format!(
+            "Invalid room status, expected one of: {}",
+            ROOM_STATUSES.join(", ")
+        )));
+    }
+
+    let mut tx = data.db.begin().await.map_err(database_error)?;
+
+    lock_room(&mut tx, id).await?;
+
+    let room = set_room_status(
+        &mut tx,
+        id,
+        &body.status,
+        StatusChange {
+            booking_id: None,
+            reason: body.reason.as_deref(),
+            changed_by: Some(staff.id),
+        },
+    )
+    .await
+    .map_err(database_error)?;
+
+    tx.commit().await.map_err(database_error)?;
+
+    tracing::info!(
+        room_id = id,
+        status = room.housekeeping_status,
+        "room status set"
+    );
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "data": serde_json::json!({
+            "room": room
+        })
+    });
+
+    Ok(Json(json_response))
+}
+
+// Handler for staff to get the status history of a room, latest first
+pub async fn room_status_history_handler(
+    State(data): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let changes = sqlx::query_as!(
+        RoomStatusChange,
+        "select * from room_status_change where room_id = $1 order by id desc limit 100",
+        id
+    )
+    .fetch_all(&data.db)
+    .await
+    .map_err(database_error)?;
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "results": changes.len(),
+        "changes": changes
+    });
+
+    Ok(Json(json_response))
+}
+
+// Handler for staff to list every open housekeeping task
+pub async fn list_housekeeping_tasks_handler(
+    State(data): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let tasks = sqlx::query_as!(
+        HousekeepingTask,
+        "select * from housekeeping_task where status = 'open' order by id"
+    )
+    .fetch_all(&data.db)
+    .await
+    .map_err(database_error)?;
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "results": tasks.len(),
+        "tasks": tasks
+    });
+
+    Ok(Json(json_response))
+}
+
+// Handler for staff to give a task to a housekeeper
+pub async fn create_housekeeping_task_handler(
+    State(data): State<Arc<AppState>>,
+    Extension(staff): Extension<Guest>,
+    Json(body): Json<CreateHousekeepingTaskSchema>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let Some(task_type) = TASK_TYPES
+        .iter()
+        .copied()
+        .find(|task_type| *task_type == body.task_type)
+    else {
+        return Err(bad_request(format!(
+            "Invalid task type, expected one of: {}",
+            TASK_TYPES.join(", ")
+        )));
+    };
+
+    if let Some(assigned_to) = body.assigned_to {
+        ensure_housekeeper(&data, assigned_to).await?;
+    }
+
+    let mut tx = data.db.begin().await.map_err(database_error)?;
+
+    lock_room(&mut tx, body.room_id).await?;
+
+    let task = create_task(
+        &mut tx,
+        NewTask {
+            room_id: body.room_id,
+            task_type,
+            assigned_to: body.assigned_to,
+            booking_id: None,
+            notes: body.notes,
+            created_by: Some(staff.id),
+        },
+    )
+    .await
+    .map_err(database_error)?;
+
+    tx.commit().await.map_err(database_error)?;
+
+    tracing::info!(housekeeping_task_id = task.id, "housekeeping task created");
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "data": serde_json::json!({
+            "task": task
+        })
+    });
+
+    Ok((StatusCode::CREATED, Json(json_response)))
+}
+
+// Handler for staff to reassign an open task or change its notes
+pub async fn update_housekeeping_task_handler(
+    State(data): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Json(body): Json<UpdateHousekeepingTaskSchema>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    if let Some(assigned_to) = body.assigned_to {
+        ensure_housekeeper(&data, assigned_to).await?;
+    }
+
+    let task = sqlx::query_as!(
+        HousekeepingTask,
+        "update housekeeping_task set
+        assigned_to = coalesce($1, assigned_to),
+        notes = coalesce($2, notes),
+        updated_at = now()
+        where id = $3 and status = 'open'
+        returning *",
+        body.assigned_to,
+        body.notes,
+        id
+    )
+    .fetch_optional(&data.db)
+    .await
+    .map_err(database_error)?
+    .ok_or_else(|| {
+        let error_response = serde_json::json!({
+            "status": "fail",
+            "message": format!("Open housekeeping task with ID: {} not found", id)
+        });
+        (StatusCode::NOT_FOUND, Json(error_response))
+    })?;
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "data": serde_json::json!({
+            "task": task
+        })
+    });
+
+    Ok(Json(json_response))
+}
+
+async fn lock_room(
+    conn: &mut sqlx::PgConnection,
+    id: i32,
+) -> Result<Room, (StatusCode, Json<serde_json::Value>)> {
+    sqlx::query_as!(Room, "select * from room where id = $1 for update", id)
+        .fetch_optional(conn)
+        .await
+        .map_err(database_error)?
+        .ok_or_else(|| {
+            let error_response = serde_json::json!({
+                "status": "fail",
+                "message": format!("Room with ID: {} not found", id)
+            });
+            (StatusCode::NOT_FOUND, Json(error_response))
+        })
+}
+
+// Util function to return 400 unless the account can be given housekeeping tasks
+async fn ensure_housekeeper(
+    data: &AppState,
+    guest_id: i32,
+) -> Result<(), (StatusCode, Json<serde_json::Value>)> {
+    let housekeeper = sqlx::query_as!(Guest, "select * from guest where id = $1", guest_id)
+        .fetch_optional(&data.db)
+        .await
+        .map_err(database_error)?;
+
+    if !housekeeper.is_some_and(|housekeeper| housekeeper.is_housekeeping()) {
+        return Err(bad_request(format!(
+            "Account with ID: {} isn't on the housekeeping staff",
+            guest_id
+        )));
+    }
+
+    Ok(())
+}
+
+fn invalid_room_status(room: &Room, action: &str) -> (StatusCode, Json<serde_json::Value>) {
+    let error_response = serde_json::json!({
+        "status": "fail",
+        "message": format!(
+            "Room {} is {} and can't be marked {}",
+            room.room_number, room.housekeeping_status, action
+        )
+    });
+    (StatusCode::CONFLICT, Json(error_response))
+}
@@ -0,0 +1,134 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+
+use crate::{
+    invoice::{issue_invoice, latest_invoice, load_invoice_document, render_html, render_pdf},
+    models::{Guest, Invoice},
+    schema::InvoiceOptions,
+    AppState,
+};
+
+use super::util::{
+    bad_request, database_error, ensure_booking_exists, ensure_guest_booking_exists,
+};
+
+// Handler for the guest to download the invoice of one of their bookings
+pub async fn get_invoice_handler(
+    State(data): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Extension(guest): Extension<Guest>,
+    opts: Option<Query<InvoiceOptions>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let Query(opts) = opts.unwrap_or_default();
+
+    ensure_guest_booking_exists(&data, id, guest.id).await?;
+
+    let invoice = issued_invoice(&data, id).await?;
+
+    render_invoice(&data, invoice, opts.format.as_deref()).await
+}
+
+// Handler for staff to download the latest invoice of any booking
+pub async fn staff_get_invoice_handler(
+    State(data): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    opts: Option<Query<InvoiceOptions>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let Query(opts) = opts.unwrap_or_default();
+
+    ensure_booking_exists(&data, id).await?;
+
+    let invoice = issued_invoice(&data, id).await?;
+
+    render_invoice(&data, invoice, opts.format.as_deref()).await
+}
+
+// Handler for staff to issue a new invoice from the current folio, e.g. after late charges
+pub async fn staff_issue_invoice_handler(
+    State(data): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    ensure_booking_exists(&data, id).await?;
+
+    let mut tx = data.db.begin().await.map_err(database_error)?;
+
+    let invoice = issue_invoice(&mut tx, id).await.map_err(database_error)?;
+
+    tx.commit().await.map_err(database_error)?;
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "data": serde_json::json!({
+            "invoice": invoice
+        })
+    });
+
+    Ok((StatusCode::CREATED, Json(json_response)))
+}
+
+// Util function to get the latest invoice, invoices are only issued by staff or at check-out
+async fn issued_invoice(
+    data: &AppState,
+    booking_id: i32,
+) -> Result<Invoice, (StatusCode, Json<serde_json::Value>)> {
+    latest_invoice(&data.db, booking_id)
+        .await
+        .map_err(database_error)?
+        .ok_or_else(|| {
+            let error_response = serde_json::json!({
+                "status": "fail",
+                "message": format!("No invoice has been issued for booking with ID: {}", booking_id)
+            });
+            (StatusCode::NOT_FOUND, Json(error_response))
+        })
+}
+
+// Util function to render the invoice as pdf (default), html or json
+async fn render_invoice(
+    data: &AppState,
+    invoice: Invoice,
+    format: Option<&str>,
+) -> Result<Response, (StatusCode, Json<serde_json::Value>)> {
+    let document = load_invoice_document(&data.db, invoice)
+        .await
+        .map_err(database_error)?;
+
+    let file_name = &document.invoice.invoice_number;
+
+    match format.unwrap_or("pdf") {
+        "pdf" => Ok((
+            [
+                (header::CONTENT_TYPE, "application/pdf".to_string()),
+                (
+                    header::CONTENT_DISPOSITION,
+                    format!("attachment; filename=\"{}.pdf\"", file_name),
+                ),
+            ],
+            render_pdf(&document),
+        )
+            .into_response()),
+        "html" => Ok((
+            [(header::CONTENT_TYPE, "text/html; charset=utf-8")],
+            render_html(&document),
+        )
+            .into_response()),
+        "json" => {
+            let json_response = serde_json::json!({
+                "status": "success",
+                "data": serde_json::json!({
+                    "invoice": document
+                })
+            });
+            Ok(Json(json_response).into_response())
+        }
+        _ => Err(bad_request(
+            "Invalid format, expected one of: pdf, html, json".to_string(),
+        )),
+    }
+}
@@ -0,0 +1,74 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Extension, Json,
+};
+
+use crate::{
+    loyalty::{next_tier, points_balance, points_value, stays_last_year, tier_for},
+    models::{Guest, LoyaltyEntry},
+    schema::FilterOptions,
+    AppState,
+};
+
+use super::util::database_error;
+
+// Handler for the guest to see their points balance, tier and points history
+pub async fn get_loyalty_handler(
+    State(data): State<Arc<AppState>>,
+    Extension(guest): Extension<Guest>,
+    opts: Option<Query<FilterOptions>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let Query(opts) = opts.unwrap_or_default();
+
+    let limit = opts.limit.unwrap_or(10);
+    let offset = (opts.page.unwrap_or(1) - 1) * limit;
+
+    let mut conn = data.db.acquire().await.map_err(database_error)?;
+
+    let balance = points_balance(&mut conn, guest.id)
+        .await
+        .map_err(database_error)?;
+    let stays = stays_last_year(&mut conn, guest.id)
+        .await
+        .map_err(database_error)?;
+    let tier = tier_for(stays);
+
+    let history = sqlx::query_as!(
+        LoyaltyEntry,
+        "select * from loyalty_ledger where guest_id = $1 order by id desc limit $2 offset $3",
+        guest.id,
+        limit as i64,
+        offset as i64
+    )
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(database_error)?;
+
+    let next_tier = next_tier(tier).map(|next| {
+        serde_json::json!({
+            "name": next.name,
+            "stays_needed": next.min_stays - stays,
+        })
+    });
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "data": serde_json::json!({
+            "balance": balance,
+            "points_value": points_value(
+                i32::try_from(balance).unwrap_or(i32::MAX),
+                data.env.loyalty.redeem_points_per_unit
+            ),
+            "tier": tier,
+            "stays_last_year": stays,
+            "next_tier": next_tier,
+            "history": history
+        })
+    });
+
+    Ok(Json(json_response))
+}
@@ -0,0 +1,484 @@
+use std::sync::Arc;
+
+use axum::{
+    body::Bytes,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::IntoResponse,
+    Extension, Json,
+};
+
+use crate::{
+    maintenance::{
+        attachment_content_type, attachment_disposition, clear_booking_flags,
+        flag_conflicting_bookings, return_room_to_service, sanitize_file_name,
+        take_room_out_of_order, MAX_FILE_NAME_LEN, OPEN_TICKET_STATUSES, PRIORITIES,
+        TICKET_STATUSES,
+    },
+    models::{Booking, Guest, MaintenanceAttachment, MaintenanceTicket},
+    schema::{
+        AttachmentOptions, CreateMaintenanceTicketSchema, MaintenanceTicketOptions,
+        UpdateMaintenanceTicketSchema,
+    },
+    AppState,
+};
+
+use super::util::{bad_request, database_error};
+
+// Handler for staff to list the maintenance tickets, most urgent first
+pub async fn list_maintenance_tickets_handler(
+    State(data): State<Arc<AppState>>,
+    opts: Option<Query<MaintenanceTicketOptions>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let Query(opts) = opts.unwrap_or_default();
+
+    let tickets = sqlx::query_as!(
+        MaintenanceTicket,
+        "select * from maintenance_ticket
+        where ($1::varchar is null or status = $1)
+            and ($2::int is null or room_id = $2)
+        order by
+            array_position(array['urgent', 'high', 'normal', 'low']::varchar[], priority),
+            id",
+        opts.status,
+        opts.room_id
+    )
+    .fetch_all(&data.db)
+    .await
+    .map_err(database_error)?;
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "results": tickets.len(),
+        "tickets": tickets
+    });
+
+    Ok(Json(json_response))
+}
+
+// Handler for staff to open a maintenance ticket on a room, out of order tickets block its inventory
+pub async fn create_maintenance_ticket_handler(
+    State(data): State<Arc<AppState>>,
+    Extension(staff): Extension<Guest>,
+    Json(body): Json<CreateMaintenanceTicketSchema>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    if body.title.trim().is_empty() {
+        return Err(bad_request("Ticket title can't be empty".to_string()));
+    }
+
+    let priority = body.priority.as_deref().unwrap_or("normal");
+    validate_priority(priority)?;
+
+    let out_of_order = body.out_of_order.unwrap_or(false);
+    if out_of_order {
+        match (body.out_of_order_from, body.out_of_order_until) {
+            (Some(from), Some(until)) if until > from => {}
+            _ => {
+                return Err(bad_request(
+                    "Out of order tickets need a from date before their until date".to_string(),
+                ))
+            }
+        }
+    }
+
+    if let Some(assigned_to) = body.assigned_to {
+        ensure_staff(&data, assigned_to).await?;
+    }
+
+    let mut tx = data.db.begin().await.map_err(database_error)?;
+
+    let room_exists: bool = sqlx::query_scalar!(
+        "select exists(select 1 from room where id = $1) as \"exists!\"",
+        body.room_id
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(database_error)?;
+
+    if !room_exists {
+        return Err(bad_request(format!(
+            "Room with ID: {} not found",
+            body.room_id
+        )));
+    }
+
+    let ticket = sqlx::query_as!(
+        MaintenanceTicket,
+        "insert into maintenance_ticket
+            (
+                room_id,
+                title,
+                description,
+                priority,
+                assigned_to,
+                reported_by,
+                out_of_order,
+                out_of_order_from,
+                out_of_order_until
+            )
+        values ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        returning *",
+        body.room_id,
+        body.title.trim(),
+        body.description,
+        priority,
+        body.assigned_to,
+        staff.id,
+        out_of_order,
+        body.out_of_order_from.filter(|_| out_of_order),
+        body.out_of_order_until.filter(|_| out_of_order)
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(database_error)?;
+
+    // Guests given the room for these dates must be moved
+    let flagged_bookings = flag_conflicting_bookings(&mut tx, &ticket)
+        .await
+        .map_err(database_error)?;
+
+    take_room_out_of_order(&mut tx, &ticket, staff.id)
+        .await
+        .map_err(database_error)?;
+
+    tx.commit().await.map_err(database_error)?;
+
+    tracing::info!(
+        maintenance_ticket_id = ticket.id,
+        room_id = ticket.room_id,
+        out_of_order,
+        flagged_bookings = flagged_bookings.len(),
+        "maintenance ticket opened"
+    );
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "data": serde_json::json!({
+            "ticket": ticket,
+            "flagged_bookings": flagged_bookings
+        })
+    });
+
+    Ok((StatusCode::CREATED, Json(json_response)))
+}
+
+// Handler for staff to get a ticket with its attachments and the bookings it conflicts with
+pub async fn get_maintenance_ticket_handler(
+    State(data): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let ticket = sqlx::query_as!(
+        MaintenanceTicket,
+        "select * from maintenance_ticket where id = $1",
+        id
+    )
+    .fetch_optional(&data.db)
+    .await
+    .map_err(database_error)?
+    .ok_or_else(|| ticket_not_found(id))?;
+
+    let attachments = sqlx::query_as!(
+        MaintenanceAttachment,
+        "select id, maintenance_ticket_id, file_name, content_type, size_bytes, uploaded_by, created_at
+        from maintenance_attachment
+        where maintenance_ticket_id = $1
+        order by id",
+        id
+    )
+    .fetch_all(&data.db)
+    .await
+    .map_err(database_error)?;
+
+    let flagged_bookings = sqlx::query_as!(
+        Booking,
+        "select * from booking where maintenance_ticket_id = $1 order by checkin_date",
+        id
+    )
+    .fetch_all(&data.db)
+    .await
+    .map_err(database_error)?;
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "data": serde_json::json!({
+            "ticket": ticket,
+            "attachments": attachments,
+            "flagged_bookings": flagged_bookings
+        })
+    });
+
+    Ok(Json(json_response))
+}
+
+// Handler for staff to work on a ticket, closing it puts the room back in inventory
+pub async fn update_maintenance_ticket_handler(
+    State(data): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Extension(staff): Extension<Guest>,
+    Json(body): Json<UpdateMaintenanceTicketSchema>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    if let Some(priority) = &body.priority {
+        validate_priority(priority)?;
+    }
+
+    if let Some(status) = &body.status {
+        if !TICKET_STATUSES.contains(&status.as_str()) {
+            return Err(bad_request(format!(
+                "Invalid ticket status, expected one of: {}",
+                TICKET_STATUSES.join(", ")
+            )));
+        }
+    }
+
+    if let Some(assigned_to) = body.assigned_to {
+        ensure_staff(&data, assigned_to).await?;
+    }
+
+    let mut tx = data.db.begin().await.map_err(database_error)?;
+
+    let ticket = sqlx::query_as!(
+        MaintenanceTicket,
+        "select * from maintenance_ticket where id = $1 for update",
+        id
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(database_error)?
+    .ok_or_else(|| ticket_not_found(id))?;
+
+    if !OPEN_TICKET_STATUSES.contains(&ticket.status.as_str()) {
+        let error_response = serde_json::json!({
+            "status": "fail",
+            "message": format!("Maintenance ticket with ID: {} is {}", id, ticket.status)
+        });
+        return Err((StatusCode::CONFLICT, Json(error_response)));
+    }
+
+    let out_of_order_until = match body.out_of_order_until {
+        Some(until) if !ticket.out_of_order => {
+            return Err(bad_request(format!(
+                "Maintenance ticket with ID: {} doesn't take the room out of order, can't set {}",
+                id, until
+            )))
+        }
+        Some(until) if ticket.out_of_order_from.is_some_and(|from| until <= from) => {
+            return Err(bad_request(
+                "Out of order until date must be after the from date".to_string(),
+            ))
+        }
+        Some(until) => Some(until),
+        None => ticket.out_of_order_until,
+    };
+
+    let status = body.status.unwrap_or(ticket.status);
+    let closed = !OPEN_TICKET_STATUSES.contains(&status.as_str());
+
+    let ticket = sqlx::query_as!(
+        MaintenanceTicket,
+        "update maintenance_ticket set
+        title = $1,
+        description = $2,
+        priority = $3,
+        status = $4,
+        assigned_to = $5,
+        out_of_order_until = $6,
+        resolved_at = case when $4::varchar = 'resolved' then now() else resolved_at end,
+        updated_at = now()
+        where id = $7
+        returning *",
+        body.title.unwrap_or(ticket.title),
+        body.description.or(ticket.description),
+        body.priority.unwrap_or(ticket.priority),
+        status,
+        body.assigned_to.or(ticket.assigned_to),
+        out_of_order_until,
+        id
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(database_error)?;
+
+    // The bookings in conflict are worked out again for the new dates
+    clear_booking_flags(&mut tx, id)
+        .await
+        .map_err(database_error)?;
+
+    let flagged_bookings = if closed {
+        return_room_to_service(&mut tx, &ticket, staff.id)
+            .await
+            .map_err(database_error)?;
+        Vec::new()
+    } else {
+        take_room_out_of_order(&mut tx, &ticket, staff.id)
+            .await
+            .map_err(database_error)?;
+        flag_conflicting_bookings(&mut tx, &ticket)
+            .await
+            .map_err(database_error)?
+    };
+
+    tx.commit().await.map_err(database_error)?;
+
+    tracing::info!(
+        maintenance_ticket_id = id,
+        status = ticket.status,
+        "maintenance ticket updated"
+    );
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "data": serde_json::json!({
+            "ticket": ticket,
+            "flagged_bookings": flagged_bookings
+        })
+    });
+
+    Ok(Json(json_response))
+}
+
+// Handler for staff to attach a file to a ticket, the file is the request body
+pub async fn upload_maintenance_attachment_handler(
+    State(data): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Extension(staff): Extension<Guest>,
+    Query(opts): Query<AttachmentOptions>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let file_name = sanitize_file_name(&opts.file_name);
+    if file_name.is_empty() || body.is_empty() {
+        return Err(bad_request(
+            "A file name and a non empty file are required".to_string(),
+        ));
+    }
+
+    if file_name.chars().count() > MAX_FILE_NAME_LEN {
+        return Err(bad_request(format!(
+            "File name can't be longer than {} characters",
+            MAX_FILE_NAME_LEN
+        )));
+    }
+
+    let content_type = attachment_content_type(
+        headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default(),
+    );
+
+    let ticket_exists: bool = sqlx::query_scalar!(
+        "select exists(select 1 from maintenance_ticket where id = $1) as \"exists!\"",
+        id
+    )
+    .fetch_one(&data.db)
+    .await
+    .map_err(database_error)?;
+
+    if !ticket_exists {
+        return Err(ticket_not_found(id));
+    }
+
+    let attachment = sqlx::query_as!(
+        MaintenanceAttachment,
+        "insert into maintenance_attachment
+            (maintenance_ticket_id, file_name, content_type, size_bytes, data, uploaded_by)
+        values ($1, $2, $3, $4, $5, $6)
+        returning id, maintenance_ticket_id, file_name, content_type, size_bytes, uploaded_by, created_at",
+        id,
+        file_name,
+        content_type,
+        body.len() as i32,
+        body.as_ref(),
+        staff.id
+    )
+    .fetch_one(&data.db)
+    .await
+    .map_err(database_error)?;
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "data": serde_json::json!({
+            "attachment": attachment
+        })
+    });
+
+    Ok((StatusCode::CREATED, Json(json_response)))
+}
+
+// Handler for staff to download an attachment of a ticket
+pub async fn download_maintenance_attachment_handler(
+    State(data): State<Arc<AppState>>,
+    Path((id, attachment_id)): Path<(i32, i32)>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let attachment = sqlx::query!(
+        "select file_name, content_type, data from maintenance_attachment
+        where id = $1 and maintenance_ticket_id = $2",
+        attachment_id,
+        id
+    )
+    .fetch_optional(&data.db)
+    .await
+    .map_err(database_error)?
+    .ok_or_else(|| {
+        let error_response = serde_json::json!({
+            "status": "fail",
+            "message": format!("Attachment with ID: {} not found", attachment_id)
+        });
+        (StatusCode::NOT_FOUND, Json(error_response))
+    })?;
+
+    // Files uploaded before the list of types was enforced are checked again
+    Ok((
+        [
+            (
+                header::CONTENT_TYPE,
+                attachment_content_type(&attachment.content_type).to_string(),
+            ),
+            (
+                header::CONTENT_DISPOSITION,
+                attachment_disposition(&attachment.file_name),
+            ),
+            (header::X_CONTENT_TYPE_OPTIONS, "nosniff".to_string()),
+        ],
+        attachment.data,
+    ))
+}
+
+fn validate_priority(priority: &str) -> Result<(), (StatusCode, Json<serde_json::Value>)> {
+    if !PRIORITIES.contains(&priority) {
+        return Err(bad_request(format!(
+            "Invalid priority, expected one of: {}",
+            PRIORITIES.join(", ")
+        )));
+    }
+
+    Ok(())
+}
+
+// Util function to return 400 unless tickets can be assigned to the account
+async fn ensure_staff(
+    data: &AppState,
+    guest_id: i32,
+) -> Result<(), (StatusCode, Json<serde_json::Value>)> {
+    let assignee = sqlx::query_as!(Guest, "select * from guest where id = $1", guest_id)
+        .fetch_optional(&data.db)
+        .await
+        .map_err(database_error)?;
+
+    if !assignee.is_some_and(|assignee| assignee.is_staff()) {
+        return Err(bad_request(format!(
+            "Account with ID: {} isn't a staff account",
+            guest_id
+        )));
+    }
+
+    Ok(())
+}
+
+fn ticket_not_found(id: i32) -> (StatusCode, Json<serde_json::Value>) {
+    let error_response = serde_json::json!({
+        "status": "fail",
+        "message": format!("Maintenance ticket with ID: {} not found", id)
+    });
+    (StatusCode::NOT_FOUND, Json(error_response))
+}
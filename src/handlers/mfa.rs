@@ -0,0 +1,387 @@
+use std::sync::Arc;
+
+use argon2::{password_hash::SaltString, Argon2, PasswordHash, PasswordHasher, PasswordVerifier};
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Extension, Json};
+use chrono::Utc;
+use jsonwebtoken::{decode, DecodingKey, Validation};
+use rand_core::OsRng;
+use totp_rs::{Algorithm, Secret, TOTP};
+
+use super::auth::login_response;
+use crate::{
+    code::random_code,
+    models::Guest,
+    schema::{ConfirmMfaSchema, LoginMfaSchema, TokenClaims},
+    telemetry::record_login,
+    AppState,
+};
+
+const MFA_ISSUER: &str = "Local Hotel";
+const RECOVERY_CODE_COUNT: usize = 10;
+
+// Handler to start the TOTP enrollment, returns the secret for the authenticator app
+pub async fn enroll_mfa_handler(
+    Extension(guest): Extension<Guest>,
+    State(data): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    if guest.mfa_enabled {
+        let error_response = serde_json::json!({
+            "status": "fail",
+            "message": "Multi-factor authentication is already enabled",
+        });
+        return Err((StatusCode::CONFLICT, Json(error_response)));
+    }
+
+    // Generate a new secret, it only becomes active once a code is confirmed
+    let secret = Secret::generate_secret().to_encoded().to_string();
+    let totp = build_totp(&secret, &guest)?;
+
+    sqlx::query!(
+        "update guest set mfa_secret = $1, updated_at = now() where id = $2",
+        secret,
+        guest.id
+    )
+    .execute(&data.db)
+    .await
+    .map_err(|e| {
+        let error_response = serde_json::json!({
+            "status": "error",
+            "message": format!("Database error: {}", e),
+        });
+        (StatusCode::INTERNAL_SERVER_ERROR, Json(error_response))
+    })?;
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "data": serde_json::json!({
+            "secret": secret,
+            "otpauth_uri": totp.get_url(),
+        })
+    });
+
+    Ok(Json(json_response))
+}
+
+// Handler to confirm the enrollment with a first code, returns the recovery codes once
+pub async fn confirm_mfa_handler(
+    Extension(guest): Extension<Guest>,
+    State(data): State<Arc<AppState>>,
+    Json(body): Json<ConfirmMfaSchema>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    if guest.mfa_enabled {
+        let error_response = serde_json::json!({
+            "status": "fail",
+            "message": "Multi-factor authentication is already enabled",
+        });
+        return Err((StatusCode::CONFLICT, Json(error_response)));
+    }
+
+    let secret = guest.mfa_secret.as_deref().ok_or_else(|| {
+        let error_response = serde_json::json!({
+            "status": "fail",
+            "message": "Start the enrollment before confirming it",
+        });
+        (StatusCode::BAD_REQUEST, Json(error_response))
+    })?;
+
+    let Some(time_step) = code_time_step(secret, &guest, &body.code)? else {
+        let error_response = serde_json::json!({
+            "status": "fail",
+            "message": "Invalid authentication code",
+        });
+        return Err((StatusCode::BAD_REQUEST, Json(error_response)));
+    };
+
+    // Generate the recovery codes, only their hashes are stored
+    let recovery_codes: Vec<String> = (0..RECOVERY_CODE_COUNT)
+        .map(|_| generate_recovery_code())
+        .collect();
+
+    let mut code_hashes = Vec::with_capacity(recovery_codes.len());
+    for code in &recovery_codes {
+        code_hashes.push(hash_recovery_code(code)?);
+    }
+
+    let database_error = |e: sqlx::Error| {
+        let error_response = serde_json::json!({
+            "status": "error",
+            "message": format!("Database error: {}", e),
+        });
+        (StatusCode::INTERNAL_SERVER_ERROR, Json(error_response))
+    };
+
+    let mut tx = data.db.begin().await.map_err(database_error)?;
+
+    sqlx::query!(
+        "update guest set mfa_enabled = true, mfa_last_time_step = $1, updated_at = now()
+        where id = $2",
+        time_step,
+        guest.id
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(database_error)?;
+
+    sqlx::query!(
+        "delete from mfa_recovery_code where guest_id = $1",
+        guest.id
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(database_error)?;
+
+    sqlx::query!(
+        "insert into mfa_recovery_code (guest_id, code_hash) select $1, unnest($2::varchar[])",
+        guest.id,
+        &code_hashes
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(database_error)?;
+
+    tx.commit().await.map_err(database_error)?;
+
+    tracing::info!(guest_id = guest.id, "multi-factor authentication enabled");
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "data": serde_json::json!({
+            "recovery_codes": recovery_codes,
+        })
+    });
+
+    Ok(Json(json_response))
+}
+
+// Handler for the second login step, trades the challenge token and a code for a full token
+pub async fn login_mfa_handler(
+    State(data): State<Arc<AppState>>,
+    Json(body): Json<LoginMfaSchema>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let invalid_challenge = || {
+        let error_response = serde_json::json!({
+            "status": "fail",
+            "message": "Invalid or expired challenge token",
+        });
+        (StatusCode::UNAUTHORIZED, Json(error_response))
+    };
+
+    let claims = decode::<TokenClaims>(
+        &body.mfa_token,
+        &DecodingKey::from_secret(data.env.jwt_secret.as_ref()),
+        &Validation::default(),
+    )
+    .map_err(|_| invalid_challenge())?
+    .claims;
+
+    if !claims.mfa_pending {
+        return Err(invalid_challenge());
+    }
+
+    let guest_id: i32 = claims.sub.parse().map_err(|_| invalid_challenge())?;
+
+    let guest = sqlx::query_as!(Guest, "select * from guest where id = $1", guest_id)
+        .fetch_optional(&data.db)
+        .await
+        .map_err(|e| {
+            let error_response = serde_json::json!({
+                "status": "error",
+                "message": format!("Database error: {}", e),
+            });
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(error_response))
+        })?
+        .ok_or_else(invalid_challenge)?;
+
+    let secret = match (guest.mfa_enabled, guest.mfa_secret.as_deref()) {
+        (true, Some(secret)) => secret,
+        _ => return Err(invalid_challenge()),
+    };
+
+    let is_valid = match (&body.code, &body.recovery_code) {
+        (Some(code), _) => match code_time_step(secret, &guest, code)? {
+            Some(time_step) => use_time_step(&data, &guest, time_step).await?,
+            None => false,
+        },
+        (None, Some(recovery_code)) => use_recovery_code(&data, &guest, recovery_code).await?,
+        (None, None) => {
+            let error_response = serde_json::json!({
+                "status": "fail",
+                "message": "Provide either an authentication code or a recovery code",
+            });
+            return Err((StatusCode::BAD_REQUEST, Json(error_response)));
+        }
+    };
+
+    if !is_valid {
+        tracing::warn!(
+            guest_id = guest.id,
+            "login attempt with an invalid second factor"
+        );
+        record_login("failure");
+        let error_response = serde_json::json!({
+            "status": "fail",
+            "message": "Invalid authentication code",
+        });
+        return Err((StatusCode::BAD_REQUEST, Json(error_response)));
+    }
+
+    Ok(login_response(&data, &guest, false))
+}
+
+// Build the TOTP generator for the guest from the base32 encoded secret
+fn build_totp(secret: &str, guest: &Guest) -> Result<TOTP, (StatusCode, Json<serde_json::Value>)> {
+    let totp_error = |message: String| {
+        let error_response = serde_json::json!({
+            "status": "error",
+            "message": format!("Error while setting up TOTP: {}", message),
+        });
+        (StatusCode::INTERNAL_SERVER_ERROR, Json(error_response))
+    };
+
+    let secret = Secret::Encoded(secret.to_string())
+        .to_bytes()
+        .map_err(|e| totp_error(format!("{:?}", e)))?;
+
+    TOTP::new(
+        Algorithm::SHA1,
+        6,
+        1,
+        30,
+        secret,
+        Some(MFA_ISSUER.to_string()),
+        guest.email_address.to_owned(),
+    )
+    .map_err(|e| totp_error(e.to_string()))
+}
+
+// Time step a code from the authenticator app was generated for, allowing one step of clock drift
+fn code_time_step(
+    secret: &str,
+    guest: &Guest,
+    code: &str,
+) -> Result<Option<i64>, (StatusCode, Json<serde_json::Value>)> {
+    let mut totp = build_totp(secret, guest)?;
+
+    // Check the steps one by one to know which one the code belongs to
+    let skew = u64::from(totp.skew);
+    totp.skew = 0;
+
+    let current = Utc::now().timestamp() as u64 / totp.step;
+    let time_step = (current.saturating_sub(skew)..=current + skew)
+        .find(|time_step| totp.check(code.trim(), time_step * totp.step));
+
+    Ok(time_step.map(|time_step| time_step as i64))
+}
+
+// Accept a code only once, and none older than the last accepted one
+async fn use_time_step(
+    data: &AppState,
+    guest: &Guest,
+    time_step: i64,
+) -> Result<bool, (StatusCode, Json<serde_json::Value>)> {
+    let rows_affected = sqlx::query!(
+        "update guest set mfa_last_time_step = $1
+        where id = $2 and (mfa_last_time_step is null or mfa_last_time_step < $1)",
+        time_step,
+        guest.id
+    )
+    .execute(&data.db)
+    .await
+    .map_err(|e| {
+        let error_response = serde_json::json!({
+            "status": "error",
+            "message": format!("Database error: {}", e),
+        });
+        (StatusCode::INTERNAL_SERVER_ERROR, Json(error_response))
+    })?
+    .rows_affected();
+
+    if rows_affected == 0 {
+        tracing::warn!(guest_id = guest.id, "authentication code replayed");
+    }
+
+    Ok(rows_affected == 1)
+}
+
+// Check a recovery code against the unused ones and burn it when it matches
+async fn use_recovery_code(
+    data: &AppState,
+    guest: &Guest,
+    recovery_code: &str,
+) -> Result<bool, (StatusCode, Json<serde_json::Value>)> {
+    let database_error = |e: sqlx::Error| {
+        let error_response = serde_json::json!({
+            "status": "error",
+            "message": format!("Database error: {}", e),
+        });
+        (StatusCode::INTERNAL_SERVER_ERROR, Json(error_response))
+    };
+
+    let codes = sqlx::query!(
+        "select id, code_hash from mfa_recovery_code where guest_id = $1 and used_at is null",
+        guest.id
+    )
+    .fetch_all(&data.db)
+    .await
+    .map_err(database_error)?;
+
+    let recovery_code = normalize_recovery_code(recovery_code);
+    let matching = codes
+        .into_iter()
+        .find(|code| match PasswordHash::new(&code.code_hash) {
+            Ok(parsed_hash) => Argon2::default()
+                .verify_password(recovery_code.as_bytes(), &parsed_hash)
+                .is_ok(),
+            Err(_) => false,
+        });
+
+    let Some(matching) = matching else {
+        return Ok(false);
+    };
+
+    // Only succeed if this request is the one that marked the code as used
+    let rows_affected = sqlx::query!(
+        "update mfa_recovery_code set used_at = now() where id = $1 and used_at is null",
+        matching.id
+    )
+    .execute(&data.db)
+    .await
+    .map_err(database_error)?
+    .rows_affected();
+
+    if rows_affected == 1 {
+        tracing::info!(guest_id = guest.id, "recovery code used");
+    }
+
+    Ok(rows_affected == 1)
+}
+
+// Generate a random recovery code formatted as `XXXXX-XXXXX`
+fn generate_recovery_code() -> String {
+    let chars = random_code(10);
+
+    format!("{}-{}", &chars[..5], &chars[5..])
+}
+
+// Recovery codes are compared without the dash and case insensitively
+fn normalize_recovery_code(code: &str) -> String {
+    code.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+// Hash a recovery code the same way passwords are hashed
+fn hash_recovery_code(code: &str) -> Result<String, (StatusCode, Json<serde_json::Value>)> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(normalize_recovery_code(code).as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| {
+            let error_response = serde_json::json!({
+                "status": "error",
+                "message": format!("Error while hashing recovery code: {}", e),
+            });
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(error_response))
+        })
+}
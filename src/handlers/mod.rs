@@ -1,7 +1,54 @@
+mod allotment;
 mod auth;
+mod availability;
 mod booking;
+mod exchange_rate;
+mod folio;
+mod front_desk;
+mod gift_voucher;
+mod group;
 mod health_check;
+mod housekeeping;
+mod invoice;
+mod loyalty;
+mod maintenance;
+mod mfa;
+mod night_audit;
+mod payment_method;
+mod pricing_rule;
+mod promo_code;
+mod rate_plan;
+mod report;
+mod room;
+mod room_type;
+mod stay_restriction;
+mod tax_rule;
+mod util;
+mod waitlist;
 
+pub use allotment::*;
 pub use auth::*;
+pub use availability::*;
 pub use booking::*;
+pub use exchange_rate::*;
+pub use folio::*;
+pub use front_desk::*;
+pub use gift_voucher::*;
+pub use group::*;
 pub use health_check::*;
+pub use housekeeping::*;
+pub use invoice::*;
+pub use loyalty::*;
+pub use maintenance::*;
+pub use mfa::*;
+pub use night_audit::*;
+pub use payment_method::*;
+pub use pricing_rule::*;
+pub use promo_code::*;
+pub use rate_plan::*;
+pub use report::*;
+pub use room::*;
+pub use room_type::*;
+pub use stay_restriction::*;
+pub use tax_rule::*;
+pub use waitlist::*;
@@ -0,0 +1,99 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Extension, Json,
+};
+
+use crate::{
+    business_date::current_business_date,
+    models::{Guest, NightAudit},
+    night_audit::{run_night_audit, AuditError},
+    schema::NightAuditOptions,
+    AppState,
+};
+
+use super::util::database_error;
+
+// Handler for staff to get the business date the books are open for
+pub async fn business_date_handler(
+    State(data): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let business_date = current_business_date(&data.db)
+        .await
+        .map_err(database_error)?;
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "business_date": business_date
+    });
+
+    Ok(Json(json_response))
+}
+
+// Handler for the night auditor to close the business date and open the next one
+pub async fn run_night_audit_handler(
+    State(data): State<Arc<AppState>>,
+    Extension(staff): Extension<Guest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let audit = run_night_audit(&data.db, Some(staff.id))
+        .await
+        .map_err(|e| match e {
+            AuditError::Rejected(message) => {
+                let error_response = serde_json::json!({
+                    "status": "fail",
+                    "message": message
+                });
+                (StatusCode::CONFLICT, Json(error_response))
+            }
+            AuditError::Database(e) => database_error(e),
+        })?;
+
+    tracing::info!(
+        business_date = %audit.business_date,
+        room_charges_posted = audit.room_charges_posted,
+        no_shows = audit.no_shows,
+        "night audit completed"
+    );
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "data": serde_json::json!({
+            "audit": audit,
+            "business_date": audit.business_date.succ_opt()
+        })
+    });
+
+    Ok((StatusCode::CREATED, Json(json_response)))
+}
+
+// Handler for staff to list the daily snapshots of the night audits, most recent first
+pub async fn night_audit_list_handler(
+    State(data): State<Arc<AppState>>,
+    opts: Option<Query<NightAuditOptions>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let Query(opts) = opts.unwrap_or_default();
+
+    let audits = sqlx::query_as!(
+        NightAudit,
+        "select * from night_audit
+        where ($1::date is null or business_date >= $1)
+            and ($2::date is null or business_date <= $2)
+        order by business_date desc",
+        opts.from,
+        opts.to
+    )
+    .fetch_all(&data.db)
+    .await
+    .map_err(database_error)?;
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "results": audits.len(),
+        "audits": audits
+    });
+
+    Ok(Json(json_response))
+}
@@ -0,0 +1,128 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Extension, Json,
+};
+use chrono::{Datelike, Utc};
+
+use crate::{
+    models::{Guest, PaymentMethod},
+    schema::CreatePaymentMethodSchema,
+    AppState,
+};
+
+use super::util::{bad_request, database_error};
+
+// Handler for the guest to list the payment methods they keep on file
+pub async fn list_payment_methods_handler(
+    State(data): State<Arc<AppState>>,
+    Extension(guest): Extension<Guest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let payment_methods = sqlx::query_as!(
+        PaymentMethod,
+        "select * from payment_method where guest_id = $1 order by id",
+        guest.id
+    )
+    .fetch_all(&data.db)
+    .await
+    .map_err(database_error)?;
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "results": payment_methods.len(),
+        "payment_methods": payment_methods
+    });
+
+    Ok(Json(json_response))
+}
+
+// Handler for the guest to keep a card on file, only allowed once their second factor is enabled
+pub async fn add_payment_method_handler(
+    State(data): State<Arc<AppState>>,
+    Extension(guest): Extension<Guest>,
+    Json(body): Json<CreatePaymentMethodSchema>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let brand = body.brand.trim().to_ascii_lowercase();
+    if brand.is_empty() || brand.len() > 20 {
+        return Err(bad_request(
+            "Card brand must be between 1 and 20 characters".to_string(),
+        ));
+    }
+
+    if body.last_four.len() != 4 || !body.last_four.chars().all(|c| c.is_ascii_digit()) {
+        return Err(bad_request(
+            "Last four must be the last 4 digits of the card".to_string(),
+        ));
+    }
+
+    if !(1..=12).contains(&body.exp_month) {
+        return Err(bad_request(
+            "Expiry month must be between 1 and 12".to_string(),
+        ));
+    }
+
+    let today = Utc::now().date_naive();
+    if (body.exp_year, body.exp_month as u32) < (today.year(), today.month()) {
+        return Err(bad_request("The card has expired".to_string()));
+    }
+
+    let payment_method = sqlx::query_as!(
+        PaymentMethod,
+        "insert into payment_method (guest_id, brand, last_four, exp_month, exp_year)
+        values ($1, $2, $3, $4, $5)
+        returning *",
+        guest.id,
+        brand,
+        body.last_four,
+        body.exp_month,
+        body.exp_year
+    )
+    .fetch_one(&data.db)
+    .await
+    .map_err(database_error)?;
+
+    tracing::info!(
+        payment_method_id = payment_method.id,
+        "payment method added"
+    );
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "data": serde_json::json!({
+            "payment_method": payment_method
+        })
+    });
+
+    Ok((StatusCode::CREATED, Json(json_response)))
+}
+
+// Handler for the guest to remove a payment method from their account
+pub async fn delete_payment_method_handler(
+    State(data): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Extension(guest): Extension<Guest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let deleted = sqlx::query!(
+        "delete from payment_method where id = $1 and guest_id = $2",
+        id,
+        guest.id
+    )
+    .execute(&data.db)
+    .await
+    .map_err(database_error)?;
+
+    if deleted.rows_affected() == 0 {
+        let error_response = serde_json::json!({
+            "status": "fail",
+            "message": format!("Payment method with ID: {} not found", id)
+        });
+        return Err((StatusCode::NOT_FOUND, Json(error_response)));
+    }
+
+    tracing::info!(payment_method_id = id, "payment method deleted");
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,302 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use bigdecimal::{BigDecimal, Zero};
+
+use crate::{
+    models::{PriceAdjustment, PricingRule},
+    schema::{CreatePricingRuleSchema, PriceAdjustmentOptions, UpdatePricingRuleSchema},
+    AppState,
+};
+
+use super::util::{bad_request, database_error};
+
+// Handler for admins to list the pricing rules in the order they are applied, inactive ones included
+pub async fn list_pricing_rules_handler(
+    State(data): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let rules = sqlx::query_as!(
+        PricingRule,
+        "select * from pricing_rule order by priority, id"
+    )
+    .fetch_all(&data.db)
+    .await
+    .map_err(database_error)?;
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "results": rules.len(),
+        "pricing_rules": rules
+    });
+
+    Ok(Json(json_response))
+}
+
+// Handler for admins to add a pricing rule, bookings already made keep their rates
+pub async fn create_pricing_rule_handler(
+    State(data): State<Arc<AppState>>,
+    Json(body): Json<CreatePricingRuleSchema>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    if body.name.trim().is_empty() {
+        return Err(bad_request("Pricing rule name can't be empty".to_string()));
+    }
+
+    validate_rule(&RuleSettings {
+        occupancy_above: body.occupancy_above.as_ref(),
+        occupancy_below: body.occupancy_below.as_ref(),
+        max_days_before_arrival: body.max_days_before_arrival,
+        adjustment_percent: &body.adjustment_percent,
+        floor_price: body.floor_price.as_ref(),
+        ceiling_price: body.ceiling_price.as_ref(),
+    })?;
+
+    if let Some(room_type_id) = body.room_type_id {
+        let exists = sqlx::query_scalar!(
+            "select exists(select 1 from room_type where id = $1) as \"exists!\"",
+            room_type_id
+        )
+        .fetch_one(&data.db)
+        .await
+        .map_err(database_error)?;
+
+        if !exists {
+            return Err(bad_request(format!(
+                "Room type with ID: {} not found",
+                room_type_id
+            )));
+        }
+    }
+
+    let rule = sqlx::query_as!(
+        PricingRule,
+        "insert into pricing_rule
+            (
+                name,
+                room_type_id,
+                occupancy_above,
+                occupancy_below,
+                max_days_before_arrival,
+                adjustment_percent,
+                floor_price,
+                ceiling_price,
+                priority
+            )
+        values ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        returning *",
+        body.name.trim(),
+        body.room_type_id,
+        body.occupancy_above,
+        body.occupancy_below,
+        body.max_days_before_arrival,
+        body.adjustment_percent,
+        body.floor_price,
+        body.ceiling_price,
+        body.priority.unwrap_or(0)
+    )
+    .fetch_one(&data.db)
+    .await
+    .map_err(database_error)?;
+
+    tracing::info!(pricing_rule_id = rule.id, "pricing rule created");
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "data": serde_json::json!({
+            "pricing_rule": rule
+        })
+    });
+
+    Ok((StatusCode::CREATED, Json(json_response)))
+}
+
+// Handler for admins to change or deactivate a pricing rule, bookings already made keep their rates
+pub async fn update_pricing_rule_handler(
+    State(data): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Json(body): Json<UpdatePricingRuleSchema>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let rule = sqlx::query_as!(PricingRule, "select * from pricing_rule where id = $1", id)
+        .fetch_optional(&data.db)
+        .await
+        .map_err(database_error)?
+        .ok_or_else(|| {
+            let error_response = serde_json::json!({
+                "status": "fail",
+                "message": format!("Pricing rule with ID: {} not found", id)
+            });
+            (StatusCode::NOT_FOUND, Json(error_response))
+        })?;
+
+    let name = body.name.unwrap_or(rule.name);
+    if name.trim().is_empty() {
+        return Err(bad_request("Pricing rule name can't be empty".to_string()));
+    }
+
+    // Conditions and bounds set to null are removed
+    let occupancy_above = body.occupancy_above.unwrap_or(rule.occupancy_above);
+    let occupancy_below = body.occupancy_below.unwrap_or(rule.occupancy_below);
+    let max_days_before_arrival = body
+        .max_days_before_arrival
+        .unwrap_or(rule.max_days_before_arrival);
+    let adjustment_percent = body.adjustment_percent.unwrap_or(rule.adjustment_percent);
+    let floor_price = body.floor_price.unwrap_or(rule.floor_price);
+    let ceiling_price = body.ceiling_price.unwrap_or(rule.ceiling_price);
+
+    validate_rule(&RuleSettings {
+        occupancy_above: occupancy_above.as_ref(),
+        occupancy_below: occupancy_below.as_ref(),
+        max_days_before_arrival,
+        adjustment_percent: &adjustment_percent,
+        floor_price: floor_price.as_ref(),
+        ceiling_price: ceiling_price.as_ref(),
+    })?;
+
+    let rule = sqlx::query_as!(
+        PricingRule,
+        "update pricing_rule set
+        name = $1,
+        occupancy_above = $2,
+        occupancy_below = $3,
+        max_days_before_arrival = $4,
+        adjustment_percent = $5,
+        floor_price = $6,
+        ceiling_price = $7,
+        priority = $8,
+        active = $9,
+        updated_at = now()
+        where id = $10
+        returning *",
+        name.trim(),
+        occupancy_above,
+        occupancy_below,
+        max_days_before_arrival,
+        adjustment_percent,
+        floor_price,
+        ceiling_price,
+        body.priority.unwrap_or(rule.priority),
+        body.active.unwrap_or(rule.active),
+        id
+    )
+    .fetch_one(&data.db)
+    .await
+    .map_err(database_error)?;
+
+    tracing::info!(
+        pricing_rule_id = rule.id,
+        active = rule.active,
+        "pricing rule updated"
+    );
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "data": serde_json::json!({
+            "pricing_rule": rule
+        })
+    });
+
+    Ok(Json(json_response))
+}
+
+// Handler for admins to see the changes the pricing rules made to the rates of bookings
+pub async fn list_price_adjustments_handler(
+    State(data): State<Arc<AppState>>,
+    opts: Option<Query<PriceAdjustmentOptions>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let Query(opts) = opts.unwrap_or_default();
+
+    let adjustments = sqlx::query_as!(
+        PriceAdjustment,
+        "select * from price_adjustment
+        where ($1::int is null or booking_id = $1)
+            and ($2::int is null or pricing_rule_id = $2)
+            and ($3::date is null or night >= $3)
+            and ($4::date is null or night <= $4)
+        order by night, booking_id, id",
+        opts.booking_id,
+        opts.pricing_rule_id,
+        opts.from,
+        opts.to
+    )
+    .fetch_all(&data.db)
+    .await
+    .map_err(database_error)?;
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "results": adjustments.len(),
+        "price_adjustments": adjustments
+    });
+
+    Ok(Json(json_response))
+}
+
+// Conditions and bounds of a rule, as they will be saved
+struct RuleSettings<'a> {
+    occupancy_above: Option<&'a BigDecimal>,
+    occupancy_below: Option<&'a BigDecimal>,
+    max_days_before_arrival: Option<i32>,
+    adjustment_percent: &'a BigDecimal,
+    floor_price: Option<&'a BigDecimal>,
+    ceiling_price: Option<&'a BigDecimal>,
+}
+
+// Util function to check a rule can match some nights and moves the rate within its bounds
+fn validate_rule(rule: &RuleSettings) -> Result<(), (StatusCode, Json<serde_json::Value>)> {
+    let hundred = BigDecimal::from(100);
+
+    for occupancy in [rule.occupancy_above, rule.occupancy_below]
+        .into_iter()
+        .flatten()
+    {
+        if occupancy < &BigDecimal::zero() || occupancy > &hundred {
+            return Err(bad_request(
+                "Occupancy must be a percentage between 0 and 100".to_string(),
+            ));
+        }
+    }
+
+    if let (Some(above), Some(below)) = (rule.occupancy_above, rule.occupancy_below) {
+        if above >= below {
+            return Err(bad_request(
+                "Occupancy above must be lower than occupancy below, or the rule never applies"
+                    .to_string(),
+            ));
+        }
+    }
+
+    if rule.max_days_before_arrival.is_some_and(|days| days < 0) {
+        return Err(bad_request(
+            "Days before arrival can't be negative".to_string(),
+        ));
+    }
+
+    if rule.adjustment_percent.is_zero() || rule.adjustment_percent <= &BigDecimal::from(-100) {
+        return Err(bad_request(
+            "Adjustment must be a non-zero percentage above -100".to_string(),
+        ));
+    }
+
+    if rule
+        .floor_price
+        .into_iter()
+        .chain(rule.ceiling_price)
+        .any(|price| price < &BigDecimal::zero())
+    {
+        return Err(bad_request("Prices can't be negative".to_string()));
+    }
+
+    if let (Some(floor), Some(ceiling)) = (rule.floor_price, rule.ceiling_price) {
+        if floor > ceiling {
+            return Err(bad_request(
+                "Floor price can't be above the ceiling price".to_string(),
+            ));
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,251 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use bigdecimal::{BigDecimal, Zero};
+
+use crate::{
+    models::PromoCode,
+    promo::DISCOUNT_TYPES,
+    schema::{CreatePromoCodeSchema, UpdatePromoCodeSchema},
+    AppState,
+};
+
+use super::util::{bad_request, database_error};
+
+// Handler for admins to list the promo codes with how often each was used
+pub async fn list_promo_codes_handler(
+    State(data): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let promo_codes = sqlx::query_as!(PromoCode, "select * from promo_code order by id")
+        .fetch_all(&data.db)
+        .await
+        .map_err(database_error)?;
+
+    let usage = sqlx::query!(
+        "select
+            promo_code_id,
+            count(*) as \"uses!\",
+            coalesce(sum(discount_amount), 0) as \"discount_total!\"
+        from promo_redemption
+        group by promo_code_id"
+    )
+    .fetch_all(&data.db)
+    .await
+    .map_err(database_error)?;
+
+    let promo_codes: Vec<serde_json::Value> = promo_codes
+        .into_iter()
+        .map(|promo| {
+            let usage = usage.iter().find(|usage| usage.promo_code_id == promo.id);
+            serde_json::json!({
+                "promo_code": promo,
+                "uses": usage.map_or(0, |usage| usage.uses),
+                "discount_total": usage.map_or_else(BigDecimal::zero, |usage| usage.discount_total.with_scale(2)),
+            })
+        })
+        .collect();
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "results": promo_codes.len(),
+        "promo_codes": promo_codes
+    });
+
+    Ok(Json(json_response))
+}
+
+// Handler for admins to create a promo code, codes are matched without case
+pub async fn create_promo_code_handler(
+    State(data): State<Arc<AppState>>,
+    Json(body): Json<CreatePromoCodeSchema>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    if !DISCOUNT_TYPES.contains(&body.discount_type.as_str()) {
+        return Err(bad_request(format!(
+            "Invalid discount type, expected one of: {}",
+            DISCOUNT_TYPES.join(", ")
+        )));
+    }
+
+    if body.discount_value <= BigDecimal::zero()
+        || (body.discount_type == "percentage" && body.discount_value > BigDecimal::from(100))
+    {
+        return Err(bad_request(
+            "Discount must be positive, and at most 100 for a percentage".to_string(),
+        ));
+    }
+
+    let min_nights = body.min_nights.unwrap_or(1);
+    validate_limits(
+        min_nights,
+        body.max_uses,
+        body.max_uses_per_guest,
+        &body.valid_from,
+        &body.valid_until,
+    )?;
+
+    let code = body.code.trim().to_uppercase();
+    if code.is_empty() {
+        return Err(bad_request("Promo code can't be empty".to_string()));
+    }
+
+    let promo = sqlx::query_as!(
+        PromoCode,
+        "insert into promo_code
+            (
+                code,
+                description,
+                discount_type,
+                discount_value,
+                valid_from,
+                valid_until,
+                min_nights,
+                max_uses,
+                max_uses_per_guest,
+                room_type_ids
+            )
+        values ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+        returning *",
+        code,
+        body.description,
+        body.discount_type,
+        body.discount_value,
+        body.valid_from,
+        body.valid_until,
+        min_nights,
+        body.max_uses,
+        body.max_uses_per_guest,
+        &body.room_type_ids.unwrap_or_default()
+    )
+    .fetch_one(&data.db)
+    .await
+    .map_err(|e| {
+        let is_duplicate = e
+            .as_database_error()
+            .and_then(|e| e.code())
+            .is_some_and(|code| code == "23505");
+
+        if is_duplicate {
+            let error_response = serde_json::json!({
+                "status": "fail",
+                "message": format!("Promo code {} already exists", code),
+            });
+            return (StatusCode::CONFLICT, Json(error_response));
+        }
+
+        database_error(e)
+    })?;
+
+    tracing::info!(promo_code_id = promo.id, "promo code created");
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "data": serde_json::json!({
+            "promo_code": promo
+        })
+    });
+
+    Ok((StatusCode::CREATED, Json(json_response)))
+}
+
+// Handler for admins to change the limits of a promo code or deactivate it
+pub async fn update_promo_code_handler(
+    State(data): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Json(body): Json<UpdatePromoCodeSchema>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let promo = sqlx::query_as!(PromoCode, "select * from promo_code where id = $1", id)
+        .fetch_optional(&data.db)
+        .await
+        .map_err(database_error)?
+        .ok_or_else(|| {
+            let error_response = serde_json::json!({
+                "status": "fail",
+                "message": format!("Promo code with ID: {} not found", id)
+            });
+            (StatusCode::NOT_FOUND, Json(error_response))
+        })?;
+
+    let min_nights = body.min_nights.unwrap_or(promo.min_nights);
+    let max_uses = body.max_uses.or(promo.max_uses);
+    let max_uses_per_guest = body.max_uses_per_guest.or(promo.max_uses_per_guest);
+    let valid_from = body.valid_from.or(promo.valid_from);
+    let valid_until = body.valid_until.or(promo.valid_until);
+    validate_limits(
+        min_nights,
+        max_uses,
+        max_uses_per_guest,
+        &valid_from,
+        &valid_until,
+    )?;
+
+    let promo = sqlx::query_as!(
+        PromoCode,
+        "update promo_code set
+        description = $1,
+        valid_from = $2,
+        valid_until = $3,
+        min_nights = $4,
+        max_uses = $5,
+        max_uses_per_guest = $6,
+        room_type_ids = $7,
+        active = $8,
+        updated_at = now()
+        where id = $9
+        returning *",
+        body.description.or(promo.description),
+        valid_from,
+        valid_until,
+        min_nights,
+        max_uses,
+        max_uses_per_guest,
+        &body.room_type_ids.unwrap_or(promo.room_type_ids),
+        body.active.unwrap_or(promo.active),
+        id
+    )
+    .fetch_one(&data.db)
+    .await
+    .map_err(database_error)?;
+
+    tracing::info!(promo_code_id = promo.id, "promo code updated");
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "data": serde_json::json!({
+            "promo_code": promo
+        })
+    });
+
+    Ok(Json(json_response))
+}
+
+// Util function to check the minimum stay, usage limits and validity window of a code
+fn validate_limits(
+    min_nights: i32,
+    max_uses: Option<i32>,
+    max_uses_per_guest: Option<i32>,
+    valid_from: &Option<chrono::DateTime<chrono::Utc>>,
+    valid_until: &Option<chrono::DateTime<chrono::Utc>>,
+) -> Result<(), (StatusCode, Json<serde_json::Value>)> {
+    if min_nights <= 0 {
+        return Err(bad_request("Min nights must be positive".to_string()));
+    }
+
+    if max_uses.is_some_and(|max_uses| max_uses <= 0)
+        || max_uses_per_guest.is_some_and(|max_uses| max_uses <= 0)
+    {
+        return Err(bad_request("Usage limits must be positive".to_string()));
+    }
+
+    if let (Some(valid_from), Some(valid_until)) = (valid_from, valid_until) {
+        if valid_until <= valid_from {
+            return Err(bad_request("Validity must end after it starts".to_string()));
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,197 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use bigdecimal::{BigDecimal, Zero};
+
+use crate::{
+    deposit::DEPOSIT_TYPES,
+    models::RatePlan,
+    schema::{CreateRatePlanSchema, UpdateRatePlanSchema},
+    AppState,
+};
+
+use super::util::{bad_request, database_error};
+
+// Handler to list the rate plans guests can book
+pub async fn rate_plan_list_handler(
+    State(data): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let plans = sqlx::query_as!(
+        RatePlan,
+        "select * from rate_plan where active = true order by id"
+    )
+    .fetch_all(&data.db)
+    .await
+    .map_err(database_error)?;
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "results": plans.len(),
+        "rate_plans": plans
+    });
+
+    Ok(Json(json_response))
+}
+
+// Handler for admins to list every rate plan, inactive ones included
+pub async fn list_rate_plans_handler(
+    State(data): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let plans = sqlx::query_as!(RatePlan, "select * from rate_plan order by id")
+        .fetch_all(&data.db)
+        .await
+        .map_err(database_error)?;
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "results": plans.len(),
+        "rate_plans": plans
+    });
+
+    Ok(Json(json_response))
+}
+
+// Handler for admins to add a rate plan
+pub async fn create_rate_plan_handler(
+    State(data): State<Arc<AppState>>,
+    Json(body): Json<CreateRatePlanSchema>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let deposit_value = body.deposit_value.unwrap_or_else(BigDecimal::zero);
+    let deposit_due_hours = body.deposit_due_hours.unwrap_or(48);
+    validate_deposit(&body.deposit_type, &deposit_value, deposit_due_hours)?;
+
+    let plan = sqlx::query_as!(
+        RatePlan,
+        "insert into rate_plan (code, name, deposit_type, deposit_value, deposit_due_hours)
+        values ($1, $2, $3, $4, $5)
+        returning *",
+        body.code,
+        body.name,
+        body.deposit_type,
+        deposit_value,
+        deposit_due_hours
+    )
+    .fetch_one(&data.db)
+    .await
+    .map_err(|e| {
+        let is_duplicate = e
+            .as_database_error()
+            .and_then(|e| e.code())
+            .is_some_and(|code| code == "23505");
+
+        if is_duplicate {
+            let error_response = serde_json::json!({
+                "status": "fail",
+                "message": format!("Rate plan with code: {} already exists", body.code),
+            });
+            return (StatusCode::CONFLICT, Json(error_response));
+        }
+
+        database_error(e)
+    })?;
+
+    tracing::info!(rate_plan_id = plan.id, "rate plan created");
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "data": serde_json::json!({
+            "rate_plan": plan
+        })
+    });
+
+    Ok((StatusCode::CREATED, Json(json_response)))
+}
+
+// Handler for admins to change or withdraw a rate plan, existing bookings keep their deposit
+pub async fn update_rate_plan_handler(
+    State(data): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Json(body): Json<UpdateRatePlanSchema>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let plan = sqlx::query_as!(RatePlan, "select * from rate_plan where id = $1", id)
+        .fetch_optional(&data.db)
+        .await
+        .map_err(database_error)?
+        .ok_or_else(|| {
+            let error_response = serde_json::json!({
+                "status": "fail",
+                "message": format!("Rate plan with ID: {} not found", id)
+            });
+            (StatusCode::NOT_FOUND, Json(error_response))
+        })?;
+
+    let deposit_type = body.deposit_type.unwrap_or(plan.deposit_type);
+    let deposit_value = body.deposit_value.unwrap_or(plan.deposit_value);
+    let deposit_due_hours = body.deposit_due_hours.unwrap_or(plan.deposit_due_hours);
+    validate_deposit(&deposit_type, &deposit_value, deposit_due_hours)?;
+
+    let plan = sqlx::query_as!(
+        RatePlan,
+        "update rate_plan set
+        name = $1,
+        deposit_type = $2,
+        deposit_value = $3,
+        deposit_due_hours = $4,
+        active = $5,
+        updated_at = now()
+        where id = $6
+        returning *",
+        body.name.unwrap_or(plan.name),
+        deposit_type,
+        deposit_value,
+        deposit_due_hours,
+        body.active.unwrap_or(plan.active),
+        id
+    )
+    .fetch_one(&data.db)
+    .await
+    .map_err(database_error)?;
+
+    tracing::info!(rate_plan_id = plan.id, "rate plan updated");
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "data": serde_json::json!({
+            "rate_plan": plan
+        })
+    });
+
+    Ok(Json(json_response))
+}
+
+// Util function to check the deposit policy of a rate plan
+fn validate_deposit(
+    deposit_type: &str,
+    deposit_value: &BigDecimal,
+    deposit_due_hours: i32,
+) -> Result<(), (StatusCode, Json<serde_json::Value>)> {
+    if !DEPOSIT_TYPES.contains(&deposit_type) {
+        return Err(bad_request(format!(
+            "Invalid deposit type, expected one of: {}",
+            DEPOSIT_TYPES.join(", ")
+        )));
+    }
+
+    if deposit_value < &BigDecimal::zero() {
+        return Err(bad_request("Deposit value can't be negative".to_string()));
+    }
+
+    if deposit_type == "percentage" && deposit_value > &BigDecimal::from(100) {
+        return Err(bad_request(
+            "Deposit percentage can't be over 100".to_string(),
+        ));
+    }
+
+    if deposit_due_hours <= 0 {
+        return Err(bad_request(
+            "Deposit due hours must be positive".to_string(),
+        ));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,181 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+
+use crate::{
+    pace::{pace_report, pickup_csv, pickup_report, MAX_PACE_DAYS},
+    report::{kpi_csv, kpi_report, KpiQuery, MAX_REPORT_DAYS, REPORT_PERIODS},
+    schema::{KpiReportOptions, PaceReportOptions, PickupReportOptions},
+    AppState,
+};
+
+use super::util::{bad_request, database_error};
+
+// Handler for management to get the occupancy rate, ADR and RevPAR by day, week or month
+pub async fn kpi_report_handler(
+    State(data): State<Arc<AppState>>,
+    Query(opts): Query<KpiReportOptions>,
+) -> Result<Response, (StatusCode, Json<serde_json::Value>)> {
+    let period = opts.period.as_deref().unwrap_or("day");
+    if !REPORT_PERIODS.contains(&period) {
+        return Err(bad_request(format!(
+            "Invalid period, expected one of: {}",
+            REPORT_PERIODS.join(", ")
+        )));
+    }
+
+    let format = opts.format.as_deref().unwrap_or("json");
+    if !["json", "csv"].contains(&format) {
+        return Err(bad_request(
+            "Invalid format, expected one of: json, csv".to_string(),
+        ));
+    }
+
+    if opts.to < opts.from {
+        return Err(bad_request(
+            "The end of the report can't be before its start".to_string(),
+        ));
+    }
+
+    if (opts.to - opts.from).num_days() >= MAX_REPORT_DAYS {
+        return Err(bad_request(format!(
+            "A report can cover at most {} days",
+            MAX_REPORT_DAYS
+        )));
+    }
+
+    let report = kpi_report(
+        &data.db,
+        &KpiQuery {
+            from: opts.from,
+            to: opts.to,
+            period,
+            room_type_id: opts.room_type_id,
+            by_room_type: opts.by_room_type.unwrap_or(false),
+        },
+    )
+    .await
+    .map_err(database_error)?;
+
+    if format == "csv" {
+        return Ok((
+            [
+                (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+                (
+                    header::CONTENT_DISPOSITION,
+                    format!(
+                        "attachment; filename=\"kpi-{}-{}-{}.csv\"",
+                        period, report.from, report.to
+                    ),
+                ),
+            ],
+            kpi_csv(&report),
+        )
+            .into_response());
+    }
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "data": serde_json::json!({
+            "report": report
+        })
+    });
+
+    Ok(Json(json_response).into_response())
+}
+
+// Handler for revenue managers to see how the bookings of a stay date came in, against last year
+pub async fn pace_report_handler(
+    State(data): State<Arc<AppState>>,
+    Query(opts): Query<PaceReportOptions>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let days_before = opts.days_before.unwrap_or(90);
+    if !(0..=MAX_PACE_DAYS).contains(&days_before) {
+        return Err(bad_request(format!(
+            "Days before must be between 0 and {}",
+            MAX_PACE_DAYS
+        )));
+    }
+
+    let report = pace_report(&data.db, opts.stay_date, opts.room_type_id, days_before)
+        .await
+        .map_err(database_error)?;
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "data": serde_json::json!({
+            "report": report
+        })
+    });
+
+    Ok(Json(json_response))
+}
+
+// Handler for revenue managers to get the pickup and occupancy forecast of upcoming stay dates
+pub async fn pickup_report_handler(
+    State(data): State<Arc<AppState>>,
+    Query(opts): Query<PickupReportOptions>,
+) -> Result<Response, (StatusCode, Json<serde_json::Value>)> {
+    let format = opts.format.as_deref().unwrap_or("json");
+    if !["json", "csv"].contains(&format) {
+        return Err(bad_request(
+            "Invalid format, expected one of: json, csv".to_string(),
+        ));
+    }
+
+    if opts.to < opts.from {
+        return Err(bad_request(
+            "The end of the report can't be before its start".to_string(),
+        ));
+    }
+
+    if (opts.to - opts.from).num_days() >= i64::from(MAX_PACE_DAYS) {
+        return Err(bad_request(format!(
+            "A report can cover at most {} days",
+            MAX_PACE_DAYS
+        )));
+    }
+
+    let pickup_days = opts.pickup_days.unwrap_or(7);
+    if !(1..=MAX_PACE_DAYS).contains(&pickup_days) {
+        return Err(bad_request(format!(
+            "Pickup days must be between 1 and {}",
+            MAX_PACE_DAYS
+        )));
+    }
+
+    let rows = pickup_report(&data.db, opts.from, opts.to, opts.room_type_id, pickup_days)
+        .await
+        .map_err(database_error)?;
+
+    if format == "csv" {
+        return Ok((
+            [
+                (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+                (
+                    header::CONTENT_DISPOSITION,
+                    format!(
+                        "attachment; filename=\"pickup-{}-{}.csv\"",
+                        opts.from, opts.to
+                    ),
+                ),
+            ],
+            pickup_csv(&rows),
+        )
+            .into_response());
+    }
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "pickup_days": pickup_days,
+        "results": rows.len(),
+        "rows": rows
+    });
+
+    Ok(Json(json_response).into_response())
+}
@@ -0,0 +1,167 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+
+use crate::{
+    models::Room,
+    schema::{CreateRoomSchema, UpdateRoomSchema},
+    AppState,
+};
+
+use super::util::{bad_request, database_error};
+
+// Handler for admins to list every room of the property
+pub async fn list_rooms_handler(
+    State(data): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let rooms = sqlx::query_as!(Room, "select * from room order by room_number")
+        .fetch_all(&data.db)
+        .await
+        .map_err(database_error)?;
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "results": rooms.len(),
+        "rooms": rooms
+    });
+
+    Ok(Json(json_response))
+}
+
+// Handler for admins to add a room to the inventory of a room type
+pub async fn create_room_handler(
+    State(data): State<Arc<AppState>>,
+    Json(body): Json<CreateRoomSchema>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let room_number = body.room_number.trim();
+    if room_number.is_empty() {
+        return Err(bad_request("Room number can't be empty".to_string()));
+    }
+
+    ensure_room_type_exists(&data, body.room_type_id).await?;
+
+    let room = sqlx::query_as!(
+        Room,
+        "insert into room (room_number, room_type_id, floor, accessible, near_elevator)
+        values ($1, $2, $3, $4, $5)
+        returning *",
+        room_number,
+        body.room_type_id,
+        body.floor,
+        body.accessible.unwrap_or(false),
+        body.near_elevator.unwrap_or(false)
+    )
+    .fetch_one(&data.db)
+    .await
+    .map_err(|e| {
+        let is_duplicate = e
+            .as_database_error()
+            .and_then(|e| e.code())
+            .is_some_and(|code| code == "23505");
+
+        if is_duplicate {
+            let error_response = serde_json::json!({
+                "status": "fail",
+                "message": format!("Room {} already exists", room_number),
+            });
+            return (StatusCode::CONFLICT, Json(error_response));
+        }
+
+        database_error(e)
+    })?;
+
+    tracing::info!(room_id = room.id, "room created");
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "data": serde_json::json!({
+            "room": room
+        })
+    });
+
+    Ok((StatusCode::CREATED, Json(json_response)))
+}
+
+// Handler for admins to move a room to another type or take it out of the inventory
+pub async fn update_room_handler(
+    State(data): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Json(body): Json<UpdateRoomSchema>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let room = sqlx::query_as!(Room, "select * from room where id = $1", id)
+        .fetch_optional(&data.db)
+        .await
+        .map_err(database_error)?
+        .ok_or_else(|| {
+            let error_response = serde_json::json!({
+                "status": "fail",
+                "message": format!("Room with ID: {} not found", id)
+            });
+            (StatusCode::NOT_FOUND, Json(error_response))
+        })?;
+
+    if let Some(room_type_id) = body.room_type_id {
+        ensure_room_type_exists(&data, room_type_id).await?;
+    }
+
+    let room = sqlx::query_as!(
+        Room,
+        "update room set
+        room_type_id = $1,
+        floor = $2,
+        active = $3,
+        accessible = $4,
+        near_elevator = $5,
+        updated_at = now()
+        where id = $6
+        returning *",
+        body.room_type_id.unwrap_or(room.room_type_id),
+        body.floor.unwrap_or(room.floor),
+        body.active.unwrap_or(room.active),
+        body.accessible.unwrap_or(room.accessible),
+        body.near_elevator.unwrap_or(room.near_elevator),
+        id
+    )
+    .fetch_one(&data.db)
+    .await
+    .map_err(database_error)?;
+
+    tracing::info!(room_id = room.id, "room updated");
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "data": serde_json::json!({
+            "room": room
+        })
+    });
+
+    Ok(Json(json_response))
+}
+
+// Util function to return 400 when the room type doesn't exist
+async fn ensure_room_type_exists(
+    data: &AppState,
+    room_type_id: i32,
+) -> Result<(), (StatusCode, Json<serde_json::Value>)> {
+    let room_type_exists: bool = sqlx::query_scalar!(
+        "select exists(select 1 from room_type where id = $1) as \"exists!\"",
+        room_type_id
+    )
+    .fetch_one(&data.db)
+    .await
+    .map_err(database_error)?;
+
+    if !room_type_exists {
+        return Err(bad_request(format!(
+            "Room type with ID: {} not found",
+            room_type_id
+        )));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,159 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use bigdecimal::{BigDecimal, Zero};
+
+use crate::{
+    models::RoomType,
+    schema::{CreateRoomTypeSchema, UpdateRoomTypeSchema},
+    AppState,
+};
+
+use super::util::{bad_request, database_error};
+
+// Handler to list the room types guests can book
+pub async fn room_type_list_handler(
+    State(data): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let room_types = sqlx::query_as!(
+        RoomType,
+        "select * from room_type where active = true order by id"
+    )
+    .fetch_all(&data.db)
+    .await
+    .map_err(database_error)?;
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "results": room_types.len(),
+        "room_types": room_types
+    });
+
+    Ok(Json(json_response))
+}
+
+// Handler for admins to add a room type
+pub async fn create_room_type_handler(
+    State(data): State<Arc<AppState>>,
+    Json(body): Json<CreateRoomTypeSchema>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    validate_room_type(&body.base_rate, body.max_occupancy)?;
+
+    let room_type = sqlx::query_as!(
+        RoomType,
+        "insert into room_type (code, name, description, base_rate, max_occupancy)
+        values ($1, $2, $3, $4, $5)
+        returning *",
+        body.code,
+        body.name,
+        body.description,
+        body.base_rate,
+        body.max_occupancy
+    )
+    .fetch_one(&data.db)
+    .await
+    .map_err(|e| {
+        let is_duplicate = e
+            .as_database_error()
+            .and_then(|e| e.code())
+            .is_some_and(|code| code == "23505");
+
+        if is_duplicate {
+            let error_response = serde_json::json!({
+                "status": "fail",
+                "message": format!("Room type with code: {} already exists", body.code),
+            });
+            return (StatusCode::CONFLICT, Json(error_response));
+        }
+
+        database_error(e)
+    })?;
+
+    tracing::info!(room_type_id = room_type.id, "room type created");
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "data": serde_json::json!({
+            "room_type": room_type
+        })
+    });
+
+    Ok((StatusCode::CREATED, Json(json_response)))
+}
+
+// Handler for admins to change or withdraw a room type, existing bookings keep their price
+pub async fn update_room_type_handler(
+    State(data): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Json(body): Json<UpdateRoomTypeSchema>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let room_type = sqlx::query_as!(RoomType, "select * from room_type where id = $1", id)
+        .fetch_optional(&data.db)
+        .await
+        .map_err(database_error)?
+        .ok_or_else(|| {
+            let error_response = serde_json::json!({
+                "status": "fail",
+                "message": format!("Room type with ID: {} not found", id)
+            });
+            (StatusCode::NOT_FOUND, Json(error_response))
+        })?;
+
+    let base_rate = body.base_rate.unwrap_or(room_type.base_rate);
+    let max_occupancy = body.max_occupancy.unwrap_or(room_type.max_occupancy);
+    validate_room_type(&base_rate, max_occupancy)?;
+
+    let room_type = sqlx::query_as!(
+        RoomType,
+        "update room_type set
+        name = $1,
+        description = $2,
+        base_rate = $3,
+        max_occupancy = $4,
+        active = $5,
+        updated_at = now()
+        where id = $6
+        returning *",
+        body.name.unwrap_or(room_type.name),
+        body.description.or(room_type.description),
+        base_rate,
+        max_occupancy,
+        body.active.unwrap_or(room_type.active),
+        id
+    )
+    .fetch_one(&data.db)
+    .await
+    .map_err(database_error)?;
+
+    tracing::info!(room_type_id = room_type.id, "room type updated");
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "data": serde_json::json!({
+            "room_type": room_type
+        })
+    });
+
+    Ok(Json(json_response))
+}
+
+// Util function to check the rate and occupancy of a room type
+fn validate_room_type(
+    base_rate: &BigDecimal,
+    max_occupancy: i32,
+) -> Result<(), (StatusCode, Json<serde_json::Value>)> {
+    if base_rate < &BigDecimal::zero() {
+        return Err(bad_request("Base rate can't be negative".to_string()));
+    }
+
+    if max_occupancy <= 0 {
+        return Err(bad_request("Max occupancy must be positive".to_string()));
+    }
+
+    Ok(())
+}
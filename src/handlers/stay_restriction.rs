@@ -0,0 +1,274 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Extension, Json,
+};
+use chrono::NaiveDate;
+
+use crate::{
+    models::{Guest, StayRestriction},
+    schema::{CreateStayRestrictionSchema, StayRestrictionOptions, UpdateStayRestrictionSchema},
+    AppState,
+};
+
+use super::util::{bad_request, database_error};
+
+// Handler for admins to list the restrictions, by date
+pub async fn list_stay_restrictions_handler(
+    State(data): State<Arc<AppState>>,
+    opts: Option<Query<StayRestrictionOptions>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let Query(opts) = opts.unwrap_or_default();
+
+    let restrictions = sqlx::query_as!(
+        StayRestriction,
+        "select * from stay_restriction
+        where ($1::date is null or end_date >= $1)
+            and ($2::date is null or start_date <= $2)
+            and ($3::int is null or room_type_id is null or room_type_id = $3)
+        order by start_date, id",
+        opts.from,
+        opts.to,
+        opts.room_type_id
+    )
+    .fetch_all(&data.db)
+    .await
+    .map_err(database_error)?;
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "results": restrictions.len(),
+        "stay_restrictions": restrictions
+    });
+
+    Ok(Json(json_response))
+}
+
+// Handler for admins to restrict the stays on some dates, bookings already made are kept
+pub async fn create_stay_restriction_handler(
+    State(data): State<Arc<AppState>>,
+    Extension(admin): Extension<Guest>,
+    Json(body): Json<CreateStayRestrictionSchema>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let closed_to_arrival = body.closed_to_arrival.unwrap_or(false);
+    let closed_to_departure = body.closed_to_departure.unwrap_or(false);
+    let stop_sell = body.stop_sell.unwrap_or(false);
+
+    validate_restriction(
+        body.start_date,
+        body.end_date,
+        body.min_stay,
+        body.max_stay,
+        closed_to_arrival || closed_to_departure || stop_sell,
+    )?;
+
+    if let Some(room_type_id) = body.room_type_id {
+        let exists = sqlx::query_scalar!(
+            "select exists(select 1 from room_type where id = $1) as \"exists!\"",
+            room_type_id
+        )
+        .fetch_one(&data.db)
+        .await
+        .map_err(database_error)?;
+
+        if !exists {
+            return Err(bad_request(format!(
+                "Room type with ID: {} not found",
+                room_type_id
+            )));
+        }
+    }
+
+    let restriction = sqlx::query_as!(
+        StayRestriction,
+        "insert into stay_restriction
+            (
+                room_type_id,
+                start_date,
+                end_date,
+                min_stay,
+                max_stay,
+                closed_to_arrival,
+                closed_to_departure,
+                stop_sell,
+                reason,
+                created_by
+            )
+        values ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+        returning *",
+        body.room_type_id,
+        body.start_date,
+        body.end_date,
+        body.min_stay,
+        body.max_stay,
+        closed_to_arrival,
+        closed_to_departure,
+        stop_sell,
+        body.reason,
+        admin.id
+    )
+    .fetch_one(&data.db)
+    .await
+    .map_err(database_error)?;
+
+    tracing::info!(
+        stay_restriction_id = restriction.id,
+        "stay restriction created"
+    );
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "data": serde_json::json!({
+            "stay_restriction": restriction
+        })
+    });
+
+    Ok((StatusCode::CREATED, Json(json_response)))
+}
+
+// Handler for admins to change a restriction, bookings already made are kept
+pub async fn update_stay_restriction_handler(
+    State(data): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Json(body): Json<UpdateStayRestrictionSchema>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let restriction = sqlx::query_as!(
+        StayRestriction,
+        "select * from stay_restriction where id = $1",
+        id
+    )
+    .fetch_optional(&data.db)
+    .await
+    .map_err(database_error)?
+    .ok_or_else(|| stay_restriction_not_found(id))?;
+
+    let start_date = body.start_date.unwrap_or(restriction.start_date);
+    let end_date = body.end_date.unwrap_or(restriction.end_date);
+    let min_stay = body.min_stay.or(restriction.min_stay);
+    let max_stay = body.max_stay.or(restriction.max_stay);
+    let closed_to_arrival = body
+        .closed_to_arrival
+        .unwrap_or(restriction.closed_to_arrival);
+    let closed_to_departure = body
+        .closed_to_departure
+        .unwrap_or(restriction.closed_to_departure);
+    let stop_sell = body.stop_sell.unwrap_or(restriction.stop_sell);
+
+    validate_restriction(
+        start_date,
+        end_date,
+        min_stay,
+        max_stay,
+        closed_to_arrival || closed_to_departure || stop_sell,
+    )?;
+
+    let restriction = sqlx::query_as!(
+        StayRestriction,
+        "update stay_restriction set
+        start_date = $1,
+        end_date = $2,
+        min_stay = $3,
+        max_stay = $4,
+        closed_to_arrival = $5,
+        closed_to_departure = $6,
+        stop_sell = $7,
+        reason = $8,
+        updated_at = now()
+        where id = $9
+        returning *",
+        start_date,
+        end_date,
+        min_stay,
+        max_stay,
+        closed_to_arrival,
+        closed_to_departure,
+        stop_sell,
+        body.reason.or(restriction.reason),
+        id
+    )
+    .fetch_one(&data.db)
+    .await
+    .map_err(database_error)?;
+
+    tracing::info!(
+        stay_restriction_id = restriction.id,
+        "stay restriction updated"
+    );
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "data": serde_json::json!({
+            "stay_restriction": restriction
+        })
+    });
+
+    Ok(Json(json_response))
+}
+
+// Handler for admins to lift a restriction
+pub async fn delete_stay_restriction_handler(
+    State(data): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let deleted = sqlx::query!("delete from stay_restriction where id = $1", id)
+        .execute(&data.db)
+        .await
+        .map_err(database_error)?;
+
+    if deleted.rows_affected() == 0 {
+        return Err(stay_restriction_not_found(id));
+    }
+
+    tracing::info!(stay_restriction_id = id, "stay restriction deleted");
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// Util function to check the dates and that the restriction restricts something
+fn validate_restriction(
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+    min_stay: Option<i32>,
+    max_stay: Option<i32>,
+    closed: bool,
+) -> Result<(), (StatusCode, Json<serde_json::Value>)> {
+    if end_date < start_date {
+        return Err(bad_request(
+            "End date can't be before the start date".to_string(),
+        ));
+    }
+
+    if min_stay.is_some_and(|nights| nights < 1) || max_stay.is_some_and(|nights| nights < 1) {
+        return Err(bad_request(
+            "Minimum and maximum stays must be at least 1 night".to_string(),
+        ));
+    }
+
+    if let (Some(min_stay), Some(max_stay)) = (min_stay, max_stay) {
+        if max_stay < min_stay {
+            return Err(bad_request(
+                "Maximum stay can't be shorter than the minimum stay".to_string(),
+            ));
+        }
+    }
+
+    if min_stay.is_none() && max_stay.is_none() && !closed {
+        return Err(bad_request(
+            "A restriction needs a stay length, closed to arrival, closed to departure or stop sell"
+                .to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+fn stay_restriction_not_found(id: i32) -> (StatusCode, Json<serde_json::Value>) {
+    let error_response = serde_json::json!({
+        "status": "fail",
+        "message": format!("Stay restriction with ID: {} not found", id)
+    });
+    (StatusCode::NOT_FOUND, Json(error_response))
+}
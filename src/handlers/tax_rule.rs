@@ -0,0 +1,170 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use bigdecimal::{BigDecimal, Zero};
+
+use crate::{
+    folio::LINE_TYPES,
+    models::TaxRule,
+    schema::{CreateTaxRuleSchema, UpdateTaxRuleSchema},
+    tax::TAX_KINDS,
+    AppState,
+};
+
+use super::util::{bad_request, database_error};
+
+// Handler for admins to list every tax rule, inactive ones included
+pub async fn list_tax_rules_handler(
+    State(data): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let rules = sqlx::query_as!(TaxRule, "select * from tax_rule order by id")
+        .fetch_all(&data.db)
+        .await
+        .map_err(database_error)?;
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "results": rules.len(),
+        "tax_rules": rules
+    });
+
+    Ok(Json(json_response))
+}
+
+// Handler for admins to add a tax rule, it applies to charges posted from now on
+pub async fn create_tax_rule_handler(
+    State(data): State<Arc<AppState>>,
+    Json(body): Json<CreateTaxRuleSchema>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    if !TAX_KINDS.contains(&body.kind.as_str()) {
+        return Err(bad_request(format!(
+            "Invalid tax kind, expected one of: {}",
+            TAX_KINDS.join(", ")
+        )));
+    }
+
+    let line_types = body.line_types.unwrap_or_else(|| vec!["room".to_string()]);
+    validate_rule(&body.kind, &body.rate, &line_types)?;
+
+    let rule = sqlx::query_as!(
+        TaxRule,
+        "insert into tax_rule (name, kind, rate, line_types, exempt_children)
+        values ($1, $2, $3, $4, $5)
+        returning *",
+        body.name,
+        body.kind,
+        body.rate,
+        &line_types,
+        body.exempt_children.unwrap_or(false)
+    )
+    .fetch_one(&data.db)
+    .await
+    .map_err(database_error)?;
+
+    tracing::info!(tax_rule_id = rule.id, "tax rule created");
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "data": serde_json::json!({
+            "tax_rule": rule
+        })
+    });
+
+    Ok((StatusCode::CREATED, Json(json_response)))
+}
+
+// Handler for admins to change or deactivate a tax rule, lines already posted keep their tax
+pub async fn update_tax_rule_handler(
+    State(data): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Json(body): Json<UpdateTaxRuleSchema>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let rule = sqlx::query_as!(TaxRule, "select * from tax_rule where id = $1", id)
+        .fetch_optional(&data.db)
+        .await
+        .map_err(database_error)?
+        .ok_or_else(|| {
+            let error_response = serde_json::json!({
+                "status": "fail",
+                "message": format!("Tax rule with ID: {} not found", id)
+            });
+            (StatusCode::NOT_FOUND, Json(error_response))
+        })?;
+
+    let rate = body.rate.unwrap_or(rule.rate);
+    let line_types = body.line_types.unwrap_or(rule.line_types);
+    validate_rule(&rule.kind, &rate, &line_types)?;
+
+    let rule = sqlx::query_as!(
+        TaxRule,
+        "update tax_rule set
+        name = $1,
+        rate = $2,
+        line_types = $3,
+        exempt_children = $4,
+        active = $5,
+        updated_at = now()
+        where id = $6
+        returning *",
+        body.name.unwrap_or(rule.name),
+        rate,
+        &line_types,
+        body.exempt_children.unwrap_or(rule.exempt_children),
+        body.active.unwrap_or(rule.active),
+        id
+    )
+    .fetch_one(&data.db)
+    .await
+    .map_err(database_error)?;
+
+    tracing::info!(
+        tax_rule_id = rule.id,
+        active = rule.active,
+        "tax rule updated"
+    );
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "data": serde_json::json!({
+            "tax_rule": rule
+        })
+    });
+
+    Ok(Json(json_response))
+}
+
+// Util function to check the rate and the charge types a rule applies to
+fn validate_rule(
+    kind: &str,
+    rate: &BigDecimal,
+    line_types: &[String],
+) -> Result<(), (StatusCode, Json<serde_json::Value>)> {
+    if rate < &BigDecimal::zero() {
+        return Err(bad_request("Tax rate can't be negative".to_string()));
+    }
+
+    if line_types.is_empty()
+        || line_types
+            .iter()
+            .any(|line_type| !LINE_TYPES.contains(&line_type.as_str()))
+    {
+        return Err(bad_request(format!(
+            "Invalid line types, expected some of: {}",
+            LINE_TYPES.join(", ")
+        )));
+    }
+
+    // Per person taxes are charged per night of the stay, only room charges are per night
+    if kind == "per_person_per_night" && line_types.iter().any(|line_type| line_type != "room") {
+        return Err(bad_request(
+            "Per person per night taxes can only apply to room charges".to_string(),
+        ));
+    }
+
+    Ok(())
+}
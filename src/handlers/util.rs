@@ -0,0 +1,69 @@
+use axum::{http::StatusCode, Json};
+
+use crate::AppState;
+
+// Util function to return 404 when the booking doesn't exist
+pub(super) async fn ensure_booking_exists(
+    data: &AppState,
+    id: i32,
+) -> Result<(), (StatusCode, Json<serde_json::Value>)> {
+    let booking_exists: bool = sqlx::query_scalar!(
+        "select exists(select 1 from booking where id = $1) as \"exists!\"",
+        id
+    )
+    .fetch_one(&data.db)
+    .await
+    .map_err(database_error)?;
+
+    if !booking_exists {
+        return Err(booking_not_found(id));
+    }
+
+    Ok(())
+}
+
+// Util function to return 404 when the booking doesn't exist or belongs to another guest
+pub(super) async fn ensure_guest_booking_exists(
+    data: &AppState,
+    id: i32,
+    guest_id: i32,
+) -> Result<(), (StatusCode, Json<serde_json::Value>)> {
+    let booking_exists: bool = sqlx::query_scalar!(
+        "select exists(select 1 from booking where id = $1 and guest_id = $2) as \"exists!\"",
+        id,
+        guest_id
+    )
+    .fetch_one(&data.db)
+    .await
+    .map_err(database_error)?;
+
+    if !booking_exists {
+        return Err(booking_not_found(id));
+    }
+
+    Ok(())
+}
+
+pub(super) fn booking_not_found(id: i32) -> (StatusCode, Json<serde_json::Value>) {
+    let error_response = serde_json::json!({
+        "status": "fail",
+        "message": format!("Booking with ID: {} not found", id)
+    });
+    (StatusCode::NOT_FOUND, Json(error_response))
+}
+
+pub(super) fn bad_request(message: String) -> (StatusCode, Json<serde_json::Value>) {
+    let error_response = serde_json::json!({
+        "status": "fail",
+        "message": message
+    });
+    (StatusCode::BAD_REQUEST, Json(error_response))
+}
+
+pub(super) fn database_error(e: sqlx::Error) -> (StatusCode, Json<serde_json::Value>) {
+    let error_response = serde_json::json!({
+        "status": "error",
+        "message": format!("Database error: {}", e),
+    });
+    (StatusCode::INTERNAL_SERVER_ERROR, Json(error_response))
+}
@@ -0,0 +1,294 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Extension, Json,
+};
+
+use crate::{
+    availability::available_rooms,
+    business_date::current_business_date,
+    models::{Guest, Notification, RoomType, WaitlistEntry},
+    schema::{JoinWaitlistSchema, WaitlistOptions},
+    waitlist::{
+        offer_freed_rooms, WAITLIST_STATUSES, WAITLIST_STATUS_CANCELLED, WAITLIST_STATUS_OFFERED,
+        WAITLIST_STATUS_WAITING,
+    },
+    AppState,
+};
+
+use super::{
+    booking::ensure_stay_allowed,
+    util::{bad_request, database_error},
+};
+
+// Handler for a guest to wait for a room type sold out on their dates
+pub async fn join_waitlist_handler(
+    State(data): State<Arc<AppState>>,
+    Extension(guest): Extension<Guest>,
+    Json(body): Json<JoinWaitlistSchema>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    if body.checkout_date <= body.checkin_date {
+        return Err(bad_request(
+            "Check-out date must be after the check-in date".to_string(),
+        ));
+    }
+
+    let today = current_business_date(&data.db)
+        .await
+        .map_err(database_error)?;
+    if body.checkin_date < today {
+        return Err(bad_request(
+            "Check-in date can't be in the past".to_string(),
+        ));
+    }
+
+    let num_adults = body.num_adults.unwrap_or(1);
+    let num_children = body.num_children.unwrap_or(0);
+    if num_adults < 1 {
+        return Err(bad_request("A stay needs at least one adult".to_string()));
+    }
+
+    if num_children < 0 {
+        return Err(bad_request(
+            "Number of children can't be negative".to_string(),
+        ));
+    }
+
+    let room_type = sqlx::query_as!(
+        RoomType,
+        "select * from room_type where id = $1 and active = true",
+        body.room_type_id
+    )
+    .fetch_optional(&data.db)
+    .await
+    .map_err(database_error)?
+    .ok_or_else(|| {
+        bad_request(format!(
+            "Room type with ID: {} not found",
+            body.room_type_id
+        ))
+    })?;
+
+    if num_adults + num_children > room_type.max_occupancy {
+        return Err(bad_request(format!(
+            "A {} sleeps at most {} guests",
+            room_type.name, room_type.max_occupancy
+        )));
+    }
+
+    // Waiting is pointless for a stay that couldn't be booked once a room is free
+    ensure_stay_allowed(
+        &data.db,
+        Some(room_type.id),
+        body.checkin_date,
+        body.checkout_date,
+    )
+    .await?;
+
+    let mut conn = data.db.acquire().await.map_err(database_error)?;
+    let available = available_rooms(
+        &mut conn,
+        room_type.id,
+        body.checkin_date,
+        body.checkout_date,
+    )
+    .await
+    .map_err(database_error)?;
+    if available > 0 {
+        let error_response = serde_json::json!({
+            "status": "fail",
+            "message": format!(
+                "A {} is available from {} to {}, book it instead",
+                room_type.name, body.checkin_date, body.checkout_date
+            )
+        });
+        return Err((StatusCode::CONFLICT, Json(error_response)));
+    }
+
+    let entry = sqlx::query_as!(
+        WaitlistEntry,
+        "insert into waitlist_entry
+            (guest_id, room_type_id, checkin_date, checkout_date, num_adults, num_children)
+        values ($1, $2, $3, $4, $5, $6)
+        returning *",
+        guest.id,
+        room_type.id,
+        body.checkin_date,
+        body.checkout_date,
+        num_adults,
+        num_children
+    )
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|e| {
+        let is_duplicate = e
+            .as_database_error()
+            .and_then(|e| e.code())
+            .is_some_and(|code| code == "23505");
+
+        if is_duplicate {
+            let error_response = serde_json::json!({
+                "status": "fail",
+                "message": format!(
+                    "You are already on the waitlist for a {} from {} to {}",
+                    room_type.name, body.checkin_date, body.checkout_date
+                ),
+            });
+            return (StatusCode::CONFLICT, Json(error_response));
+        }
+
+        database_error(e)
+    })?;
+
+    tracing::info!(waitlist_entry_id = entry.id, "guest joined the waitlist");
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "data": serde_json::json!({
+            "waitlist_entry": entry
+        })
+    });
+
+    Ok((StatusCode::CREATED, Json(json_response)))
+}
+
+// Handler to get the waitlist entries of the guest, most recent first
+pub async fn my_waitlist_handler(
+    State(data): State<Arc<AppState>>,
+    Extension(guest): Extension<Guest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let entries = sqlx::query_as!(
+        WaitlistEntry,
+        "select * from waitlist_entry where guest_id = $1 order by id desc",
+        guest.id
+    )
+    .fetch_all(&data.db)
+    .await
+    .map_err(database_error)?;
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "results": entries.len(),
+        "waitlist": entries
+    });
+
+    Ok(Json(json_response))
+}
+
+// Handler for a guest to leave the waitlist, a room held for them goes to the next guest
+pub async fn leave_waitlist_handler(
+    State(data): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Extension(guest): Extension<Guest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let entry = sqlx::query_as!(
+        WaitlistEntry,
+        "update waitlist_entry set status = $1, updated_at = now()
+        where id = $2 and guest_id = $3 and status in ($4, $5)
+        returning *",
+        WAITLIST_STATUS_CANCELLED,
+        id,
+        guest.id,
+        WAITLIST_STATUS_WAITING,
+        WAITLIST_STATUS_OFFERED
+    )
+    .fetch_optional(&data.db)
+    .await
+    .map_err(database_error)?
+    .ok_or_else(|| {
+        let error_response = serde_json::json!({
+            "status": "fail",
+            "message": format!("Waitlist entry with ID: {} not found or no longer waiting", id)
+        });
+        (StatusCode::NOT_FOUND, Json(error_response))
+    })?;
+
+    tracing::info!(waitlist_entry_id = entry.id, "guest left the waitlist");
+
+    if entry.hold_expires_at.is_some() {
+        notify_waitlist(&data).await;
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// Handler to get the messages sent to the guest, most recent first
+pub async fn notification_list_handler(
+    State(data): State<Arc<AppState>>,
+    Extension(guest): Extension<Guest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let notifications = sqlx::query_as!(
+        Notification,
+        "select * from notification where guest_id = $1 order by id desc",
+        guest.id
+    )
+    .fetch_all(&data.db)
+    .await
+    .map_err(database_error)?;
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "results": notifications.len(),
+        "notifications": notifications
+    });
+
+    Ok(Json(json_response))
+}
+
+// Handler for staff to see who is waiting for rooms, in the order rooms are offered
+pub async fn waitlist_handler(
+    State(data): State<Arc<AppState>>,
+    opts: Option<Query<WaitlistOptions>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
+    let Query(opts) = opts.unwrap_or_default();
+
+    let statuses = match &opts.status {
+        Some(status) if WAITLIST_STATUSES.contains(&status.as_str()) => vec![status.clone()],
+        Some(_) => {
+            return Err(bad_request(format!(
+                "Invalid status, expected one of: {}",
+                WAITLIST_STATUSES.join(", ")
+            )))
+        }
+        None => vec![
+            WAITLIST_STATUS_WAITING.to_string(),
+            WAITLIST_STATUS_OFFERED.to_string(),
+        ],
+    };
+
+    let entries = sqlx::query_as!(
+        WaitlistEntry,
+        "select * from waitlist_entry
+        where status = any($1)
+            and ($2::int is null or room_type_id = $2)
+        order by created_at, id",
+        &statuses,
+        opts.room_type_id
+    )
+    .fetch_all(&data.db)
+    .await
+    .map_err(database_error)?;
+
+    let json_response = serde_json::json!({
+        "status": "success",
+        "results": entries.len(),
+        "waitlist": entries
+    });
+
+    Ok(Json(json_response))
+}
+
+// Offer the rooms just freed to the waitlist, the worker catches up on a failure
+pub(super) async fn notify_waitlist(data: &AppState) {
+    match offer_freed_rooms(&data.db, data.env.waitlist_hold_hours).await {
+        Ok(entries) => {
+            for entry in entries {
+                tracing::info!(waitlist_entry_id = entry.id, "room offered to the waitlist");
+            }
+        }
+        Err(e) => tracing::error!("Failed to offer rooms to the waitlist: {}", e),
+    }
+}
@@ -0,0 +1,238 @@
+use sqlx::PgConnection;
+
+use crate::models::{Booking, HousekeepingTask, Room, BOOKING_STATUS_CHECKED_IN};
+
+pub const ROOM_STATUSES: &[&str] = &[
+    "clean",
+    "dirty",
+    "inspected",
+    "out_of_order",
+    "out_of_service",
+];
+
+pub const ROOM_STATUS_CLEAN: &str = "clean";
+pub const ROOM_STATUS_DIRTY: &str = "dirty";
+pub const ROOM_STATUS_INSPECTED: &str = "inspected";
+
+// Statuses a guest can be checked into
+pub const READY_STATUSES: &[&str] = &[ROOM_STATUS_CLEAN, ROOM_STATUS_INSPECTED];
+
+pub const TASK_TYPES: &[&str] = &["clean", "inspect"];
+
+pub const TASK_TYPE_CLEAN: &str = "clean";
+pub const TASK_TYPE_INSPECT: &str = "inspect";
+
+// Why a room couldn't be given to a guest
+pub enum RoomError {
+    Rejected(String),
+    Database(sqlx::Error),
+}
+
+impl From<sqlx::Error> for RoomError {
+    fn from(e: sqlx::Error) -> Self {
+        RoomError::Database(e)
+    }
+}
+
+// Who changed the status of a room and why, kept in its history
+pub struct StatusChange<'a> {
+    pub booking_id: Option<i32>,
+    pub reason: Option<&'a str>,
+    pub changed_by: Option<i32>,
+}
+
+// A task to give to the housekeeping staff
+pub struct NewTask {
+    pub room_id: i32,
+    pub task_type: &'static str,
+    pub assigned_to: Option<i32>,
+    pub booking_id: Option<i32>,
+    pub notes: Option<String>,
+    pub created_by: Option<i32>,
+}
+
+// Move the room to a new housekeeping status, recording the change in its history
+pub async fn set_room_status(
+    conn: &mut PgConnection,
+    room_id: i32,
+    to_status: &str,
+    change: StatusChange<'_>,
+) -> Result<Room, sqlx::Error> {
+    let from_status = sqlx::query_scalar!(
+        "select housekeeping_status from room where id = $1 for update",
+        room_id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let room = sqlx::query_as!(
+        Room,
+        "update room set housekeeping_status = $1, status_updated_at = now(), updated_at = now()
+        where id = $2
+        returning *",
+        to_status,
+        room_id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    if from_status != to_status {
+        sqlx::query!(
+            "insert into room_status_change
+                (room_id, from_status, to_status, booking_id, reason, changed_by)
+            values ($1, $2, $3, $4, $5, $6)",
+            room_id,
+            from_status,
+            to_status,
+            change.booking_id,
+            change.reason,
+            change.changed_by
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    Ok(room)
+}
+
+pub async fn create_task(
+    conn: &mut PgConnection,
+    task: NewTask,
+) -> Result<HousekeepingTask, sqlx::Error> {
+    sqlx::query_as!(
+        HousekeepingTask,
+        "insert into housekeeping_task
+            (room_id, task_type, assigned_to, booking_id, notes, created_by)
+        values ($1, $2, $3, $4, $5, $6)
+        returning *",
+        task.room_id,
+        task.task_type,
+        task.assigned_to,
+        task.booking_id,
+        task.notes,
+        task.created_by
+    )
+    .fetch_one(conn)
+    .await
+}
+
+// Close the open tasks of the room once the work is done, whoever they were assigned to
+pub async fn complete_tasks(
+    conn: &mut PgConnection,
+    room_id: i32,
+    task_type: &str,
+    completed_by: i32,
+) -> Result<Vec<HousekeepingTask>, sqlx::Error> {
+    sqlx::query_as!(
+        HousekeepingTask,
+        "update housekeeping_task set
+        status = 'done',
+        completed_by = $1,
+        completed_at = now(),
+        updated_at = now()
+        where room_id = $2 and task_type = $3 and status = 'open'
+        returning *",
+        completed_by,
+        room_id,
+        task_type
+    )
+    .fetch_all(conn)
+    .await
+}
+
+// The room of a departing guest is dirty and needs cleaning
+pub async fn release_room_after_check_out(
+    conn: &mut PgConnection,
+    booking: &Booking,
+) -> Result<Option<HousekeepingTask>, sqlx::Error> {
+    let Some(room_id) = booking.room_id else {
+        return Ok(None);
+    };
+
+    let task = release_room(conn, room_id, booking.id, "Guest checked out").await?;
+
+    Ok(Some(task))
+}
+
+// Leave the room the guest of the booking stayed in dirty, with a task to clean it
+pub async fn release_room(
+    conn: &mut PgConnection,
+    room_id: i32,
+    booking_id: i32,
+    reason: &str,
+) -> Result<HousekeepingTask, sqlx::Error> {
+    set_room_status(
+        &mut *conn,
+        room_id,
+        ROOM_STATUS_DIRTY,
+        StatusChange {
+            booking_id: Some(booking_id),
+            reason: Some(reason),
+            changed_by: None,
+        },
+    )
+    .await?;
+
+    create_task(
+        conn,
+        NewTask {
+            room_id,
+            task_type: TASK_TYPE_CLEAN,
+            assigned_to: None,
+            booking_id: Some(booking_id),
+            notes: None,
+            created_by: None,
+        },
+    )
+    .await
+}
+
+// Check the room can be given to the guest of the booking, the room row stays locked
+pub async fn ensure_room_ready(
+    conn: &mut PgConnection,
+    room_id: i32,
+    booking: &Booking,
+) -> Result<Room, RoomError> {
+    let room = sqlx::query_as!(Room, "select * from room where id = $1 for update", room_id)
+        .fetch_optional(&mut *conn)
+        .await?
+        .filter(|room| room.active)
+        .ok_or_else(|| RoomError::Rejected(format!("Room with ID: {} not found", room_id)))?;
+
+    if booking
+        .room_type_id
+        .is_some_and(|room_type_id| room_type_id != room.room_type_id)
+    {
+        return Err(RoomError::Rejected(format!(
+            "Room {} isn't of the room type booked",
+            room.room_number
+        )));
+    }
+
+    if !READY_STATUSES.contains(&room.housekeeping_status.as_str()) {
+        return Err(RoomError::Rejected(format!(
+            "Room {} is {} and can't be given to a guest",
+            room.room_number, room.housekeeping_status
+        )));
+    }
+
+    let occupied: bool = sqlx::query_scalar!(
+        "select exists(
+            select 1 from booking where room_id = $1 and status = $2 and id <> $3
+        ) as \"exists!\"",
+        room.id,
+        BOOKING_STATUS_CHECKED_IN,
+        booking.id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    if occupied {
+        return Err(RoomError::Rejected(format!(
+            "Room {} is occupied",
+            room.room_number
+        )));
+    }
+
+    Ok(room)
+}
@@ -0,0 +1,418 @@
+use std::collections::BTreeMap;
+
+use bigdecimal::{BigDecimal, Zero};
+use sqlx::{PgConnection, Pool, Postgres};
+
+use crate::{
+    folio::load_folio,
+    models::{Invoice, InvoiceLine, Property, DEFAULT_PROPERTY_ID},
+    pdf::PdfDocument,
+    response::{InvoiceDocument, TaxBreakdown},
+};
+
+// Issue a new invoice from the current folio of the booking, in the caller's transaction
+//
+// The invoice number is taken from a counter row that is locked until the
+// transaction commits, so numbers are sequential and a failed invoice gives
+// its number back instead of leaving a gap. The folio is read once the
+// counter is locked, so concurrent invoices never copy a stale folio.
+pub async fn issue_invoice(
+    conn: &mut PgConnection,
+    booking_id: i32,
+) -> Result<Invoice, sqlx::Error> {
+    let number = sqlx::query_scalar!(
+        "update invoice_number_sequence set last_number = last_number + 1 where id = 1 returning last_number"
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let folio = load_folio(&mut *conn, booking_id).await?;
+
+    let guest = sqlx::query!(
+        "select g.id, g.first_name, g.last_name, g.email_address, g.phone_number
+        from booking b join guest g on g.id = b.guest_id
+        where b.id = $1",
+        booking_id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let invoice = sqlx::query_as!(
+        Invoice,
+        "insert into invoice
+            (
+                invoice_number,
+                booking_id,
+                guest_id,
+                property_id,
+                bill_to_name,
+                bill_to_email,
+                bill_to_phone,
+                net_total,
+                tax_total,
+                total,
+                payments_total,
+                balance,
+                currency
+            )
+        values ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+        returning *",
+        format!("INV-{:06}", number),
+        booking_id,
+        guest.id,
+        DEFAULT_PROPERTY_ID,
+        format!("{} {}", guest.first_name, guest.last_name),
+        guest.email_address,
+        guest.phone_number,
+        folio.net_total,
+        folio.tax_total,
+        folio.charges_total,
+        folio.payments_total,
+        folio.balance,
+        folio.currency
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    // Copy the lines so later folio changes don't alter an issued invoice
+    let lines: Vec<_> = folio
+        .lines
+        .iter()
+        .filter(|line| line.voided_at.is_none())
+        .collect();
+    for line in &lines {
+        let net_amount = line.net_amount().with_scale(2);
+        let total = (&net_amount + &line.tax_amount).with_scale(2);
+
+        // Tax lines are printed with the amount they were computed on, charges
+        // with a tax entered by hand are taxed on their own amount
+        let taxable_amount = if line.is_tax() {
+            lines
+                .iter()
+                .find(|parent| Some(parent.id) == line.parent_line_id)
+                .map(|parent| parent.net_amount().with_scale(2))
+        } else if !line.tax_amount.is_zero() {
+            Some(net_amount.clone())
+        } else {
+            None
+        };
+
+        sqlx::query!(
+            "insert into invoice_line
+                (
+                    invoice_id,
+                    line_type,
+                    description,
+                    quantity,
+                    unit_price,
+                    net_amount,
+                    tax_amount,
+                    total,
+                    taxable_amount
+                )
+            values ($1, $2, $3, $4, $5, $6, $7, $8, $9)",
+            invoice.id,
+            line.line_type,
+            line.description,
+            line.quantity,
+            line.unit_price,
+            net_amount,
+            line.tax_amount,
+            total,
+            taxable_amount
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    tracing::info!(
+        booking_id,
+        invoice_number = invoice.invoice_number,
+        "invoice issued"
+    );
+
+    Ok(invoice)
+}
+
+// Latest invoice of the booking, if any was issued
+pub async fn latest_invoice(
+    db: &Pool<Postgres>,
+    booking_id: i32,
+) -> Result<Option<Invoice>, sqlx::Error> {
+    sqlx::query_as!(
+        Invoice,
+        "select * from invoice where booking_id = $1 order by id desc limit 1",
+        booking_id
+    )
+    .fetch_optional(db)
+    .await
+}
+
+// Load the lines and hotel details needed to render the invoice
+pub async fn load_invoice_document(
+    db: &Pool<Postgres>,
+    invoice: Invoice,
+) -> Result<InvoiceDocument, sqlx::Error> {
+    let lines = sqlx::query_as!(
+        InvoiceLine,
+        "select * from invoice_line where invoice_id = $1 order by id",
+        invoice.id
+    )
+    .fetch_all(db)
+    .await?;
+
+    let property = sqlx::query_as!(
+        Property,
+        "select * from property where id = $1",
+        invoice.property_id
+    )
+    .fetch_one(db)
+    .await?;
+
+    // Sum the taxable and tax amounts per tax, taxes entered by hand on a
+    // charge are grouped under the category of the charge
+    let mut categories: BTreeMap<String, (BigDecimal, BigDecimal)> = BTreeMap::new();
+    for line in &lines {
+        let Some(taxable_amount) = &line.taxable_amount else {
+            continue;
+        };
+
+        let (category, tax) = if line.line_type == "tax" {
+            (line.description.to_owned(), &line.net_amount)
+        } else {
+            (format!("Tax on {}", line.line_type), &line.tax_amount)
+        };
+
+        let (net_amount, tax_amount) = categories
+            .entry(category)
+            .or_insert_with(|| (BigDecimal::zero(), BigDecimal::zero()));
+        *net_amount += taxable_amount;
+        *tax_amount += tax;
+    }
+
+    let tax_breakdown = categories
+        .into_iter()
+        .map(|(category, (net_amount, tax_amount))| TaxBreakdown {
+            category,
+            net_amount: net_amount.with_scale(2),
+            tax_amount: tax_amount.with_scale(2),
+        })
+        .collect();
+
+    Ok(InvoiceDocument {
+        invoice,
+        lines,
+        property,
+        tax_breakdown,
+    })
+}
+
+// Render the invoice as a standalone html page
+pub fn render_html(document: &InvoiceDocument) -> String {
+    let invoice = &document.invoice;
+    let property = &document.property;
+
+    let lines: String = document
+        .lines
+        .iter()
+        .map(|line| {
+            format!(
+                "<tr><td>{}</td><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td>\
+                 <td class=\"num\">{}</td><td class=\"num\">{}</td></tr>",
+                escape_html(&line.description),
+                escape_html(&line.line_type),
+                line.quantity,
+                amount(&line.unit_price),
+                amount(&line.tax_amount),
+                amount(&line.total)
+            )
+        })
+        .collect();
+
+    let tax_breakdown: String = document
+        .tax_breakdown
+        .iter()
+        .map(|tax| {
+            format!(
+                "<tr><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td></tr>",
+                escape_html(&tax.category),
+                amount(&tax.net_amount),
+                amount(&tax.tax_amount)
+            )
+        })
+        .collect();
+
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Invoice {number}</title>
+<style>
+body {{ font-family: sans-serif; margin: 40px; }}
+table {{ border-collapse: collapse; width: 100%; margin-bottom: 24px; }}
+th, td {{ border-bottom: 1px solid #ddd; padding: 6px; text-align: left; }}
+.num {{ text-align: right; }}
+</style>
+</head>
+<body>
+<h1>{property_name}</h1>
+<p>{address}<br>{email} &middot; {phone}{tax_id}</p>
+<h2>Invoice {number}</h2>
+<p>Issued: {issued_at}<br>Booking: #{booking_id}<br>Currency: {currency}</p>
+<h3>Bill to</h3>
+<p>{bill_to_name}<br>{bill_to_email}<br>{bill_to_phone}</p>
+<table>
+<tr><th>Description</th><th>Type</th><th class="num">Qty</th><th class="num">Unit price</th><th class="num">Tax</th><th class="num">Total</th></tr>
+{lines}
+</table>
+<h3>Tax breakdown</h3>
+<table>
+<tr><th>Tax</th><th class="num">Taxable</th><th class="num">Amount</th></tr>
+{tax_breakdown}
+</table>
+<table>
+<tr><td>Net total</td><td class="num">{net_total}</td></tr>
+<tr><td>Tax total</td><td class="num">{tax_total}</td></tr>
+<tr><th>Total</th><th class="num">{total}</th></tr>
+<tr><td>Paid</td><td class="num">{payments_total}</td></tr>
+<tr><th>Balance due</th><th class="num">{balance}</th></tr>
+</table>
+</body>
+</html>
+"#,
+        number = escape_html(&invoice.invoice_number),
+        property_name = escape_html(&property.name),
+        address = escape_html(&property.address),
+        email = escape_html(&property.email_address),
+        phone = escape_html(&property.phone_number),
+        tax_id = property
+            .tax_id
+            .as_deref()
+            .map(|tax_id| format!("<br>Tax ID: {}", escape_html(tax_id)))
+            .unwrap_or_default(),
+        issued_at = invoice.issued_at.format("%Y-%m-%d"),
+        booking_id = invoice.booking_id,
+        currency = escape_html(&invoice.currency),
+        bill_to_name = escape_html(&invoice.bill_to_name),
+        bill_to_email = escape_html(&invoice.bill_to_email),
+        bill_to_phone = escape_html(&invoice.bill_to_phone),
+        lines = lines,
+        tax_breakdown = tax_breakdown,
+        net_total = amount(&invoice.net_total),
+        tax_total = amount(&invoice.tax_total),
+        total = amount(&invoice.total),
+        payments_total = amount(&invoice.payments_total),
+        balance = amount(&invoice.balance),
+    )
+}
+
+// Render the invoice as a pdf file
+pub fn render_pdf(document: &InvoiceDocument) -> Vec<u8> {
+    let invoice = &document.invoice;
+    let property = &document.property;
+
+    let mut pdf = PdfDocument::new();
+
+    pdf.text(&property.name, 16.0, true);
+    pdf.text(&property.address, 10.0, false);
+    pdf.text(
+        &format!("{}  {}", property.email_address, property.phone_number),
+        10.0,
+        false,
+    );
+    if let Some(tax_id) = &property.tax_id {
+        pdf.text(&format!("Tax ID: {}", tax_id), 10.0, false);
+    }
+
+    pdf.gap(16.0);
+    pdf.text(&format!("Invoice {}", invoice.invoice_number), 14.0, true);
+    pdf.text(
+        &format!("Issued: {}", invoice.issued_at.format("%Y-%m-%d")),
+        10.0,
+        false,
+    );
+    pdf.text(&format!("Booking: #{}", invoice.booking_id), 10.0, false);
+    pdf.text(&format!("Currency: {}", invoice.currency), 10.0, false);
+
+    pdf.gap(12.0);
+    pdf.text("Bill to", 11.0, true);
+    pdf.text(&invoice.bill_to_name, 10.0, false);
+    pdf.text(&invoice.bill_to_email, 10.0, false);
+    pdf.text(&invoice.bill_to_phone, 10.0, false);
+
+    pdf.gap(12.0);
+    pdf.text(
+        &format!(
+            "{:<32} {:>4} {:>11} {:>10} {:>11}",
+            "Description", "Qty", "Unit price", "Tax", "Total"
+        ),
+        9.0,
+        true,
+    );
+    for line in &document.lines {
+        pdf.text(
+            &format!(
+                "{:<32} {:>4} {:>11} {:>10} {:>11}",
+                truncate(&line.description, 32),
+                line.quantity,
+                amount(&line.unit_price),
+                amount(&line.tax_amount),
+                amount(&line.total)
+            ),
+            9.0,
+            false,
+        );
+    }
+
+    pdf.gap(12.0);
+    pdf.text("Tax breakdown", 11.0, true);
+    pdf.text(
+        &format!("{:<32} {:>13} {:>13}", "Tax", "Taxable", "Amount"),
+        9.0,
+        true,
+    );
+    for tax in &document.tax_breakdown {
+        pdf.text(
+            &format!(
+                "{:<32} {:>13} {:>13}",
+                truncate(&tax.category, 32),
+                amount(&tax.net_amount),
+                amount(&tax.tax_amount)
+            ),
+            9.0,
+            false,
+        );
+    }
+
+    pdf.gap(12.0);
+    for (label, value, bold) in [
+        ("Net total", &invoice.net_total, false),
+        ("Tax total", &invoice.tax_total, false),
+        ("Total", &invoice.total, true),
+        ("Paid", &invoice.payments_total, false),
+        ("Balance due", &invoice.balance, true),
+    ] {
+        pdf.text(&format!("{:<32} {:>27}", label, amount(value)), 10.0, bold);
+    }
+
+    pdf.finish()
+}
+
+// Amounts are always printed with two decimals
+fn amount(value: &BigDecimal) -> String {
+    value.with_scale(2).to_string()
+}
+
+// Keep table cells from overflowing into the next column
+fn truncate(text: &str, max_chars: usize) -> String {
+    text.chars().take(max_chars).collect()
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
@@ -6,7 +6,7 @@ use axum::{
     http::{header, Request, StatusCode},
     middleware::Next,
     response::IntoResponse,
-    Json,
+    Extension, Json,
 };
 
 use axum_extra::extract::cookie::CookieJar;
@@ -65,6 +65,15 @@ pub async fn auth(
     })?
     .claims;
 
+    // A challenge token only proves the password, not the second factor
+    if claims.mfa_pending {
+        let json_error = ErrorResponse {
+            status: "fail",
+            message: "Multi-factor authentication is not complete".to_string(),
+        };
+        return Err((StatusCode::UNAUTHORIZED, Json(json_error)));
+    }
+
     let guest_id: i32 = claims.sub.parse().map_err(|_| {
         let json_error = ErrorResponse {
             status: "fail",
@@ -95,6 +104,95 @@ pub async fn auth(
         (StatusCode::UNAUTHORIZED, Json(json_error))
     })?;
 
+    tracing::Span::current().record("guest_id", guest.id);
+
     req.extensions_mut().insert(guest);
     Ok(next.run(req).await)
 }
+
+// Middleware to restrict a route to staff accounts, must run after `auth`
+pub async fn require_staff(
+    Extension(guest): Extension<Guest>,
+    req: Request<Body>,
+    next: Next,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    if !guest.is_staff() {
+        let json_error = ErrorResponse {
+            status: "fail",
+            message: "You are not allowed to access this resource".to_string(),
+        };
+        return Err((StatusCode::FORBIDDEN, Json(json_error)));
+    }
+
+    // Staff accounts need a second factor before they can use staff endpoints
+    if !guest.mfa_enabled {
+        let json_error = ErrorResponse {
+            status: "fail",
+            message: "Enable multi-factor authentication to access this resource".to_string(),
+        };
+        return Err((StatusCode::FORBIDDEN, Json(json_error)));
+    }
+
+    Ok(next.run(req).await)
+}
+
+// Middleware to restrict a route to housekeeping and staff accounts, must run after `auth`
+pub async fn require_housekeeping(
+    Extension(guest): Extension<Guest>,
+    req: Request<Body>,
+    next: Next,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    if !guest.is_housekeeping() {
+        let json_error = ErrorResponse {
+            status: "fail",
+            message: "You are not allowed to access this resource".to_string(),
+        };
+        return Err((StatusCode::FORBIDDEN, Json(json_error)));
+    }
+
+    // Housekeeping accounts need a second factor like every other staff account
+    if !guest.mfa_enabled {
+        let json_error = ErrorResponse {
+            status: "fail",
+            message: "Enable multi-factor authentication to access this resource".to_string(),
+        };
+        return Err((StatusCode::FORBIDDEN, Json(json_error)));
+    }
+
+    Ok(next.run(req).await)
+}
+
+// Middleware to restrict a route to accounts with a second factor, must run after `auth`
+// Guests keep payment methods on file only once their second factor is enabled
+pub async fn require_mfa(
+    Extension(guest): Extension<Guest>,
+    req: Request<Body>,
+    next: Next,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    if !guest.mfa_enabled {
+        let json_error = ErrorResponse {
+            status: "fail",
+            message: "Enable multi-factor authentication to access this resource".to_string(),
+        };
+        return Err((StatusCode::FORBIDDEN, Json(json_error)));
+    }
+
+    Ok(next.run(req).await)
+}
+
+// Only admins can change the configuration of the property, e.g. its tax rules
+pub async fn require_admin(
+    Extension(guest): Extension<Guest>,
+    req: Request<Body>,
+    next: Next,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    if !guest.is_admin() {
+        let json_error = ErrorResponse {
+            status: "fail",
+            message: "You are not allowed to access this resource".to_string(),
+        };
+        return Err((StatusCode::FORBIDDEN, Json(json_error)));
+    }
+
+    Ok(next.run(req).await)
+}
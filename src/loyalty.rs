@@ -0,0 +1,162 @@
+use bigdecimal::{BigDecimal, ToPrimitive};
+use serde::Serialize;
+use sqlx::PgConnection;
+
+use crate::models::{Booking, LoyaltyEntry, BOOKING_STATUS_CHECKED_OUT};
+
+// Tiers are reached with completed stays over the last twelve months, and earn extra points
+#[derive(Debug, Serialize)]
+pub struct Tier {
+    pub name: &'static str,
+    pub min_stays: i64,
+    pub earn_multiplier_percent: i64,
+}
+
+pub const TIERS: &[Tier] = &[
+    Tier {
+        name: "silver",
+        min_stays: 0,
+        earn_multiplier_percent: 100,
+    },
+    Tier {
+        name: "gold",
+        min_stays: 5,
+        earn_multiplier_percent: 125,
+    },
+    Tier {
+        name: "platinum",
+        min_stays: 10,
+        earn_multiplier_percent: 150,
+    },
+];
+
+// Highest tier the number of stays reaches
+pub fn tier_for(stays: i64) -> &'static Tier {
+    TIERS
+        .iter()
+        .rev()
+        .find(|tier| stays >= tier.min_stays)
+        .unwrap_or(&TIERS[0])
+}
+
+// Tier after the current one, if any
+pub fn next_tier(tier: &Tier) -> Option<&'static Tier> {
+    TIERS.iter().find(|next| next.min_stays > tier.min_stays)
+}
+
+// Completed stays of the guest over the last twelve months
+pub async fn stays_last_year(conn: &mut PgConnection, guest_id: i32) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar!(
+        "select count(*) as \"count!\" from booking
+        where guest_id = $1 and status = $2 and checked_out_at >= now() - interval '1 year'",
+        guest_id,
+        BOOKING_STATUS_CHECKED_OUT
+    )
+    .fetch_one(conn)
+    .await
+}
+
+pub async fn points_balance(conn: &mut PgConnection, guest_id: i32) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar!(
+        "select coalesce(sum(points), 0) as \"balance!\" from loyalty_ledger where guest_id = $1",
+        guest_id
+    )
+    .fetch_one(conn)
+    .await
+}
+
+// Balance of the guest, locked until the transaction ends so points can't be spent twice
+pub async fn lock_points_balance(
+    conn: &mut PgConnection,
+    guest_id: i32,
+) -> Result<i64, sqlx::Error> {
+    sqlx::query!("select id from guest where id = $1 for update", guest_id)
+        .fetch_one(&mut *conn)
+        .await?;
+
+    points_balance(conn, guest_id).await
+}
+
+// Amount the points take off a booking
+pub fn points_value(points: i32, redeem_points_per_unit: u32) -> BigDecimal {
+    (BigDecimal::from(points) / BigDecimal::from(redeem_points_per_unit))
+        .round(2)
+        .with_scale(2)
+}
+
+// Credit the points of a completed stay, only once per booking
+pub async fn earn_points(
+    conn: &mut PgConnection,
+    booking: &Booking,
+    earn_points_per_unit: u32,
+) -> Result<Option<LoyaltyEntry>, sqlx::Error> {
+    let stays = stays_last_year(&mut *conn, booking.guest_id).await?;
+    let tier = tier_for(stays);
+
+    let points = (&booking.booking_amount
+        * BigDecimal::from(earn_points_per_unit)
+        * BigDecimal::from(tier.earn_multiplier_percent)
+        / BigDecimal::from(100))
+    .with_scale(0)
+    .to_i32()
+    .unwrap_or(0);
+
+    if points <= 0 {
+        return Ok(None);
+    }
+
+    sqlx::query_as!(
+        LoyaltyEntry,
+        "insert into loyalty_ledger (guest_id, booking_id, entry_type, points, description)
+        values ($1, $2, 'earn', $3, $4)
+        on conflict (booking_id) where entry_type = 'earn' do nothing
+        returning *",
+        booking.guest_id,
+        booking.id,
+        points,
+        format!("Stay #{} ({} tier)", booking.id, tier.name)
+    )
+    .fetch_optional(conn)
+    .await
+}
+
+// Spend points on a booking, the balance must have been checked with `lock_points_balance`
+pub async fn redeem_points(
+    conn: &mut PgConnection,
+    guest_id: i32,
+    booking_id: i32,
+    points: i32,
+) -> Result<LoyaltyEntry, sqlx::Error> {
+    sqlx::query_as!(
+        LoyaltyEntry,
+        "insert into loyalty_ledger (guest_id, booking_id, entry_type, points, description)
+        values ($1, $2, 'redeem', $3, $4)
+        returning *",
+        guest_id,
+        booking_id,
+        -points,
+        format!("Redeemed on booking #{}", booking_id)
+    )
+    .fetch_one(conn)
+    .await
+}
+
+// Give back the points spent on a booking that won't take place
+pub async fn reverse_redemptions(
+    conn: &mut PgConnection,
+    booking_id: i32,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        "insert into loyalty_ledger (guest_id, booking_id, entry_type, points, description)
+        select guest_id, booking_id, 'reversal', -sum(points), 'Points returned, booking #' || booking_id || ' cancelled'
+        from loyalty_ledger
+        where booking_id = $1 and entry_type in ('redeem', 'reversal')
+        group by guest_id, booking_id
+        having sum(points) < 0",
+        booking_id
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
@@ -1,30 +1,67 @@
 // Import modules
+mod allotment;
+mod availability;
+mod business_date;
+mod code;
 mod config;
+mod csv;
+mod currency;
+mod deposit;
+mod folio;
+mod front_desk;
+mod gift_voucher;
+mod group;
 mod handlers;
+mod housekeeping;
+mod invoice;
 mod jwt_auth;
+mod loyalty;
+mod maintenance;
 mod models;
+mod night_audit;
+mod pace;
+mod pdf;
+mod pricing;
+mod promo;
+mod rate_limit;
+mod report;
 mod response;
+mod restriction;
+mod room_assignment;
 mod route;
 mod schema;
+mod tax;
+mod telemetry;
+mod waitlist;
+mod worker;
 
-use std::{sync::Arc, time::Duration};
+use std::{net::SocketAddr, sync::Arc, time::Duration};
 
 use crate::route::create_router;
 
 use axum::http::{
     header::{ACCEPT, AUTHORIZATION, CONTENT_TYPE},
-    HeaderValue, Method,
+    HeaderName, HeaderValue, Method,
 };
 use config::Config;
 use dotenv::dotenv;
-use sqlx::{postgres::PgPoolOptions, Pool, Postgres};
+use metrics_exporter_prometheus::PrometheusHandle;
+use rate_limit::RateLimiter;
+use sqlx::{migrate::Migrator, postgres::PgPoolOptions, Pool, Postgres};
 use tower_http::cors::CorsLayer;
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, Layer};
+use worker::Heartbeats;
+
+// Embed the migrations so they can be run at startup and checked for readiness
+pub static MIGRATOR: Migrator = sqlx::migrate!();
 
 // Keep track of shared elements in the AppState structure
 pub struct AppState {
     db: Pool<Postgres>,
     env: Config,
+    heartbeats: Heartbeats,
+    metrics: PrometheusHandle,
+    rate_limiter: RateLimiter,
 }
 
 // Use tokio runtime to make the main function async
@@ -33,18 +70,28 @@ async fn main() {
     // Check if the `.env` is available form the root directory
     dotenv().ok();
 
+    // Init config from config.rs with the environment variables
+    let config = Config::init();
+
+    // Log either human readable lines or one json object per line
+    let fmt_layer = if config.log_json {
+        tracing_subscriber::fmt::layer()
+            .json()
+            .with_current_span(true)
+            .boxed()
+    } else {
+        tracing_subscriber::fmt::layer().boxed()
+    };
+
     tracing_subscriber::registry()
         .with(
             tracing_subscriber::EnvFilter::try_from_default_env().unwrap_or_else(|_| {
                 "local_hotel=debug,tower_http=debug,axum::rejection=trace".into()
             }),
         )
-        .with(tracing_subscriber::fmt::layer())
+        .with(fmt_layer)
         .init();
 
-    // Init config from config.rs with the environment variables
-    let config = Config::init();
-
     // Set up the database connection pool,
     // with 10 max connections
     // and connection timeout of 3 seconds
@@ -65,21 +112,31 @@ async fn main() {
     };
 
     // Run db migrations
-    sqlx::migrate!().run(&db_pool).await.unwrap();
+    MIGRATOR.run(&db_pool).await.unwrap();
 
     // Init cors with different configurations
     let cors = CorsLayer::new()
         .allow_origin("http://localhost:3000".parse::<HeaderValue>().unwrap())
         .allow_methods([Method::GET, Method::POST, Method::PATCH, Method::DELETE])
         .allow_credentials(true)
-        .allow_headers([AUTHORIZATION, ACCEPT, CONTENT_TYPE]);
+        .allow_headers([AUTHORIZATION, ACCEPT, CONTENT_TYPE])
+        .expose_headers([HeaderName::from_static(telemetry::REQUEST_ID_HEADER)]);
 
     // Init app state
     let app_state = Arc::new(AppState {
         db: db_pool.clone(),
         env: config.clone(),
+        heartbeats: Heartbeats::default(),
+        metrics: telemetry::setup_metrics_recorder(),
+        rate_limiter: RateLimiter::default(),
     });
 
+    // Start the background workers
+    worker::spawn_deposit_expiry(app_state.clone());
+    worker::spawn_allotment_release(app_state.clone());
+    worker::spawn_night_audit(app_state.clone());
+    worker::spawn_waitlist_offers(app_state.clone());
+
     // Configure routing with application
     // Add database to the app
     let app = create_router(app_state).layer(cors);
@@ -87,5 +144,11 @@ async fn main() {
     // Run app with tokio rt
     let listener = tokio::net::TcpListener::bind("0.0.0.0:3000").await.unwrap();
     tracing::debug!("🚀 Server started successfully, on port 3000");
-    axum::serve(listener, app).await.unwrap();
+    // Keep the client address around for the rate limiter
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await
+    .unwrap();
 }
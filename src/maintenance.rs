@@ -0,0 +1,188 @@
+use sqlx::PgConnection;
+
+use crate::{
+    business_date::current_business_date,
+    housekeeping::{set_room_status, StatusChange, ROOM_STATUS_DIRTY},
+    models::{
+        Booking, MaintenanceTicket, BOOKING_STATUS_CHECKED_IN, BOOKING_STATUS_CONFIRMED,
+        BOOKING_STATUS_PENDING,
+    },
+};
+
+pub const PRIORITIES: &[&str] = &["low", "normal", "high", "urgent"];
+
+pub const TICKET_STATUSES: &[&str] = &["open", "in_progress", "resolved", "cancelled"];
+
+// Tickets still being worked on, only they keep a room out of order
+pub const OPEN_TICKET_STATUSES: &[&str] = &["open", "in_progress"];
+
+pub const ROOM_STATUS_OUT_OF_ORDER: &str = "out_of_order";
+
+// Photos and documents staff attach to a ticket, anything else is downloaded as plain bytes
+pub const ATTACHMENT_CONTENT_TYPES: &[&str] = &[
+    "image/jpeg",
+    "image/png",
+    "image/gif",
+    "image/webp",
+    "application/pdf",
+    "text/plain",
+];
+
+pub const MAX_FILE_NAME_LEN: usize = 255;
+
+// Flag the bookings given the room while it is out of order, they must be moved to another room
+pub async fn flag_conflicting_bookings(
+    conn: &mut PgConnection,
+    ticket: &MaintenanceTicket,
+) -> Result<Vec<Booking>, sqlx::Error> {
+    let (Some(from), Some(until)) = (ticket.out_of_order_from, ticket.out_of_order_until) else {
+        return Ok(Vec::new());
+    };
+
+    sqlx::query_as!(
+        Booking,
+        "update booking set maintenance_ticket_id = $1, updated_at = now()
+        where room_id = $2
+            and status in ($3, $4, $5)
+            and checkin_date < $6
+            and checkout_date > $7
+        returning *",
+        ticket.id,
+        ticket.room_id,
+        BOOKING_STATUS_PENDING,
+        BOOKING_STATUS_CONFIRMED,
+        BOOKING_STATUS_CHECKED_IN,
+        until,
+        from
+    )
+    .fetch_all(conn)
+    .await
+}
+
+// Bookings flagged by the ticket don't need to move anymore
+pub async fn clear_booking_flags(
+    conn: &mut PgConnection,
+    ticket_id: i32,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        "update booking set maintenance_ticket_id = null, updated_at = now()
+        where maintenance_ticket_id = $1",
+        ticket_id
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+// Show the room out of order on the status board while the ticket covers today
+pub async fn take_room_out_of_order(
+    conn: &mut PgConnection,
+    ticket: &MaintenanceTicket,
+    changed_by: i32,
+) -> Result<(), sqlx::Error> {
+    let today = current_business_date(&mut *conn).await?;
+    let covers_today = ticket.out_of_order
+        && ticket.out_of_order_from.is_some_and(|from| from <= today)
+        && ticket.out_of_order_until.is_some_and(|until| until > today);
+
+    if covers_today {
+        set_room_status(
+            conn,
+            ticket.room_id,
+            ROOM_STATUS_OUT_OF_ORDER,
+            StatusChange {
+                booking_id: None,
+                reason: Some(&ticket.title),
+                changed_by: Some(changed_by),
+            },
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+// A room back from maintenance needs cleaning before it is sold
+pub async fn return_room_to_service(
+    conn: &mut PgConnection,
+    ticket: &MaintenanceTicket,
+    changed_by: i32,
+) -> Result<(), sqlx::Error> {
+    let status = sqlx::query_scalar!(
+        "select housekeeping_status from room where id = $1",
+        ticket.room_id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    if status == ROOM_STATUS_OUT_OF_ORDER {
+        set_room_status(
+            conn,
+            ticket.room_id,
+            ROOM_STATUS_DIRTY,
+            StatusChange {
+                booking_id: None,
+                reason: Some("Maintenance finished"),
+                changed_by: Some(changed_by),
+            },
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+// Content type an attachment is served with, types off the list are sent as plain bytes
+pub fn attachment_content_type(content_type: &str) -> &'static str {
+    let essence = content_type.split(';').next().unwrap_or_default().trim();
+
+    ATTACHMENT_CONTENT_TYPES
+        .iter()
+        .find(|allowed| allowed.eq_ignore_ascii_case(essence))
+        .copied()
+        .unwrap_or("application/octet-stream")
+}
+
+// Keep the last part of an uploaded file name, without control characters or quotes
+pub fn sanitize_file_name(file_name: &str) -> String {
+    file_name
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .map(|c| if c.is_control() || c == '"' { '_' } else { c })
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+// `Content-Disposition` of a download, with an ASCII fallback for clients ignoring `filename*`
+pub fn attachment_disposition(file_name: &str) -> String {
+    let fallback: String = file_name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_graphic() && c != '"' && c != '\\' || c == ' ' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+
+    let encoded: String = file_name
+        .bytes()
+        .map(|byte| {
+            if byte.is_ascii_alphanumeric() || b"!#$&+-.^_`|~".contains(&byte) {
+                (byte as char).to_string()
+            } else {
+                format!("%{:02X}", byte)
+            }
+        })
+        .collect();
+
+    format!(
+        "attachment; filename=\"{}\"; filename*=UTF-8''{}",
+        fallback, encoded
+    )
+}
@@ -16,6 +16,32 @@ pub struct Guest {
     pub created_at: Option<DateTime<Utc>>,
     #[serde(rename = "updatedAt")]
     pub updated_at: Option<DateTime<Utc>>,
+    pub role: String,
+    pub mfa_enabled: bool,
+    pub mfa_secret: Option<String>,
+    pub preferred_currency: Option<String>,
+    pub mfa_last_time_step: Option<i64>,
+}
+
+impl Guest {
+    // Staff and admin accounts can use the staff endpoints
+    pub fn is_staff(&self) -> bool {
+        self.role == "staff" || self.role == "admin"
+    }
+
+    pub fn is_admin(&self) -> bool {
+        self.role == "admin"
+    }
+
+    // Housekeeping accounts only see the room status board and their tasks
+    pub fn is_housekeeping(&self) -> bool {
+        self.role == "housekeeping" || self.is_staff()
+    }
+
+    // Accounts that must enroll a second factor, every staff role and guests with payment methods on file
+    pub fn requires_mfa(&self, has_payment_methods: bool) -> bool {
+        self.is_housekeeping() || has_payment_methods
+    }
 }
 
 #[allow(non_snake_case)]
@@ -29,6 +55,108 @@ pub struct Booking {
     pub num_adults: i32,
     pub num_children: i32,
     pub booking_amount: BigDecimal,
+    pub currency: String,
+    pub rate_plan_id: i32,
+    pub status: String,
+    pub room_type_id: Option<i32>,
+    // Taken off the room amount by a promo code or loyalty points, `booking_amount` is what is left to pay
+    pub discount_amount: BigDecimal,
+    pub checked_in_at: Option<DateTime<Utc>>,
+    pub checked_out_at: Option<DateTime<Utc>>,
+    pub reservation_group_id: Option<i32>,
+    pub allotment_block_id: Option<i32>,
+    pub room_id: Option<i32>,
+    // Set when the room of the booking was taken out of order, it needs another room
+    pub maintenance_ticket_id: Option<i32>,
+    #[serde(rename = "createdAt")]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: Option<DateTime<Utc>>,
+    // Room preferences of the guest, used when a room is assigned
+    pub preferred_floor: Option<i32>,
+    pub accessible_room: bool,
+    pub near_elevator: bool,
+    pub special_requests: Option<String>,
+}
+
+#[allow(non_snake_case)]
+#[derive(Debug, Deserialize, sqlx::FromRow, Serialize, Clone)]
+pub struct RoomType {
+    pub id: i32,
+    pub code: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub base_rate: BigDecimal,
+    pub max_occupancy: i32,
+    pub active: bool,
+    #[serde(rename = "createdAt")]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[allow(non_snake_case)]
+#[derive(Debug, Deserialize, sqlx::FromRow, Serialize, Clone)]
+pub struct PromoCode {
+    pub id: i32,
+    pub code: String,
+    pub description: Option<String>,
+    pub discount_type: String,
+    pub discount_value: BigDecimal,
+    pub valid_from: Option<DateTime<Utc>>,
+    pub valid_until: Option<DateTime<Utc>>,
+    pub min_nights: i32,
+    pub max_uses: Option<i32>,
+    pub max_uses_per_guest: Option<i32>,
+    // Empty when the code applies to every room type
+    pub room_type_ids: Vec<i32>,
+    pub active: bool,
+    #[serde(rename = "createdAt")]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+// Bookings wait in `pending` until their deposit is paid
+pub const BOOKING_STATUS_PENDING: &str = "pending";
+pub const BOOKING_STATUS_CONFIRMED: &str = "confirmed";
+pub const BOOKING_STATUS_EXPIRED: &str = "expired";
+pub const BOOKING_STATUS_CHECKED_IN: &str = "checked_in";
+pub const BOOKING_STATUS_CHECKED_OUT: &str = "checked_out";
+// Set by the night audit on arrivals that never checked in
+pub const BOOKING_STATUS_NO_SHOW: &str = "no_show";
+
+#[allow(non_snake_case)]
+#[derive(Debug, Deserialize, sqlx::FromRow, Serialize, Clone)]
+pub struct RatePlan {
+    pub id: i32,
+    pub code: String,
+    pub name: String,
+    pub deposit_type: String,
+    pub deposit_value: BigDecimal,
+    pub deposit_due_hours: i32,
+    pub active: bool,
+    #[serde(rename = "createdAt")]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+// Seeded rate plan without deposit, used when the guest doesn't pick one
+pub const DEFAULT_RATE_PLAN_ID: i32 = 1;
+
+#[allow(non_snake_case)]
+#[derive(Debug, Deserialize, sqlx::FromRow, Serialize, Clone)]
+pub struct DepositRequest {
+    pub id: i32,
+    pub booking_id: i32,
+    pub amount: BigDecimal,
+    pub currency: String,
+    // Total of the stay when the booking was made, the rest is due at check-in
+    pub stay_total: BigDecimal,
+    pub due_at: DateTime<Utc>,
+    pub status: String,
+    pub paid_at: Option<DateTime<Utc>>,
     #[serde(rename = "createdAt")]
     pub created_at: Option<DateTime<Utc>>,
     #[serde(rename = "updatedAt")]
@@ -45,3 +173,446 @@ pub struct PaymentStatus {
     #[serde(rename = "updatedAt")]
     pub updated_at: Option<DateTime<Utc>>,
 }
+
+// Seeded rows of the payment_status table
+pub const PAYMENT_STATUS_PAID: i32 = 1;
+pub const PAYMENT_STATUS_PARTIAL: i32 = 2;
+pub const PAYMENT_STATUS_UNPAID: i32 = 3;
+
+#[allow(non_snake_case)]
+#[derive(Debug, Deserialize, sqlx::FromRow, Serialize, Clone)]
+pub struct FolioLine {
+    pub id: i32,
+    pub booking_id: i32,
+    pub line_type: String,
+    pub description: String,
+    pub quantity: i32,
+    pub unit_price: BigDecimal,
+    pub tax_amount: BigDecimal,
+    pub posted_by: Option<i32>,
+    pub voided_at: Option<DateTime<Utc>>,
+    pub voided_by: Option<i32>,
+    pub void_reason: Option<String>,
+    #[serde(rename = "createdAt")]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: Option<DateTime<Utc>>,
+    pub parent_line_id: Option<i32>,
+    pub tax_rule_id: Option<i32>,
+    pub currency: String,
+    // Day of the books the line was posted on, the night it is for for room charges
+    pub business_date: Option<NaiveDate>,
+}
+
+impl FolioLine {
+    // Net amount of the line, before tax
+    pub fn net_amount(&self) -> BigDecimal {
+        &self.unit_price * BigDecimal::from(self.quantity)
+    }
+
+    // Tax lines hold the tax computed for their parent charge
+    pub fn is_tax(&self) -> bool {
+        self.line_type == "tax"
+    }
+}
+
+#[allow(non_snake_case)]
+#[derive(Debug, Deserialize, sqlx::FromRow, Serialize, Clone)]
+pub struct TaxRule {
+    pub id: i32,
+    pub name: String,
+    pub kind: String,
+    pub rate: BigDecimal,
+    pub line_types: Vec<String>,
+    pub exempt_children: bool,
+    pub active: bool,
+    #[serde(rename = "createdAt")]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[allow(non_snake_case)]
+#[derive(Debug, Deserialize, sqlx::FromRow, Serialize, Clone)]
+pub struct Payment {
+    pub id: i32,
+    pub booking_id: i32,
+    pub amount: BigDecimal,
+    pub currency: String,
+    pub method: String,
+    pub reference: Option<String>,
+    pub recorded_by: Option<i32>,
+    #[serde(rename = "createdAt")]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+// The property every booking belongs to until several properties are managed
+pub const DEFAULT_PROPERTY_ID: i32 = 1;
+
+#[allow(non_snake_case)]
+#[derive(Debug, Deserialize, sqlx::FromRow, Serialize, Clone)]
+pub struct Property {
+    pub id: i32,
+    pub name: String,
+    pub address: String,
+    pub email_address: String,
+    pub phone_number: String,
+    pub tax_id: Option<String>,
+    pub base_currency: String,
+    #[serde(rename = "createdAt")]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+// Rates are quoted as the amount of `currency` one unit of `base_currency` buys
+#[allow(non_snake_case)]
+#[derive(Debug, Deserialize, sqlx::FromRow, Serialize, Clone)]
+pub struct ExchangeRate {
+    pub id: i32,
+    pub base_currency: String,
+    pub currency: String,
+    pub rate: BigDecimal,
+    pub updated_by: Option<i32>,
+    #[serde(rename = "createdAt")]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[allow(non_snake_case)]
+#[derive(Debug, Deserialize, sqlx::FromRow, Serialize, Clone)]
+pub struct Invoice {
+    pub id: i32,
+    pub invoice_number: String,
+    pub booking_id: i32,
+    pub guest_id: i32,
+    pub property_id: i32,
+    pub bill_to_name: String,
+    pub bill_to_email: String,
+    pub bill_to_phone: String,
+    pub net_total: BigDecimal,
+    pub tax_total: BigDecimal,
+    pub total: BigDecimal,
+    pub payments_total: BigDecimal,
+    pub balance: BigDecimal,
+    pub currency: String,
+    pub issued_at: DateTime<Utc>,
+    #[serde(rename = "createdAt")]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize, sqlx::FromRow, Serialize, Clone)]
+pub struct InvoiceLine {
+    pub id: i32,
+    pub invoice_id: i32,
+    pub line_type: String,
+    pub description: String,
+    pub quantity: i32,
+    pub unit_price: BigDecimal,
+    pub net_amount: BigDecimal,
+    pub tax_amount: BigDecimal,
+    pub total: BigDecimal,
+    pub taxable_amount: Option<BigDecimal>,
+}
+
+#[allow(non_snake_case)]
+#[derive(Debug, Deserialize, sqlx::FromRow, Serialize, Clone)]
+pub struct LoyaltyEntry {
+    pub id: i32,
+    pub guest_id: i32,
+    pub booking_id: Option<i32>,
+    pub entry_type: String,
+    pub points: i32,
+    pub description: String,
+    #[serde(rename = "createdAt")]
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+#[allow(non_snake_case)]
+#[derive(Debug, Deserialize, sqlx::FromRow, Serialize, Clone)]
+pub struct GiftVoucher {
+    pub id: i32,
+    pub code: String,
+    pub initial_amount: BigDecimal,
+    pub balance: BigDecimal,
+    pub currency: String,
+    pub purchased_by: Option<i32>,
+    pub recipient_name: Option<String>,
+    pub recipient_email: Option<String>,
+    pub message: Option<String>,
+    pub expires_at: DateTime<Utc>,
+    #[serde(rename = "createdAt")]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[allow(non_snake_case)]
+#[derive(Debug, Deserialize, sqlx::FromRow, Serialize, Clone)]
+pub struct GiftVoucherTransaction {
+    pub id: i32,
+    pub gift_voucher_id: i32,
+    pub transaction_type: String,
+    // Positive when money is added to the voucher, negative when it is spent
+    pub amount: BigDecimal,
+    pub booking_id: Option<i32>,
+    pub payment_id: Option<i32>,
+    pub method: Option<String>,
+    pub performed_by: Option<i32>,
+    #[serde(rename = "createdAt")]
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+#[allow(non_snake_case)]
+#[derive(Debug, Deserialize, sqlx::FromRow, Serialize, Clone)]
+pub struct ReservationGroup {
+    pub id: i32,
+    pub code: String,
+    pub name: String,
+    pub lead_guest_id: i32,
+    pub billing_type: String,
+    // Room type of the held block, rooms picked up with the code default to it
+    pub room_type_id: Option<i32>,
+    pub checkin_date: NaiveDate,
+    pub checkout_date: NaiveDate,
+    pub rooms_held: i32,
+    #[serde(rename = "createdAt")]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[allow(non_snake_case)]
+#[derive(Debug, Deserialize, sqlx::FromRow, Serialize, Clone)]
+pub struct Room {
+    pub id: i32,
+    pub room_number: String,
+    pub room_type_id: i32,
+    pub floor: i32,
+    pub active: bool,
+    #[serde(rename = "createdAt")]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: Option<DateTime<Utc>>,
+    pub housekeeping_status: String,
+    pub status_updated_at: Option<DateTime<Utc>>,
+    pub accessible: bool,
+    pub near_elevator: bool,
+}
+
+#[allow(non_snake_case)]
+#[derive(Debug, Deserialize, sqlx::FromRow, Serialize, Clone)]
+pub struct AllotmentBlock {
+    pub id: i32,
+    pub code: String,
+    pub name: String,
+    pub room_type_id: i32,
+    pub start_date: NaiveDate,
+    // Check-out date of the last night held
+    pub end_date: NaiveDate,
+    pub rooms: i32,
+    pub cutoff_date: NaiveDate,
+    // Set once the rooms not picked up went back to general availability
+    pub released_at: Option<DateTime<Utc>>,
+    pub created_by: Option<i32>,
+    #[serde(rename = "createdAt")]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[allow(non_snake_case)]
+#[derive(Debug, Deserialize, sqlx::FromRow, Serialize, Clone)]
+pub struct RoomStatusChange {
+    pub id: i32,
+    pub room_id: i32,
+    pub from_status: String,
+    pub to_status: String,
+    pub booking_id: Option<i32>,
+    pub reason: Option<String>,
+    pub changed_by: Option<i32>,
+    #[serde(rename = "createdAt")]
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+#[allow(non_snake_case)]
+#[derive(Debug, Deserialize, sqlx::FromRow, Serialize, Clone)]
+pub struct HousekeepingTask {
+    pub id: i32,
+    pub room_id: i32,
+    pub task_type: String,
+    pub status: String,
+    pub assigned_to: Option<i32>,
+    pub booking_id: Option<i32>,
+    pub notes: Option<String>,
+    pub created_by: Option<i32>,
+    pub completed_by: Option<i32>,
+    pub completed_at: Option<DateTime<Utc>>,
+    #[serde(rename = "createdAt")]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[allow(non_snake_case)]
+#[derive(Debug, Deserialize, sqlx::FromRow, Serialize, Clone)]
+pub struct MaintenanceTicket {
+    pub id: i32,
+    pub room_id: i32,
+    pub title: String,
+    pub description: Option<String>,
+    pub priority: String,
+    pub status: String,
+    pub assigned_to: Option<i32>,
+    pub reported_by: Option<i32>,
+    pub out_of_order: bool,
+    pub out_of_order_from: Option<NaiveDate>,
+    // First night the room can be sold again
+    pub out_of_order_until: Option<NaiveDate>,
+    pub resolved_at: Option<DateTime<Utc>>,
+    #[serde(rename = "createdAt")]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+// Attachment of a maintenance ticket, without its content
+#[allow(non_snake_case)]
+#[derive(Debug, Deserialize, sqlx::FromRow, Serialize, Clone)]
+pub struct MaintenanceAttachment {
+    pub id: i32,
+    pub maintenance_ticket_id: i32,
+    pub file_name: String,
+    pub content_type: String,
+    pub size_bytes: i32,
+    pub uploaded_by: Option<i32>,
+    #[serde(rename = "createdAt")]
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+#[allow(non_snake_case)]
+#[derive(Debug, Deserialize, sqlx::FromRow, Serialize, Clone)]
+pub struct NightAudit {
+    pub id: i32,
+    pub business_date: NaiveDate,
+    pub rooms_total: i32,
+    pub rooms_out_of_order: i32,
+    pub rooms_occupied: i32,
+    pub arrivals: i32,
+    pub departures: i32,
+    pub no_shows: i32,
+    pub room_charges_posted: i32,
+    pub room_revenue: BigDecimal,
+    pub other_revenue: BigDecimal,
+    pub tax_revenue: BigDecimal,
+    pub currency: String,
+    pub run_by: Option<i32>,
+    #[serde(rename = "createdAt")]
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+#[allow(non_snake_case)]
+#[derive(Debug, Deserialize, sqlx::FromRow, Serialize, Clone)]
+pub struct PricingRule {
+    pub id: i32,
+    pub name: String,
+    pub room_type_id: Option<i32>,
+    pub occupancy_above: Option<BigDecimal>,
+    pub occupancy_below: Option<BigDecimal>,
+    pub max_days_before_arrival: Option<i32>,
+    pub adjustment_percent: BigDecimal,
+    pub floor_price: Option<BigDecimal>,
+    pub ceiling_price: Option<BigDecimal>,
+    pub priority: i32,
+    pub active: bool,
+    #[serde(rename = "createdAt")]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+// Change a pricing rule made to the rate of one night of a booking
+#[allow(non_snake_case)]
+#[derive(Debug, Deserialize, sqlx::FromRow, Serialize, Clone)]
+pub struct PriceAdjustment {
+    pub id: i32,
+    pub booking_id: i32,
+    pub pricing_rule_id: i32,
+    pub room_type_id: i32,
+    pub night: NaiveDate,
+    pub occupancy_percent: BigDecimal,
+    pub rate_before: BigDecimal,
+    pub rate_after: BigDecimal,
+    #[serde(rename = "createdAt")]
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+#[allow(non_snake_case)]
+#[derive(Debug, Deserialize, sqlx::FromRow, Serialize, Clone)]
+pub struct StayRestriction {
+    pub id: i32,
+    pub room_type_id: Option<i32>,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub min_stay: Option<i32>,
+    pub max_stay: Option<i32>,
+    pub closed_to_arrival: bool,
+    pub closed_to_departure: bool,
+    pub stop_sell: bool,
+    pub reason: Option<String>,
+    pub created_by: Option<i32>,
+    #[serde(rename = "createdAt")]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[allow(non_snake_case)]
+#[derive(Debug, Deserialize, sqlx::FromRow, Serialize, Clone)]
+pub struct WaitlistEntry {
+    pub id: i32,
+    pub guest_id: i32,
+    pub room_type_id: i32,
+    pub checkin_date: NaiveDate,
+    pub checkout_date: NaiveDate,
+    pub num_adults: i32,
+    pub num_children: i32,
+    pub status: String,
+    pub offered_at: Option<DateTime<Utc>>,
+    pub hold_expires_at: Option<DateTime<Utc>>,
+    pub booking_id: Option<i32>,
+    #[serde(rename = "createdAt")]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[allow(non_snake_case)]
+#[derive(Debug, Deserialize, sqlx::FromRow, Serialize, Clone)]
+pub struct Notification {
+    pub id: i32,
+    pub guest_id: i32,
+    pub kind: String,
+    pub message: String,
+    pub waitlist_entry_id: Option<i32>,
+    #[serde(rename = "createdAt")]
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+#[allow(non_snake_case)]
+#[derive(Debug, Deserialize, sqlx::FromRow, Serialize, Clone)]
+pub struct PaymentMethod {
+    pub id: i32,
+    pub guest_id: i32,
+    pub brand: String,
+    pub last_four: String,
+    pub exp_month: i32,
+    pub exp_year: i32,
+    #[serde(rename = "createdAt")]
+    pub created_at: Option<DateTime<Utc>>,
+}
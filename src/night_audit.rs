@@ -0,0 +1,234 @@
+use bigdecimal::BigDecimal;
+use chrono::{Duration, NaiveDate, Utc};
+use sqlx::{PgConnection, Pool, Postgres};
+
+use crate::{
+    currency::base_currency,
+    folio::{post_charge, refresh_payment_status, NewCharge},
+    maintenance::OPEN_TICKET_STATUSES,
+    models::{
+        Booking, NightAudit, BOOKING_STATUS_CHECKED_IN, BOOKING_STATUS_CHECKED_OUT,
+        BOOKING_STATUS_CONFIRMED, BOOKING_STATUS_NO_SHOW, BOOKING_STATUS_PENDING,
+    },
+};
+
+// Why the day couldn't be closed
+pub enum AuditError {
+    Rejected(String),
+    Database(sqlx::Error),
+}
+
+impl From<sqlx::Error> for AuditError {
+    fn from(e: sqlx::Error) -> Self {
+        AuditError::Database(e)
+    }
+}
+
+// Close the current business date and open the next one
+//
+// Every in-house booking is charged the night, arrivals that never came are
+// marked no-shows and the occupancy and revenue of the day are kept in a
+// snapshot. Everything happens in one transaction, so a failed audit can be
+// run again.
+pub async fn run_night_audit(
+    db: &Pool<Postgres>,
+    run_by: Option<i32>,
+) -> Result<NightAudit, AuditError> {
+    let mut tx = db.begin().await?;
+
+    // Only one audit runs at a time
+    let business_date = sqlx::query_scalar!("select business_date from business_date for update")
+        .fetch_one(&mut *tx)
+        .await?;
+
+    // The day can be closed before midnight but the books can't run ahead of the calendar
+    if business_date > Utc::now().date_naive() {
+        return Err(AuditError::Rejected(format!(
+            "Business date {} is ahead of the calendar, the night audit already ran today",
+            business_date
+        )));
+    }
+
+    let charged = post_room_charges(&mut tx, business_date).await?;
+
+    let no_shows = sqlx::query_scalar!(
+        "update booking set status = $1, updated_at = now()
+        where checkin_date <= $2 and status in ($3, $4)
+        returning id",
+        BOOKING_STATUS_NO_SHOW,
+        business_date,
+        BOOKING_STATUS_PENDING,
+        BOOKING_STATUS_CONFIRMED
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let audit = snapshot(
+        &mut tx,
+        business_date,
+        charged.len() as i32,
+        no_shows.len() as i32,
+        run_by,
+    )
+    .await?;
+
+    sqlx::query!(
+        "update business_date set business_date = $1, updated_at = now()",
+        business_date + Duration::days(1)
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    // Charged bookings now owe the night
+    for booking_id in &charged {
+        refresh_payment_status(db, *booking_id).await?;
+    }
+
+    for booking_id in no_shows {
+        tracing::info!(booking_id, "booking marked no-show");
+    }
+
+    Ok(audit)
+}
+
+// Charge the in-house bookings the night of the business date, skipping the ones already charged
+async fn post_room_charges(
+    conn: &mut PgConnection,
+    business_date: NaiveDate,
+) -> Result<Vec<i32>, sqlx::Error> {
+    let bookings = sqlx::query_as!(
+        Booking,
+        "select * from booking b
+        where b.status = $1
+            and b.checkin_date <= $2
+            and b.checkout_date > $2
+            and not exists (
+                select 1 from folio_line l
+                where l.booking_id = b.id
+                    and l.line_type = 'room'
+                    and l.posted_by is null
+                    and l.voided_at is null
+                    and l.business_date = $2
+            )
+        order by b.id",
+        BOOKING_STATUS_CHECKED_IN,
+        business_date
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut charged = Vec::with_capacity(bookings.len());
+    for booking in bookings {
+        post_charge(
+            &mut *conn,
+            NewCharge {
+                booking_id: booking.id,
+                line_type: "room".to_string(),
+                description: format!("Room charge for the night of {}", business_date),
+                quantity: 1,
+                unit_price: night_rate(&booking, business_date),
+                tax_amount: None,
+                posted_by: None,
+            },
+        )
+        .await?;
+        charged.push(booking.id);
+    }
+
+    Ok(charged)
+}
+
+// The booking amount spread over the nights of the stay, the last night takes the rounding difference
+fn night_rate(booking: &Booking, night: NaiveDate) -> BigDecimal {
+    let nights = (booking.checkout_date - booking.checkin_date)
+        .num_days()
+        .max(1);
+    let rate = (&booking.booking_amount / BigDecimal::from(nights)).round(2);
+
+    if night + Duration::days(1) == booking.checkout_date {
+        return (&booking.booking_amount - &rate * BigDecimal::from(nights - 1)).with_scale(2);
+    }
+
+    rate.with_scale(2)
+}
+
+// Keep the occupancy and revenue of the business date
+async fn snapshot(
+    conn: &mut PgConnection,
+    business_date: NaiveDate,
+    room_charges_posted: i32,
+    no_shows: i32,
+    run_by: Option<i32>,
+) -> Result<NightAudit, sqlx::Error> {
+    let currency = base_currency(&mut *conn).await?;
+
+    sqlx::query_as!(
+        NightAudit,
+        "insert into night_audit
+            (
+                business_date,
+                rooms_total,
+                rooms_out_of_order,
+                rooms_occupied,
+                arrivals,
+                departures,
+                no_shows,
+                room_charges_posted,
+                room_revenue,
+                other_revenue,
+                tax_revenue,
+                currency,
+                run_by
+            )
+        select
+            $1,
+            (select count(*) from room where active)::int,
+            (
+                select count(distinct room_id) from maintenance_ticket
+                where out_of_order
+                    and status = any($2)
+                    and out_of_order_from <= $1
+                    and out_of_order_until > $1
+            )::int,
+            (
+                select count(*) from booking
+                where status = $3 and checkin_date <= $1 and checkout_date > $1
+            )::int,
+            (
+                select count(*) from booking
+                where checkin_date = $1 and status in ($3, $4)
+            )::int,
+            (
+                select count(*) from booking
+                where checkout_date = $1 and status = $4
+            )::int,
+            $5,
+            $6,
+            round(coalesce(lines.room_revenue, 0), 2),
+            round(coalesce(lines.other_revenue, 0), 2),
+            round(coalesce(lines.tax_revenue, 0), 2),
+            $7,
+            $8
+        from (
+            select
+                sum(unit_price * quantity) filter (where line_type = 'room') as room_revenue,
+                sum(unit_price * quantity) filter (where line_type not in ('room', 'tax')) as other_revenue,
+                sum(case when line_type = 'tax' then unit_price * quantity else tax_amount end) as tax_revenue
+            from folio_line
+            where business_date = $1 and voided_at is null
+        ) as lines
+        returning *",
+        business_date,
+        OPEN_TICKET_STATUSES as &[&str],
+        BOOKING_STATUS_CHECKED_IN,
+        BOOKING_STATUS_CHECKED_OUT,
+        no_shows,
+        room_charges_posted,
+        currency,
+        run_by
+    )
+    .fetch_one(conn)
+    .await
+}
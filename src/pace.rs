@@ -0,0 +1,228 @@
+use bigdecimal::{BigDecimal, Zero};
+use chrono::{Duration, NaiveDate};
+use sqlx::{Pool, Postgres};
+
+use crate::{
+    business_date::current_business_date,
+    csv::CsvWriter,
+    maintenance::OPEN_TICKET_STATUSES,
+    models::BOOKING_STATUS_EXPIRED,
+    response::{PacePoint, PaceReport, PickupRow},
+};
+
+// Stay dates are compared with the same weekday a year before
+pub const LAST_YEAR_DAYS: i32 = 364;
+
+// Longest pace curve and range of stay dates a report covers
+pub const MAX_PACE_DAYS: i32 = 365;
+
+// Room nights on the books for every stay date between `from` and `to`, with a forecast of where they end
+//
+// A room night is on the books from the day its booking was made, expired
+// bookings never were. The forecast adds the room nights last year's stay date
+// picked up from the same number of days out to what is on the books now.
+pub async fn pickup_report(
+    db: &Pool<Postgres>,
+    from: NaiveDate,
+    to: NaiveDate,
+    room_type_id: Option<i32>,
+    pickup_days: i32,
+) -> Result<Vec<PickupRow>, sqlx::Error> {
+    let business_date = current_business_date(db).await?;
+
+    let rows = sqlx::query!(
+        "with nights as (
+            select night::date as night, greatest(night::date - $3::date, 0) as lead_days
+            from generate_series($1::date, $2::date, interval '1 day') as night
+        )
+        select
+            n.night as \"stay_date!\",
+            n.lead_days as \"lead_days!\",
+            (
+                select count(*) from room r
+                where r.active
+                    and ($4::int is null or r.room_type_id = $4)
+                    and not exists (
+                        select 1 from maintenance_ticket t
+                        where t.room_id = r.id
+                            and t.out_of_order
+                            and t.status = any($7)
+                            and t.out_of_order_from <= n.night
+                            and t.out_of_order_until > n.night
+                    )
+            ) as \"capacity!\",
+            count(b.id) filter (
+                where b.checkin_date <= n.night and b.checkout_date > n.night
+                    and b.created_at::date <= $3
+            ) as \"on_the_books!\",
+            count(b.id) filter (
+                where b.checkin_date <= n.night and b.checkout_date > n.night
+                    and b.created_at::date <= $3 - $5::int
+            ) as \"on_the_books_before!\",
+            count(b.id) filter (
+                where b.checkin_date <= n.night - $8::int and b.checkout_date > n.night - $8::int
+                    and b.created_at::date <= n.night - $8::int - n.lead_days
+            ) as \"last_year_on_the_books!\",
+            count(b.id) filter (
+                where b.checkin_date <= n.night - $8::int and b.checkout_date > n.night - $8::int
+            ) as \"last_year_final!\"
+        from nights n
+        left join booking b on b.status <> $6
+            and ($4::int is null or b.room_type_id = $4)
+            and (
+                (b.checkin_date <= n.night and b.checkout_date > n.night)
+                or (b.checkin_date <= n.night - $8::int and b.checkout_date > n.night - $8::int)
+            )
+        group by n.night, n.lead_days
+        order by n.night",
+        from,
+        to,
+        business_date,
+        room_type_id,
+        pickup_days,
+        BOOKING_STATUS_EXPIRED,
+        OPEN_TICKET_STATUSES as &[&str],
+        LAST_YEAR_DAYS
+    )
+    .fetch_all(db)
+    .await?;
+
+    let rows = rows
+        .into_iter()
+        .map(|row| {
+            let forecast_room_nights = forecast(
+                row.capacity,
+                row.on_the_books,
+                row.lead_days,
+                row.last_year_on_the_books,
+                row.last_year_final,
+            );
+            let forecast_occupancy_percent = if row.capacity == 0 {
+                BigDecimal::zero().with_scale(2)
+            } else {
+                (BigDecimal::from(forecast_room_nights * 100) / BigDecimal::from(row.capacity))
+                    .round(2)
+                    .with_scale(2)
+            };
+
+            PickupRow {
+                stay_date: row.stay_date,
+                lead_days: row.lead_days,
+                capacity: row.capacity,
+                on_the_books: row.on_the_books,
+                pickup: row.on_the_books - row.on_the_books_before,
+                last_year_stay_date: row.stay_date - Duration::days(LAST_YEAR_DAYS.into()),
+                last_year_on_the_books: row.last_year_on_the_books,
+                last_year_final: row.last_year_final,
+                forecast_room_nights,
+                forecast_occupancy_percent,
+            }
+        })
+        .collect();
+
+    Ok(rows)
+}
+
+// What is on the books plus what last year still picked up, no more than can be sold
+fn forecast(
+    capacity: i64,
+    on_the_books: i64,
+    lead_days: i32,
+    last_year_on_the_books: i64,
+    last_year_final: i64,
+) -> i64 {
+    // Nothing is left to pick up once the stay date is reached
+    if lead_days == 0 {
+        return on_the_books;
+    }
+
+    let remaining_pickup = (last_year_final - last_year_on_the_books).max(0);
+    (on_the_books + remaining_pickup)
+        .min(capacity)
+        .max(on_the_books)
+}
+
+// How the bookings of a stay date came in over the days before it, next to last year's
+pub async fn pace_report(
+    db: &Pool<Postgres>,
+    stay_date: NaiveDate,
+    room_type_id: Option<i32>,
+    days_before: i32,
+) -> Result<PaceReport, sqlx::Error> {
+    let forecast = pickup_report(db, stay_date, stay_date, room_type_id, 7)
+        .await?
+        .remove(0);
+
+    // The curve stops at the business date for stay dates still to come
+    let pace = sqlx::query!(
+        "select
+            d as \"days_before!\",
+            count(b.id) filter (
+                where b.checkin_date <= $1 and b.checkout_date > $1
+                    and b.created_at::date <= $1 - d
+            ) as \"on_the_books!\",
+            count(b.id) filter (
+                where b.checkin_date <= $2 and b.checkout_date > $2
+                    and b.created_at::date <= $2 - d
+            ) as \"last_year_on_the_books!\"
+        from generate_series($3::int, $4::int, -1) as d
+        left join booking b on b.status <> $6
+            and ($5::int is null or b.room_type_id = $5)
+            and (
+                (b.checkin_date <= $1 and b.checkout_date > $1)
+                or (b.checkin_date <= $2 and b.checkout_date > $2)
+            )
+        group by d
+        order by d desc",
+        stay_date,
+        forecast.last_year_stay_date,
+        days_before.max(forecast.lead_days),
+        forecast.lead_days,
+        room_type_id,
+        BOOKING_STATUS_EXPIRED
+    )
+    .fetch_all(db)
+    .await?
+    .into_iter()
+    .map(|point| PacePoint {
+        days_before: point.days_before,
+        on_the_books: point.on_the_books,
+        last_year_on_the_books: point.last_year_on_the_books,
+    })
+    .collect();
+
+    Ok(PaceReport { forecast, pace })
+}
+
+// Render the forecast of every stay date
+pub fn pickup_csv(rows: &[PickupRow]) -> String {
+    let mut csv = CsvWriter::new(&[
+        "stay_date",
+        "lead_days",
+        "capacity",
+        "on_the_books",
+        "pickup",
+        "last_year_stay_date",
+        "last_year_on_the_books",
+        "last_year_final",
+        "forecast_room_nights",
+        "forecast_occupancy_percent",
+    ]);
+
+    for row in rows {
+        csv.record(&[
+            row.stay_date.to_string(),
+            row.lead_days.to_string(),
+            row.capacity.to_string(),
+            row.on_the_books.to_string(),
+            row.pickup.to_string(),
+            row.last_year_stay_date.to_string(),
+            row.last_year_on_the_books.to_string(),
+            row.last_year_final.to_string(),
+            row.forecast_room_nights.to_string(),
+            row.forecast_occupancy_percent.to_string(),
+        ]);
+    }
+
+    csv.finish()
+}
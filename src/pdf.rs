@@ -0,0 +1,148 @@
+// Minimal PDF writer for plain text documents, enough for invoices and receipts
+//
+// Text is laid out top to bottom in the built-in Courier fonts, so no font has
+// to be embedded and columns line up without measuring glyph widths.
+
+// A4 in points
+const PAGE_WIDTH: f32 = 595.0;
+const PAGE_HEIGHT: f32 = 842.0;
+const MARGIN: f32 = 50.0;
+
+pub struct PdfDocument {
+    pages: Vec<String>,
+    current: String,
+    y: f32,
+}
+
+impl Default for PdfDocument {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PdfDocument {
+    pub fn new() -> PdfDocument {
+        PdfDocument {
+            pages: Vec::new(),
+            current: String::new(),
+            y: PAGE_HEIGHT - MARGIN,
+        }
+    }
+
+    // Write one line of text at the cursor, starting a new page when this one is full
+    pub fn text(&mut self, text: &str, size: f32, bold: bool) {
+        let line_height = size * 1.4;
+        if self.y - line_height < MARGIN {
+            self.new_page();
+        }
+        self.y -= line_height;
+
+        let font = if bold { "F2" } else { "F1" };
+        self.current.push_str(&format!(
+            "BT /{} {} Tf {} {:.2} Td ({}) Tj ET\n",
+            font,
+            size,
+            MARGIN,
+            self.y,
+            escape(text)
+        ));
+    }
+
+    // Leave some vertical space
+    pub fn gap(&mut self, points: f32) {
+        self.y -= points;
+    }
+
+    fn new_page(&mut self) {
+        self.pages.push(std::mem::take(&mut self.current));
+        self.y = PAGE_HEIGHT - MARGIN;
+    }
+
+    // Serialize the document, computing the cross reference table on the way
+    pub fn finish(mut self) -> Vec<u8> {
+        self.new_page();
+
+        let mut objects: Vec<String> = Vec::new();
+        let page_count = self.pages.len();
+
+        // 1: catalog, 2: page tree, 3 and 4: fonts, then a page and its content for every page
+        let kids: Vec<String> = (0..page_count)
+            .map(|i| format!("{} 0 R", 5 + i * 2))
+            .collect();
+
+        objects.push("<< /Type /Catalog /Pages 2 0 R >>".to_string());
+        objects.push(format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            kids.join(" "),
+            page_count
+        ));
+        objects.push(
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Courier /Encoding /WinAnsiEncoding >>"
+                .to_string(),
+        );
+        objects.push(
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Courier-Bold /Encoding /WinAnsiEncoding >>"
+                .to_string(),
+        );
+
+        for (i, content) in self.pages.iter().enumerate() {
+            objects.push(format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] \
+                 /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+                PAGE_WIDTH,
+                PAGE_HEIGHT,
+                6 + i * 2
+            ));
+            objects.push(format!(
+                "<< /Length {} >>\nstream\n{}endstream",
+                content.len(),
+                content
+            ));
+        }
+
+        let mut output = b"%PDF-1.4\n".to_vec();
+        let mut offsets = Vec::with_capacity(objects.len());
+
+        for (i, object) in objects.iter().enumerate() {
+            offsets.push(output.len());
+            output.extend_from_slice(format!("{} 0 obj\n{}\nendobj\n", i + 1, object).as_bytes());
+        }
+
+        let xref_offset = output.len();
+        output.extend_from_slice(
+            format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes(),
+        );
+        for offset in offsets {
+            output.extend_from_slice(format!("{:010} 00000 n \n", offset).as_bytes());
+        }
+        output.extend_from_slice(
+            format!(
+                "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+                objects.len() + 1,
+                xref_offset
+            )
+            .as_bytes(),
+        );
+
+        output
+    }
+}
+
+// Escape a string for a PDF literal, latin-1 characters are written as octal codes
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '\\' | '(' | ')' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            ' '..='~' => escaped.push(c),
+            '\u{a0}'..='\u{ff}' => escaped.push_str(&format!("\\{:03o}", c as u32)),
+            _ => escaped.push('?'),
+        }
+    }
+
+    escaped
+}
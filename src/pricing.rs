@@ -0,0 +1,243 @@
+use bigdecimal::{BigDecimal, Zero};
+use chrono::NaiveDate;
+use sqlx::{PgConnection, Pool, Postgres};
+
+use crate::{
+    availability::night_occupancy,
+    business_date::current_business_date,
+    currency::{base_currency, convert, exchange_rate},
+    models::{PricingRule, RoomType},
+    response::{NightlyRate, Quote, RateAdjustment},
+    tax::{active_tax_rules, compute_taxes, TaxLine, Taxable},
+};
+
+// Price a stay in the property currency with the taxes the active rules add to the room charge
+pub async fn quote_stay(
+    db: &Pool<Postgres>,
+    checkin_date: NaiveDate,
+    checkout_date: NaiveDate,
+    num_adults: i32,
+    num_children: i32,
+    net_amount: &BigDecimal,
+) -> Result<Quote, sqlx::Error> {
+    let nights = (checkout_date - checkin_date).num_days() as i32;
+
+    let rules = active_tax_rules(db).await?;
+    let taxes = compute_taxes(
+        &rules,
+        &Taxable {
+            line_type: "room",
+            net_amount,
+            nights,
+            num_adults,
+            num_children,
+        },
+    );
+
+    let tax_total = taxes
+        .iter()
+        .fold(BigDecimal::zero(), |total, tax| total + &tax.amount);
+    let total = net_amount + &tax_total;
+
+    Ok(Quote {
+        currency: base_currency(db).await?,
+        exchange_rate: None,
+        nights,
+        net_amount: net_amount.with_scale(2),
+        taxes,
+        tax_total: tax_total.with_scale(2),
+        total: total.with_scale(2),
+    })
+}
+
+// Show a quote in another currency, charges are still posted in the property currency
+fn convert_quote(quote: &Quote, currency: &str, rate: &BigDecimal) -> Quote {
+    let taxes = quote
+        .taxes
+        .iter()
+        .map(|tax| TaxLine {
+            amount: convert(&tax.amount, rate),
+            taxable_amount: convert(&tax.taxable_amount, rate),
+            ..tax.clone()
+        })
+        .collect();
+
+    Quote {
+        currency: currency.to_string(),
+        exchange_rate: Some(rate.clone()),
+        nights: quote.nights,
+        net_amount: convert(&quote.net_amount, rate),
+        taxes,
+        tax_total: convert(&quote.tax_total, rate),
+        total: convert(&quote.total, rate),
+    }
+}
+
+// Convert a quote with the configured rate, `None` when no rate is configured for the currency
+pub async fn quote_in_currency(
+    db: &Pool<Postgres>,
+    quote: &Quote,
+    currency: &str,
+) -> Result<Option<Quote>, sqlx::Error> {
+    let rate = exchange_rate(db, &quote.currency, currency).await?;
+
+    Ok(rate.map(|rate| convert_quote(quote, currency, &rate)))
+}
+
+// Rate of every night of a stay in the room type, moved by the pricing rules matching
+// the occupancy of the night and how far ahead of the business date the stay starts
+//
+// Occupancy is that of the room type: rooms booked, held in blocks or held for the
+// waitlist over the rooms that can be sold that night, the stay being priced not included.
+// A booking moved to new dates is priced without its own nights.
+pub async fn price_stay(
+    conn: &mut PgConnection,
+    room_type: &RoomType,
+    checkin_date: NaiveDate,
+    checkout_date: NaiveDate,
+    except_booking_id: Option<i32>,
+) -> Result<Vec<NightlyRate>, sqlx::Error> {
+    let rules = sqlx::query_as!(
+        PricingRule,
+        "select * from pricing_rule
+        where active = true and (room_type_id is null or room_type_id = $1)
+        order by priority, id",
+        room_type.id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let today = current_business_date(&mut *conn).await?;
+    let days_before_arrival = (checkin_date - today).num_days();
+
+    let nights = night_occupancy(
+        &mut *conn,
+        room_type.id,
+        checkin_date,
+        checkout_date,
+        except_booking_id,
+    )
+    .await?;
+
+    Ok(nights
+        .into_iter()
+        .map(|night| {
+            let occupancy_percent = occupancy_percent(night.sold, night.sellable);
+            let (rate, adjustments) = apply_pricing_rules(
+                &rules,
+                &room_type.base_rate,
+                &occupancy_percent,
+                days_before_arrival,
+            );
+
+            NightlyRate {
+                night: night.night,
+                occupancy_percent,
+                base_rate: room_type.base_rate.with_scale(2),
+                rate,
+                adjustments,
+            }
+        })
+        .collect())
+}
+
+// Room amount of a stay priced night by night
+pub fn stay_amount(rates: &[NightlyRate]) -> BigDecimal {
+    rates
+        .iter()
+        .fold(BigDecimal::zero(), |total, night| total + &night.rate)
+        .with_scale(2)
+}
+
+// Keep the changes the pricing rules made to the rates of a booking
+pub async fn log_price_adjustments(
+    conn: &mut PgConnection,
+    booking_id: i32,
+    room_type_id: i32,
+    rates: &[NightlyRate],
+) -> Result<(), sqlx::Error> {
+    for night in rates {
+        for adjustment in &night.adjustments {
+            sqlx::query!(
+                "insert into price_adjustment
+                    (booking_id, pricing_rule_id, room_type_id, night, occupancy_percent, rate_before, rate_after)
+                values ($1, $2, $3, $4, $5, $6, $7)",
+                booking_id,
+                adjustment.pricing_rule_id,
+                room_type_id,
+                night.night,
+                night.occupancy_percent,
+                adjustment.rate_before,
+                adjustment.rate_after
+            )
+            .execute(&mut *conn)
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+// Share of the sellable rooms sold, a night with nothing left to sell is full
+fn occupancy_percent(sold: i64, sellable: i64) -> BigDecimal {
+    if sellable <= 0 {
+        return BigDecimal::from(100).with_scale(2);
+    }
+
+    let percent = BigDecimal::from(sold.clamp(0, sellable) * 100) / BigDecimal::from(sellable);
+    percent.round(2).with_scale(2)
+}
+
+// Apply the matching rules one after the other, a rule never takes the rate past its
+// ceiling when raising it or below its floor when discounting it
+fn apply_pricing_rules(
+    rules: &[PricingRule],
+    base_rate: &BigDecimal,
+    occupancy_percent: &BigDecimal,
+    days_before_arrival: i64,
+) -> (BigDecimal, Vec<RateAdjustment>) {
+    let mut rate = base_rate.with_scale(2);
+    let mut adjustments = Vec::new();
+
+    for rule in rules {
+        let matches = rule
+            .occupancy_above
+            .as_ref()
+            .is_none_or(|above| occupancy_percent > above)
+            && rule
+                .occupancy_below
+                .as_ref()
+                .is_none_or(|below| occupancy_percent < below)
+            && rule
+                .max_days_before_arrival
+                .is_none_or(|days| days_before_arrival <= i64::from(days));
+        if !matches {
+            continue;
+        }
+
+        let mut adjusted = (&rate * (BigDecimal::from(100) + &rule.adjustment_percent)
+            / BigDecimal::from(100))
+        .round(2);
+        if adjusted > rate {
+            if let Some(ceiling) = &rule.ceiling_price {
+                adjusted = adjusted.min(rate.clone().max(ceiling.clone()));
+            }
+        } else if let Some(floor) = &rule.floor_price {
+            adjusted = adjusted.max(rate.clone().min(floor.clone()));
+        }
+        let adjusted = adjusted.with_scale(2);
+
+        if adjusted != rate {
+            adjustments.push(RateAdjustment {
+                pricing_rule_id: rule.id,
+                name: rule.name.to_owned(),
+                adjustment_percent: rule.adjustment_percent.clone(),
+                rate_before: rate.clone(),
+                rate_after: adjusted.clone(),
+            });
+            rate = adjusted;
+        }
+    }
+
+    (rate, adjustments)
+}
@@ -0,0 +1,148 @@
+use bigdecimal::{BigDecimal, Zero};
+use chrono::Utc;
+use sqlx::PgConnection;
+
+use crate::models::{PromoCode, BOOKING_STATUS_EXPIRED};
+
+// Ways a promo code can take money off the room amount
+pub const DISCOUNT_TYPES: &[&str] = &["percentage", "fixed"];
+
+// Why a promo code couldn't be used
+pub enum PromoError {
+    Rejected(String),
+    Database(sqlx::Error),
+}
+
+impl From<sqlx::Error> for PromoError {
+    fn from(e: sqlx::Error) -> Self {
+        PromoError::Database(e)
+    }
+}
+
+// The stay a promo code is applied to
+pub struct PromoStay<'a> {
+    pub guest_id: i32,
+    pub room_type_id: Option<i32>,
+    pub nights: i32,
+    pub room_amount: &'a BigDecimal,
+}
+
+// Check the code can be used for the stay and compute its discount
+//
+// The code row stays locked until the transaction creating the booking ends,
+// so concurrent bookings can't go over the usage limits.
+pub async fn apply_promo_code(
+    conn: &mut PgConnection,
+    code: &str,
+    stay: &PromoStay<'_>,
+) -> Result<(PromoCode, BigDecimal), PromoError> {
+    let promo = sqlx::query_as!(
+        PromoCode,
+        "select * from promo_code where upper(code) = upper($1) and active = true for update",
+        code
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| PromoError::Rejected(format!("Promo code {} is not valid", code)))?;
+
+    let now = Utc::now();
+    let not_started = promo.valid_from.is_some_and(|valid_from| valid_from > now);
+    let ended = promo
+        .valid_until
+        .is_some_and(|valid_until| valid_until < now);
+    if not_started || ended {
+        return Err(PromoError::Rejected(format!(
+            "Promo code {} is not valid at this time",
+            promo.code
+        )));
+    }
+
+    if stay.nights < promo.min_nights {
+        return Err(PromoError::Rejected(format!(
+            "Promo code {} requires a stay of at least {} nights",
+            promo.code, promo.min_nights
+        )));
+    }
+
+    let room_type_allowed = promo.room_type_ids.is_empty()
+        || stay
+            .room_type_id
+            .is_some_and(|room_type_id| promo.room_type_ids.contains(&room_type_id));
+    if !room_type_allowed {
+        return Err(PromoError::Rejected(format!(
+            "Promo code {} doesn't apply to this room type",
+            promo.code
+        )));
+    }
+
+    // Expired bookings give their use of the code back
+    let usage = sqlx::query!(
+        "select
+            count(*) as \"uses!\",
+            count(*) filter (where r.guest_id = $2) as \"guest_uses!\"
+        from promo_redemption r join booking b on b.id = r.booking_id
+        where r.promo_code_id = $1 and b.status <> $3",
+        promo.id,
+        stay.guest_id,
+        BOOKING_STATUS_EXPIRED
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    if promo
+        .max_uses
+        .is_some_and(|max_uses| usage.uses >= max_uses.into())
+    {
+        return Err(PromoError::Rejected(format!(
+            "Promo code {} has been fully redeemed",
+            promo.code
+        )));
+    }
+
+    if promo
+        .max_uses_per_guest
+        .is_some_and(|max_uses| usage.guest_uses >= max_uses.into())
+    {
+        return Err(PromoError::Rejected(format!(
+            "You have already used promo code {}",
+            promo.code
+        )));
+    }
+
+    let discount = discount_amount(&promo, stay.room_amount);
+
+    Ok((promo, discount))
+}
+
+// Discount on the room amount, never more than the amount itself
+pub fn discount_amount(promo: &PromoCode, room_amount: &BigDecimal) -> BigDecimal {
+    let discount = match promo.discount_type.as_str() {
+        "percentage" => room_amount * &promo.discount_value / BigDecimal::from(100),
+        "fixed" => promo.discount_value.clone(),
+        _ => BigDecimal::zero(),
+    };
+
+    discount.min(room_amount.clone()).round(2).with_scale(2)
+}
+
+// Count the use of the code, inside the transaction creating the booking
+pub async fn record_redemption(
+    conn: &mut PgConnection,
+    promo: &PromoCode,
+    booking_id: i32,
+    guest_id: i32,
+    discount: &BigDecimal,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        "insert into promo_redemption (promo_code_id, booking_id, guest_id, discount_amount)
+        values ($1, $2, $3, $4)",
+        promo.id,
+        booking_id,
+        guest_id,
+        discount
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,294 @@
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use axum::{
+    body::{to_bytes, Body},
+    extract::{ConnectInfo, State},
+    http::{header, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+
+use jsonwebtoken::{decode, DecodingKey, Validation};
+
+use crate::{
+    config::RateLimitConfig, schema::TokenClaims, telemetry::record_rate_limited, AppState,
+};
+
+// Auth requests are tiny, anything bigger is not worth buffering
+const MAX_BODY_BYTES: usize = 64 * 1024;
+
+// Above this many tracked keys, expired entries are dropped on the next insert
+const PRUNE_THRESHOLD: usize = 10_000;
+
+// Fixed window request counter for one key
+struct Window {
+    started: Instant,
+    count: u32,
+}
+
+// Failed logins for one account within the failure window
+struct Failures {
+    started: Instant,
+    count: u32,
+    locked_until: Option<Instant>,
+}
+
+// In-process rate limiter for the auth endpoints, keyed by ip address and account
+#[derive(Default)]
+pub struct RateLimiter {
+    windows: Mutex<HashMap<String, Window>>,
+    failures: Mutex<HashMap<String, Failures>>,
+}
+
+impl RateLimiter {
+    // Count a request for `key`, returns how long to wait when the limit is exceeded
+    fn hit(&self, key: String, max_requests: u32, window: Duration) -> Option<Duration> {
+        let now = Instant::now();
+        let mut windows = self.windows.lock().unwrap();
+
+        if windows.len() > PRUNE_THRESHOLD {
+            windows.retain(|_, entry| now.duration_since(entry.started) < window);
+        }
+
+        let entry = windows.entry(key).or_insert(Window {
+            started: now,
+            count: 0,
+        });
+
+        // Start a new window once the previous one is over
+        if now.duration_since(entry.started) >= window {
+            entry.started = now;
+            entry.count = 0;
+        }
+
+        entry.count += 1;
+        if entry.count > max_requests {
+            return Some(window - now.duration_since(entry.started));
+        }
+
+        None
+    }
+
+    // How long the account behind `key` stays locked, if it is locked
+    fn locked_for(&self, key: &str) -> Option<Duration> {
+        let now = Instant::now();
+        let failures = self.failures.lock().unwrap();
+
+        failures
+            .get(key)
+            .and_then(|entry| entry.locked_until)
+            .filter(|locked_until| *locked_until > now)
+            .map(|locked_until| locked_until - now)
+    }
+
+    // Record a failed login, locking the account once too many failures piled up
+    fn record_failure(&self, key: String, config: &RateLimitConfig) {
+        let now = Instant::now();
+        let mut failures = self.failures.lock().unwrap();
+
+        let window = Duration::from_secs(config.login_failure_window_secs);
+
+        // Only entries whose window and lockout are both over are dropped, they count for nothing anymore
+        if failures.len() > PRUNE_THRESHOLD {
+            failures.retain(|_, entry| {
+                now.duration_since(entry.started) < window
+                    || entry.locked_until.is_some_and(|until| until > now)
+            });
+        }
+
+        let entry = failures.entry(key).or_insert(Failures {
+            started: now,
+            count: 0,
+            locked_until: None,
+        });
+
+        // Failures older than the window are forgotten
+        if now.duration_since(entry.started) >= window {
+            entry.started = now;
+            entry.count = 0;
+        }
+
+        entry.count += 1;
+        if entry.count >= config.login_max_failures {
+            entry.count = 0;
+            entry.locked_until = Some(now + Duration::from_secs(config.login_lockout_secs));
+        }
+    }
+
+    // Forget previous failures after a successful login
+    fn record_success(&self, key: &str) {
+        self.failures.lock().unwrap().remove(key);
+    }
+}
+
+// Key of the account a request is for, read from its body
+type AccountKey = fn(&AppState, &serde_json::Value) -> Option<String>;
+
+// The password step names the account by its email address
+fn email_key(_data: &AppState, body: &serde_json::Value) -> Option<String> {
+    body["email_address"]
+        .as_str()
+        .map(|email| format!("email:{}", email.to_ascii_lowercase()))
+}
+
+// The second factor step names the account by the subject of its challenge token
+fn challenge_key(data: &AppState, body: &serde_json::Value) -> Option<String> {
+    let token = body["mfa_token"].as_str()?;
+
+    decode::<TokenClaims>(
+        token,
+        &DecodingKey::from_secret(data.env.jwt_secret.as_ref()),
+        &Validation::default(),
+    )
+    .ok()
+    .filter(|token| token.claims.mfa_pending)
+    .map(|token| format!("guest:{}", token.claims.sub))
+}
+
+// Middleware limiting registration attempts per ip address and per email address
+pub async fn limit_register(
+    State(data): State<Arc<AppState>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    req: Request<Body>,
+    next: Next,
+) -> Response {
+    match check_limits(&data, addr, req, email_key).await {
+        Ok((req, _)) => next.run(req).await,
+        Err(response) => response,
+    }
+}
+
+// Middleware limiting login attempts and locking accounts after repeated failures
+pub async fn limit_login(
+    State(data): State<Arc<AppState>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    req: Request<Body>,
+    next: Next,
+) -> Response {
+    limit_failures(&data, addr, req, next, email_key).await
+}
+
+// Middleware limiting second factor attempts, the account is locked like for bad passwords
+pub async fn limit_login_mfa(
+    State(data): State<Arc<AppState>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    req: Request<Body>,
+    next: Next,
+) -> Response {
+    limit_failures(&data, addr, req, next, challenge_key).await
+}
+
+// Apply the limits and count the failed attempts of the account, locking it after too many
+async fn limit_failures(
+    data: &AppState,
+    addr: SocketAddr,
+    req: Request<Body>,
+    next: Next,
+    account_key: AccountKey,
+) -> Response {
+    let (req, key) = match check_limits(data, addr, req, account_key).await {
+        Ok(checked) => checked,
+        Err(response) => return response,
+    };
+
+    let Some(key) = key else {
+        return next.run(req).await;
+    };
+
+    if let Some(retry_after) = data.rate_limiter.locked_for(&key) {
+        tracing::warn!("login attempt for a locked account");
+        record_rate_limited("lockout");
+        return too_many_requests(
+            "Too many failed logins, the account is temporarily locked",
+            retry_after,
+        );
+    }
+
+    let response = next.run(req).await;
+
+    // The login handlers answer bad credentials with 400
+    if response.status() == StatusCode::BAD_REQUEST {
+        data.rate_limiter.record_failure(key, &data.env.rate_limit);
+    } else if response.status().is_success() {
+        data.rate_limiter.record_success(&key);
+    }
+
+    response
+}
+
+// Apply the ip and account limits, handing back the request with its body restored
+async fn check_limits(
+    data: &AppState,
+    addr: SocketAddr,
+    req: Request<Body>,
+    account_key: AccountKey,
+) -> Result<(Request<Body>, Option<String>), Response> {
+    let config = &data.env.rate_limit;
+
+    if let Some(retry_after) = data.rate_limiter.hit(
+        format!("ip:{}", addr.ip()),
+        config.ip_max_requests,
+        Duration::from_secs(config.ip_window_secs),
+    ) {
+        record_rate_limited("ip");
+        return Err(too_many_requests(
+            "Too many requests from this address, please try again later",
+            retry_after,
+        ));
+    }
+
+    // Buffer the body to find the account, then put it back for the handler
+    let (parts, body) = req.into_parts();
+    let bytes = to_bytes(body, MAX_BODY_BYTES).await.map_err(|_| {
+        let error_response = serde_json::json!({
+            "status": "fail",
+            "message": "Request body is too large",
+        });
+        (StatusCode::PAYLOAD_TOO_LARGE, Json(error_response)).into_response()
+    })?;
+
+    let key = serde_json::from_slice::<serde_json::Value>(&bytes)
+        .ok()
+        .and_then(|body| account_key(data, &body));
+
+    if let Some(key) = &key {
+        if let Some(retry_after) = data.rate_limiter.hit(
+            key.clone(),
+            config.email_max_requests,
+            Duration::from_secs(config.email_window_secs),
+        ) {
+            record_rate_limited("email");
+            return Err(too_many_requests(
+                "Too many requests for this account, please try again later",
+                retry_after,
+            ));
+        }
+    }
+
+    Ok((Request::from_parts(parts, Body::from(bytes)), key))
+}
+
+// Construct a 429 response telling the client when to retry
+fn too_many_requests(message: &str, retry_after: Duration) -> Response {
+    // Round up so clients never retry a moment too early
+    let retry_after_secs = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+
+    let error_response = serde_json::json!({
+        "status": "fail",
+        "message": message,
+        "retry_after": retry_after_secs,
+    });
+
+    (
+        StatusCode::TOO_MANY_REQUESTS,
+        [(header::RETRY_AFTER, retry_after_secs.to_string())],
+        Json(error_response),
+    )
+        .into_response()
+}
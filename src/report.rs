@@ -0,0 +1,249 @@
+use std::collections::{BTreeMap, HashMap};
+
+use bigdecimal::{BigDecimal, Zero};
+use chrono::NaiveDate;
+use sqlx::{Pool, Postgres};
+
+use crate::{
+    csv::CsvWriter,
+    currency::base_currency,
+    maintenance::OPEN_TICKET_STATUSES,
+    models::{BOOKING_STATUS_CHECKED_IN, BOOKING_STATUS_CHECKED_OUT, BOOKING_STATUS_CONFIRMED},
+    response::{KpiReport, KpiRow},
+};
+
+// Periods the KPIs can be grouped by, weeks start on monday
+pub const REPORT_PERIODS: &[&str] = &["day", "week", "month"];
+
+// Longest range of nights a report covers
+pub const MAX_REPORT_DAYS: i64 = 731;
+
+pub struct KpiQuery<'a> {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub period: &'a str,
+    pub room_type_id: Option<i32>,
+    pub by_room_type: bool,
+}
+
+// Occupancy, ADR and RevPAR of every period between `from` and `to`
+//
+// A room night is sold when a confirmed, in-house or departed booking covers
+// it. Its revenue is the room charge the night audit posted for it, or the
+// booking amount spread over the stay for nights not audited yet, so past and
+// future nights can be reported on together.
+pub async fn kpi_report(
+    db: &Pool<Postgres>,
+    query: &KpiQuery<'_>,
+) -> Result<KpiReport, sqlx::Error> {
+    let rows = sqlx::query!(
+        "with nights as (
+            select night::date as night
+            from generate_series($1::date, $2::date, interval '1 day') as night
+        ),
+        capacity as (
+            select n.night, r.room_type_id, count(*) as rooms
+            from nights n
+            join room r on r.active
+            where ($4::int is null or r.room_type_id = $4)
+                and not exists (
+                    select 1 from maintenance_ticket t
+                    where t.room_id = r.id
+                        and t.out_of_order
+                        and t.status = any($5)
+                        and t.out_of_order_from <= n.night
+                        and t.out_of_order_until > n.night
+                )
+            group by n.night, r.room_type_id
+        ),
+        sold as (
+            select
+                n.night,
+                b.room_type_id,
+                count(*) as room_nights,
+                sum(coalesce(
+                    charged.amount,
+                    b.booking_amount / (b.checkout_date - b.checkin_date)
+                )) as room_revenue
+            from nights n
+            join booking b on b.checkin_date <= n.night
+                and b.checkout_date > n.night
+                and b.status in ($6, $7, $8)
+            left join lateral (
+                select sum(l.unit_price * l.quantity) as amount
+                from folio_line l
+                where l.booking_id = b.id
+                    and l.line_type = 'room'
+                    and l.voided_at is null
+                    and l.business_date = n.night
+            ) charged on true
+            where ($4::int is null or b.room_type_id = $4)
+            group by n.night, b.room_type_id
+        ),
+        other as (
+            select l.business_date as night, b.room_type_id, sum(l.unit_price * l.quantity) as revenue
+            from folio_line l
+            join booking b on b.id = l.booking_id
+            where l.business_date between $1 and $2
+                and l.voided_at is null
+                and l.line_type not in ('room', 'tax')
+                and ($4::int is null or b.room_type_id = $4)
+            group by l.business_date, b.room_type_id
+        ),
+        keys as (
+            select night, room_type_id from capacity
+            union select night, room_type_id from sold
+            union select night, room_type_id from other
+        )
+        select
+            date_trunc($3, k.night)::date as \"period_start!\",
+            k.room_type_id,
+            coalesce(sum(c.rooms), 0)::bigint as \"rooms_available!\",
+            coalesce(sum(s.room_nights), 0)::bigint as \"room_nights_sold!\",
+            coalesce(sum(s.room_revenue), 0) as \"room_revenue!\",
+            coalesce(sum(o.revenue), 0) as \"other_revenue!\"
+        from keys k
+        left join capacity c on c.night = k.night and c.room_type_id is not distinct from k.room_type_id
+        left join sold s on s.night = k.night and s.room_type_id is not distinct from k.room_type_id
+        left join other o on o.night = k.night and o.room_type_id is not distinct from k.room_type_id
+        group by 1, 2
+        order by 1, 2",
+        query.from,
+        query.to,
+        query.period,
+        query.room_type_id,
+        OPEN_TICKET_STATUSES as &[&str],
+        BOOKING_STATUS_CONFIRMED,
+        BOOKING_STATUS_CHECKED_IN,
+        BOOKING_STATUS_CHECKED_OUT
+    )
+    .fetch_all(db)
+    .await?;
+
+    let room_types: HashMap<i32, String> = sqlx::query!("select id, name from room_type")
+        .fetch_all(db)
+        .await?
+        .into_iter()
+        .map(|room_type| (room_type.id, room_type.name))
+        .collect();
+
+    let mut totals = KpiTotals::default();
+    // Keyed by period then room type, so rows come out in order
+    let mut periods: BTreeMap<(NaiveDate, Option<i32>), KpiTotals> = BTreeMap::new();
+    for row in rows {
+        let room_type_id = if query.by_room_type {
+            row.room_type_id
+        } else {
+            query.room_type_id
+        };
+        let period = periods.entry((row.period_start, room_type_id)).or_default();
+
+        for sums in [period, &mut totals] {
+            sums.rooms_available += row.rooms_available;
+            sums.room_nights_sold += row.room_nights_sold;
+            sums.room_revenue += &row.room_revenue;
+            sums.other_revenue += &row.other_revenue;
+        }
+    }
+
+    let rows: Vec<KpiRow> = periods
+        .into_iter()
+        .map(|((period_start, room_type_id), sums)| {
+            let room_type = room_type_id.and_then(|id| room_types.get(&id).cloned());
+            sums.into_row(period_start, room_type_id, room_type)
+        })
+        .collect();
+
+    Ok(KpiReport {
+        from: query.from,
+        to: query.to,
+        period: query.period.to_string(),
+        currency: base_currency(db).await?,
+        rows,
+        totals: totals.into_row(
+            query.from,
+            query.room_type_id,
+            query
+                .room_type_id
+                .and_then(|id| room_types.get(&id).cloned()),
+        ),
+    })
+}
+
+#[derive(Default)]
+struct KpiTotals {
+    rooms_available: i64,
+    room_nights_sold: i64,
+    room_revenue: BigDecimal,
+    other_revenue: BigDecimal,
+}
+
+impl KpiTotals {
+    fn into_row(
+        self,
+        period_start: NaiveDate,
+        room_type_id: Option<i32>,
+        room_type: Option<String>,
+    ) -> KpiRow {
+        let available = BigDecimal::from(self.rooms_available);
+        let sold = BigDecimal::from(self.room_nights_sold);
+
+        KpiRow {
+            period_start,
+            room_type_id,
+            room_type,
+            rooms_available: self.rooms_available,
+            room_nights_sold: self.room_nights_sold,
+            occupancy_percent: ratio(&(&sold * BigDecimal::from(100)), &available),
+            adr: ratio(&self.room_revenue, &sold),
+            revpar: ratio(&self.room_revenue, &available),
+            total_revenue: (&self.room_revenue + &self.other_revenue).with_scale(2),
+            room_revenue: self.room_revenue.with_scale(2),
+            other_revenue: self.other_revenue.with_scale(2),
+        }
+    }
+}
+
+// Rounded to cents, zero when nothing could be sold
+fn ratio(numerator: &BigDecimal, denominator: &BigDecimal) -> BigDecimal {
+    if denominator.is_zero() {
+        return BigDecimal::zero().with_scale(2);
+    }
+
+    (numerator / denominator).round(2).with_scale(2)
+}
+
+// Render the report rows, the totals are left to the spreadsheet
+pub fn kpi_csv(report: &KpiReport) -> String {
+    let mut csv = CsvWriter::new(&[
+        "period_start",
+        "room_type",
+        "rooms_available",
+        "room_nights_sold",
+        "occupancy_percent",
+        "adr",
+        "revpar",
+        "room_revenue",
+        "other_revenue",
+        "total_revenue",
+        "currency",
+    ]);
+
+    for row in &report.rows {
+        csv.record(&[
+            row.period_start.to_string(),
+            row.room_type.clone().unwrap_or_default(),
+            row.rooms_available.to_string(),
+            row.room_nights_sold.to_string(),
+            row.occupancy_percent.to_string(),
+            row.adr.to_string(),
+            row.revpar.to_string(),
+            row.room_revenue.to_string(),
+            row.other_revenue.to_string(),
+            row.total_revenue.to_string(),
+            report.currency.clone(),
+        ]);
+    }
+
+    csv.finish()
+}
@@ -1,6 +1,12 @@
+use bigdecimal::BigDecimal;
 use chrono::prelude::*;
 use serde::Serialize;
 
+use crate::{
+    models::{AllotmentBlock, FolioLine, Invoice, InvoiceLine, Payment, Property, Room},
+    tax::TaxLine,
+};
+
 #[allow(non_snake_case)]
 #[derive(Debug, Serialize)]
 pub struct FilteredGuest {
@@ -10,6 +16,9 @@ pub struct FilteredGuest {
     pub email_address: String,
     pub verified: bool,
     pub phone_number: String,
+    pub role: String,
+    pub mfa_enabled: bool,
+    pub preferred_currency: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -24,3 +33,218 @@ pub struct GuestResponse {
     pub status: String,
     pub data: GuestData,
 }
+
+// Charges and payments of a booking with the running balance
+#[derive(Serialize, Debug)]
+pub struct Folio {
+    pub booking_id: i32,
+    pub currency: String,
+    pub lines: Vec<FolioLine>,
+    pub payments: Vec<Payment>,
+    pub net_total: BigDecimal,
+    pub tax_total: BigDecimal,
+    pub charges_total: BigDecimal,
+    pub payments_total: BigDecimal,
+    pub balance: BigDecimal,
+}
+
+// What is left to pay on one booking of a reservation group
+#[derive(Serialize, Debug)]
+pub struct GroupBookingBalance {
+    pub booking_id: i32,
+    pub guest_id: i32,
+    pub status: String,
+    pub balance: BigDecimal,
+}
+
+// Balances of every booking of a reservation group, and the part the lead guest pays
+#[derive(Serialize, Debug)]
+pub struct GroupBilling {
+    pub billing_type: String,
+    pub bookings: Vec<GroupBookingBalance>,
+    pub balance_total: BigDecimal,
+    pub lead_balance_due: BigDecimal,
+}
+
+// One room on the housekeeping status board
+#[derive(Serialize, Debug)]
+pub struct RoomBoardEntry {
+    pub room_id: i32,
+    pub room_number: String,
+    pub floor: i32,
+    pub room_type: String,
+    pub housekeeping_status: String,
+    pub status_updated_at: Option<DateTime<Utc>>,
+    // Booking of the guest staying in the room
+    pub booking_id: Option<i32>,
+    pub open_tasks: i64,
+}
+
+// One booking on a front desk list
+#[derive(Serialize, Debug)]
+pub struct FrontDeskEntry {
+    pub booking_id: i32,
+    pub status: String,
+    pub guest_name: String,
+    pub email_address: String,
+    pub phone_number: String,
+    pub room_number: Option<String>,
+    pub room_type: Option<String>,
+    pub checkin_date: NaiveDate,
+    pub checkout_date: NaiveDate,
+    pub num_adults: i32,
+    pub num_children: i32,
+    pub balance_due: BigDecimal,
+    pub currency: String,
+    pub special_requests: Option<String>,
+}
+
+// A room free for the whole stay of a booking, ranked against the guest preferences
+#[derive(Serialize, Debug)]
+pub struct RoomSuggestion {
+    pub room: Room,
+    // Clean or inspected, the guest can be checked in right away
+    pub ready: bool,
+    pub score: i32,
+    // Why the room ranks where it does
+    pub reasons: Vec<&'static str>,
+}
+
+// Rooms of an allotment block picked up for one night
+#[derive(Serialize, Debug)]
+pub struct BlockNightPickup {
+    pub night: NaiveDate,
+    pub held: i32,
+    pub picked_up: i64,
+    pub remaining: i64,
+}
+
+// How much of an allotment block has been picked up
+#[derive(Serialize, Debug)]
+pub struct BlockPickup {
+    pub block: AllotmentBlock,
+    pub nights: Vec<BlockNightPickup>,
+    pub room_nights_held: i64,
+    pub room_nights_picked_up: i64,
+    pub pickup_percent: i32,
+}
+
+// Occupancy and revenue of one period, for one room type or every room type together
+#[derive(Serialize, Debug, Clone)]
+pub struct KpiRow {
+    pub period_start: NaiveDate,
+    pub room_type_id: Option<i32>,
+    pub room_type: Option<String>,
+    // Room nights that could be sold, rooms out of order aside
+    pub rooms_available: i64,
+    pub room_nights_sold: i64,
+    pub occupancy_percent: BigDecimal,
+    // Average daily rate, room revenue per room night sold
+    pub adr: BigDecimal,
+    // Room revenue per room night available
+    pub revpar: BigDecimal,
+    pub room_revenue: BigDecimal,
+    pub other_revenue: BigDecimal,
+    pub total_revenue: BigDecimal,
+}
+
+#[derive(Serialize, Debug)]
+pub struct KpiReport {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub period: String,
+    pub currency: String,
+    pub rows: Vec<KpiRow>,
+    pub totals: KpiRow,
+}
+
+// Bookings on the books for a stay date, and where it is expected to end
+#[derive(Serialize, Debug)]
+pub struct PickupRow {
+    pub stay_date: NaiveDate,
+    // Days left until the stay date, counted from the business date
+    pub lead_days: i32,
+    pub capacity: i64,
+    pub on_the_books: i64,
+    // Room nights booked over the pickup window
+    pub pickup: i64,
+    // Same weekday a year before
+    pub last_year_stay_date: NaiveDate,
+    pub last_year_on_the_books: i64,
+    pub last_year_final: i64,
+    pub forecast_room_nights: i64,
+    pub forecast_occupancy_percent: BigDecimal,
+}
+
+// Room nights on the books for the stay date some days before it, this year and last year
+#[derive(Serialize, Debug)]
+pub struct PacePoint {
+    pub days_before: i32,
+    pub on_the_books: i64,
+    pub last_year_on_the_books: i64,
+}
+
+#[derive(Serialize, Debug)]
+pub struct PaceReport {
+    pub forecast: PickupRow,
+    // Furthest from the stay date first
+    pub pace: Vec<PacePoint>,
+}
+
+// Taxable and tax amounts of one tax, `net_amount` is the amount the tax was computed on
+#[derive(Serialize, Debug)]
+pub struct TaxBreakdown {
+    pub category: String,
+    pub net_amount: BigDecimal,
+    pub tax_amount: BigDecimal,
+}
+
+// Everything printed on an invoice
+#[derive(Serialize, Debug)]
+pub struct InvoiceDocument {
+    pub invoice: Invoice,
+    pub lines: Vec<InvoiceLine>,
+    pub property: Property,
+    pub tax_breakdown: Vec<TaxBreakdown>,
+}
+
+// Price of a stay split into the room charge and the taxes added to it
+#[derive(Serialize, Debug)]
+pub struct Quote {
+    pub currency: String,
+    // Set when the quote was converted from the property currency
+    pub exchange_rate: Option<BigDecimal>,
+    pub nights: i32,
+    pub net_amount: BigDecimal,
+    pub taxes: Vec<TaxLine>,
+    pub tax_total: BigDecimal,
+    pub total: BigDecimal,
+}
+
+// Change one pricing rule made to the rate of a night
+#[derive(Serialize, Debug, Clone)]
+pub struct RateAdjustment {
+    pub pricing_rule_id: i32,
+    pub name: String,
+    pub adjustment_percent: BigDecimal,
+    pub rate_before: BigDecimal,
+    pub rate_after: BigDecimal,
+}
+
+// Rate of one night of a stay, the base rate of the room type moved by the pricing rules
+#[derive(Serialize, Debug, Clone)]
+pub struct NightlyRate {
+    pub night: NaiveDate,
+    pub occupancy_percent: BigDecimal,
+    pub base_rate: BigDecimal,
+    pub rate: BigDecimal,
+    pub adjustments: Vec<RateAdjustment>,
+}
+
+// Restriction a stay doesn't meet, `rule` is the column of the restriction that was broken
+#[derive(Serialize, Debug, Clone)]
+pub struct RestrictionViolation {
+    pub stay_restriction_id: i32,
+    pub rule: &'static str,
+    pub message: String,
+}
@@ -0,0 +1,110 @@
+use chrono::{Days, NaiveDate};
+use sqlx::PgExecutor;
+
+use crate::{models::StayRestriction, response::RestrictionViolation};
+
+// Check a stay against the restrictions set on its dates, for the room type or for every room type
+// Stays without a room type are only held to the restrictions set for every room type
+pub async fn stay_restriction_violations<'e>(
+    executor: impl PgExecutor<'e>,
+    room_type_id: Option<i32>,
+    checkin_date: NaiveDate,
+    checkout_date: NaiveDate,
+) -> Result<Vec<RestrictionViolation>, sqlx::Error> {
+    let restrictions = sqlx::query_as!(
+        StayRestriction,
+        "select * from stay_restriction
+        where (room_type_id is null or room_type_id = $1)
+            and start_date <= $3
+            and end_date >= $2
+        order by start_date, id",
+        room_type_id,
+        checkin_date,
+        checkout_date
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(restrictions
+        .iter()
+        .flat_map(|restriction| check_restriction(restriction, checkin_date, checkout_date))
+        .collect())
+}
+
+// Every rule of the restriction the stay breaks
+fn check_restriction(
+    restriction: &StayRestriction,
+    checkin_date: NaiveDate,
+    checkout_date: NaiveDate,
+) -> Vec<RestrictionViolation> {
+    let covers = |date: NaiveDate| restriction.start_date <= date && date <= restriction.end_date;
+    let nights = (checkout_date - checkin_date).num_days();
+    let violation = |rule, message| RestrictionViolation {
+        stay_restriction_id: restriction.id,
+        rule,
+        message,
+    };
+
+    let mut violations = Vec::new();
+
+    // Length of stay is set by the arrival date
+    if covers(checkin_date) {
+        if let Some(min_stay) = restriction.min_stay {
+            if nights < i64::from(min_stay) {
+                violations.push(violation(
+                    "min_stay",
+                    format!(
+                        "Stays arriving on {} must be at least {} nights",
+                        checkin_date, min_stay
+                    ),
+                ));
+            }
+        }
+
+        if let Some(max_stay) = restriction.max_stay {
+            if nights > i64::from(max_stay) {
+                violations.push(violation(
+                    "max_stay",
+                    format!(
+                        "Stays arriving on {} can be at most {} nights",
+                        checkin_date, max_stay
+                    ),
+                ));
+            }
+        }
+
+        if restriction.closed_to_arrival {
+            violations.push(violation(
+                "closed_to_arrival",
+                format!("Arrivals are closed on {}", checkin_date),
+            ));
+        }
+    }
+
+    if restriction.closed_to_departure && covers(checkout_date) {
+        violations.push(violation(
+            "closed_to_departure",
+            format!("Departures are closed on {}", checkout_date),
+        ));
+    }
+
+    if restriction.stop_sell {
+        let first_night = checkin_date.max(restriction.start_date);
+        let last_night = checkout_date
+            .checked_sub_days(Days::new(1))
+            .map_or(restriction.end_date, |night| {
+                night.min(restriction.end_date)
+            });
+        if first_night <= last_night {
+            violations.push(violation(
+                "stop_sell",
+                format!(
+                    "Sales are stopped for the nights from {} to {}",
+                    first_night, last_night
+                ),
+            ));
+        }
+    }
+
+    violations
+}
@@ -0,0 +1,261 @@
+use sqlx::PgConnection;
+
+use crate::{
+    business_date::current_business_date,
+    housekeeping::{ensure_room_ready, RoomError, READY_STATUSES},
+    maintenance::OPEN_TICKET_STATUSES,
+    models::{
+        Booking, Room, BOOKING_STATUS_CHECKED_IN, BOOKING_STATUS_CHECKED_OUT,
+        BOOKING_STATUS_CONFIRMED, BOOKING_STATUS_PENDING,
+    },
+    response::RoomSuggestion,
+};
+
+// Weights of what makes a room a better fit, staying in the same room matters the most
+const SCORE_SAME_ROOM: i32 = 8;
+const SCORE_PREFERRED_FLOOR: i32 = 4;
+const SCORE_READY: i32 = 3;
+const SCORE_NEAR_ELEVATOR: i32 = 2;
+// Accessible rooms are kept for the guests who need them
+const SCORE_ACCESSIBLE_NOT_NEEDED: i32 = -1;
+
+// Rooms of the type booked that no other booking or maintenance holds during the stay, locked until the transaction ends
+async fn free_rooms(
+    conn: &mut PgConnection,
+    booking: &Booking,
+    room_type_id: i32,
+) -> Result<Vec<Room>, sqlx::Error> {
+    sqlx::query_as!(
+        Room,
+        "select r.* from room r
+        where r.active
+            and r.room_type_id = $1
+            and not exists (
+                select 1 from booking b
+                where b.room_id = r.id
+                    and b.id <> $2
+                    and b.status in ($3, $4, $5)
+                    and b.checkin_date < $7
+                    -- Guests staying past their check-out date still hold the room
+                    and greatest(b.checkout_date, case when b.status = $3 then (select business_date from business_date) + 1 end) > $6
+            )
+            and not exists (
+                select 1 from maintenance_ticket t
+                where t.room_id = r.id
+                    and t.out_of_order
+                    and t.status = any($8)
+                    and t.out_of_order_from < $7
+                    and t.out_of_order_until > $6
+            )
+        order by r.room_number
+        for update of r",
+        room_type_id,
+        booking.id,
+        BOOKING_STATUS_CHECKED_IN,
+        BOOKING_STATUS_PENDING,
+        BOOKING_STATUS_CONFIRMED,
+        booking.checkin_date,
+        booking.checkout_date,
+        OPEN_TICKET_STATUSES as &[&str]
+    )
+    .fetch_all(conn)
+    .await
+}
+
+// Rooms of the guest's stays right before and after this one, keeping the room saves a move
+async fn adjoining_rooms(
+    conn: &mut PgConnection,
+    booking: &Booking,
+) -> Result<Vec<i32>, sqlx::Error> {
+    sqlx::query_scalar!(
+        "select room_id as \"room_id!\" from booking
+        where guest_id = $1
+            and id <> $2
+            and room_id is not null
+            and status in ($3, $4, $5, $6)
+            and (checkout_date = $7 or checkin_date = $8)",
+        booking.guest_id,
+        booking.id,
+        BOOKING_STATUS_PENDING,
+        BOOKING_STATUS_CONFIRMED,
+        BOOKING_STATUS_CHECKED_IN,
+        BOOKING_STATUS_CHECKED_OUT,
+        booking.checkin_date,
+        booking.checkout_date
+    )
+    .fetch_all(conn)
+    .await
+}
+
+// Rank the rooms the booking can be given for its whole stay, best first
+pub async fn suggest_rooms(
+    conn: &mut PgConnection,
+    booking: &Booking,
+) -> Result<Vec<RoomSuggestion>, sqlx::Error> {
+    let Some(room_type_id) = booking.room_type_id else {
+        return Ok(Vec::new());
+    };
+
+    let adjoining = adjoining_rooms(&mut *conn, booking).await?;
+    let arriving = booking.checkin_date <= current_business_date(&mut *conn).await?;
+
+    let mut suggestions: Vec<RoomSuggestion> = free_rooms(conn, booking, room_type_id)
+        .await?
+        .into_iter()
+        // Guests needing an accessible room are only offered one
+        .filter(|room| room.accessible || !booking.accessible_room)
+        .map(|room| {
+            let ready = READY_STATUSES.contains(&room.housekeeping_status.as_str());
+            let mut score = 0;
+            let mut reasons = Vec::new();
+
+            if adjoining.contains(&room.id) {
+                score += SCORE_SAME_ROOM;
+                reasons.push("same room as the adjoining stay");
+            }
+            if booking.preferred_floor == Some(room.floor) {
+                score += SCORE_PREFERRED_FLOOR;
+                reasons.push("preferred floor");
+            }
+            // Only rooms ready now make a difference to a guest arriving today
+            if ready && arriving {
+                score += SCORE_READY;
+                reasons.push("ready for check-in");
+            }
+            if booking.near_elevator && room.near_elevator {
+                score += SCORE_NEAR_ELEVATOR;
+                reasons.push("near elevator");
+            }
+            if room.accessible && booking.accessible_room {
+                reasons.push("accessible");
+            }
+            if room.accessible && !booking.accessible_room {
+                score += SCORE_ACCESSIBLE_NOT_NEEDED;
+            }
+
+            RoomSuggestion {
+                room,
+                ready,
+                score,
+                reasons,
+            }
+        })
+        .collect();
+
+    // Stable sort, rooms scoring the same stay in room number order
+    suggestions.sort_by_key(|suggestion| std::cmp::Reverse(suggestion.score));
+
+    Ok(suggestions)
+}
+
+// Pick the room to give the booking, `room_id` overrides the suggestion and rooms for a check-in must be ready
+pub async fn assign_room(
+    conn: &mut PgConnection,
+    booking: &Booking,
+    room_id: Option<i32>,
+    check_in: bool,
+) -> Result<Room, RoomError> {
+    if let Some(room_id) = room_id {
+        return check_room(conn, booking, room_id, check_in).await;
+    }
+
+    // Keep the room given before arrival unless it was taken out of order or isn't ready
+    if let Some(room_id) = booking
+        .room_id
+        .filter(|_| booking.maintenance_ticket_id.is_none())
+    {
+        match check_room(&mut *conn, booking, room_id, check_in).await {
+            Ok(room) => return Ok(room),
+            Err(RoomError::Rejected(_)) => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    if booking.room_type_id.is_none() {
+        return Err(RoomError::Rejected(format!(
+            "Booking with ID: {} has no room type, a room must be picked",
+            booking.id
+        )));
+    }
+
+    let suggestions = suggest_rooms(&mut *conn, booking).await?;
+    for suggestion in suggestions {
+        if !check_in {
+            return Ok(suggestion.room);
+        }
+
+        // A guest staying on past their dates still holds the room
+        match ensure_room_ready(&mut *conn, suggestion.room.id, booking).await {
+            Ok(room) => return Ok(room),
+            Err(RoomError::Rejected(_)) => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    Err(RoomError::Rejected(format!(
+        "No {}room of the type booked is free from {} to {}",
+        if check_in { "ready " } else { "" },
+        booking.checkin_date,
+        booking.checkout_date
+    )))
+}
+
+// A room picked by the front desk must be free for the whole stay, preferences aside
+async fn check_room(
+    conn: &mut PgConnection,
+    booking: &Booking,
+    room_id: i32,
+    check_in: bool,
+) -> Result<Room, RoomError> {
+    let room = if check_in {
+        ensure_room_ready(&mut *conn, room_id, booking).await?
+    } else {
+        sqlx::query_as!(Room, "select * from room where id = $1", room_id)
+            .fetch_optional(&mut *conn)
+            .await?
+            .filter(|room| room.active)
+            .ok_or_else(|| RoomError::Rejected(format!("Room with ID: {} not found", room_id)))?
+    };
+
+    if booking
+        .room_type_id
+        .is_some_and(|room_type_id| room_type_id != room.room_type_id)
+    {
+        return Err(RoomError::Rejected(format!(
+            "Room {} isn't of the room type booked",
+            room.room_number
+        )));
+    }
+
+    let room_type_id = booking.room_type_id.unwrap_or(room.room_type_id);
+    let free = free_rooms(conn, booking, room_type_id)
+        .await?
+        .iter()
+        .any(|free| free.id == room.id);
+
+    if !free {
+        return Err(RoomError::Rejected(format!(
+            "Room {} isn't free from {} to {}",
+            room.room_number, booking.checkin_date, booking.checkout_date
+        )));
+    }
+
+    Ok(room)
+}
+
+// Whether the room given to the booking is still free after its dates moved
+pub async fn room_still_free(
+    conn: &mut PgConnection,
+    booking: &Booking,
+) -> Result<bool, sqlx::Error> {
+    let (Some(room_id), Some(room_type_id)) = (booking.room_id, booking.room_type_id) else {
+        return Ok(true);
+    };
+
+    let free = free_rooms(conn, booking, room_type_id)
+        .await?
+        .iter()
+        .any(|free| free.id == room_id);
+
+    Ok(free)
+}
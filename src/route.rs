@@ -2,17 +2,53 @@ use std::sync::Arc;
 
 use axum::{
     middleware,
-    routing::{get, post},
+    routing::{delete, get, patch, post, put},
     Router,
 };
+use tower_http::{
+    request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer},
+    trace::TraceLayer,
+};
 
 use crate::{
     handlers::{
-        booking_list_handler, create_booking_handler, delete_booking_handler, get_booking_handler,
-        get_me_handler, handler_404, health_check_handler, login_guest_handler, logout_handle,
-        register_guest_handler, update_booking_handler,
+        add_payment_method_handler, allotment_pickup_handler, assign_room_handler,
+        availability_handler, booking_list_handler, booking_quote_handler, business_date_handler,
+        cancel_group_handler, check_in_handler, check_out_handler, confirm_mfa_handler,
+        create_allotment_block_handler, create_booking_handler, create_group_handler,
+        create_housekeeping_task_handler, create_maintenance_ticket_handler,
+        create_pricing_rule_handler, create_promo_code_handler, create_rate_plan_handler,
+        create_room_handler, create_room_type_handler, create_stay_restriction_handler,
+        create_tax_rule_handler, delete_booking_handler, delete_exchange_rate_handler,
+        delete_payment_method_handler, delete_stay_restriction_handler,
+        download_maintenance_attachment_handler, enroll_mfa_handler, front_desk_list_handler,
+        get_booking_handler, get_folio_handler, get_group_handler, get_invoice_handler,
+        get_loyalty_handler, get_maintenance_ticket_handler, get_me_handler, group_list_handler,
+        handler_404, health_check_handler, join_waitlist_handler, kpi_report_handler,
+        leave_waitlist_handler, list_allotment_blocks_handler, list_exchange_rates_handler,
+        list_gift_vouchers_handler, list_housekeeping_tasks_handler,
+        list_maintenance_tickets_handler, list_payment_methods_handler,
+        list_price_adjustments_handler, list_pricing_rules_handler, list_promo_codes_handler,
+        list_rate_plans_handler, list_rooms_handler, list_stay_restrictions_handler,
+        list_tax_rules_handler, login_guest_handler, login_mfa_handler, logout_handle,
+        metrics_handler, my_housekeeping_tasks_handler, my_waitlist_handler,
+        night_audit_list_handler, notification_list_handler, pace_report_handler,
+        pickup_report_handler, post_charge_handler, put_exchange_rate_handler,
+        rate_plan_list_handler, readiness_handler, record_payment_handler, register_guest_handler,
+        release_allotment_block_handler, room_board_handler, room_cleaned_handler,
+        room_inspected_handler, room_status_history_handler, room_suggestions_handler,
+        room_type_list_handler, run_night_audit_handler, set_room_status_handler,
+        staff_get_folio_handler, staff_get_gift_voucher_handler, staff_get_invoice_handler,
+        staff_issue_invoice_handler, staff_sell_gift_voucher_handler, update_booking_handler,
+        update_group_handler, update_housekeeping_task_handler, update_maintenance_ticket_handler,
+        update_me_handler, update_pricing_rule_handler, update_promo_code_handler,
+        update_rate_plan_handler, update_room_handler, update_room_type_handler,
+        update_stay_restriction_handler, update_tax_rule_handler,
+        upload_maintenance_attachment_handler, void_charge_handler, waitlist_handler,
     },
-    jwt_auth::auth,
+    jwt_auth::{auth, require_admin, require_housekeeping, require_mfa, require_staff},
+    rate_limit::{limit_login, limit_login_mfa, limit_register},
+    telemetry::{echo_request_id, log_response, make_request_span, track_metrics},
     AppState,
 };
 
@@ -20,8 +56,39 @@ use crate::{
 pub fn create_router(app_state: Arc<AppState>) -> Router {
     Router::new()
         .route("/api/healthchecker", get(health_check_handler))
-        .route("/v1/api/auth/register", post(register_guest_handler))
-        .route("/v1/api/auth/login", post(login_guest_handler))
+        .route("/health/live", get(health_check_handler))
+        .route("/health/ready", get(readiness_handler))
+        .route(
+            "/v1/api/auth/register",
+            post(register_guest_handler).route_layer(middleware::from_fn_with_state(
+                app_state.clone(),
+                limit_register,
+            )),
+        )
+        .route(
+            "/v1/api/auth/login",
+            post(login_guest_handler).route_layer(middleware::from_fn_with_state(
+                app_state.clone(),
+                limit_login,
+            )),
+        )
+        .route(
+            "/v1/api/auth/login/mfa",
+            post(login_mfa_handler).route_layer(middleware::from_fn_with_state(
+                app_state.clone(),
+                limit_login_mfa,
+            )),
+        )
+        .route(
+            "/v1/api/auth/mfa/enroll",
+            post(enroll_mfa_handler)
+                .route_layer(middleware::from_fn_with_state(app_state.clone(), auth)),
+        )
+        .route(
+            "/v1/api/auth/mfa/confirm",
+            post(confirm_mfa_handler)
+                .route_layer(middleware::from_fn_with_state(app_state.clone(), auth)),
+        )
         .route(
             "/v1/api/auth/logout",
             get(logout_handle).route_layer(middleware::from_fn_with_state(app_state.clone(), auth)),
@@ -29,6 +96,25 @@ pub fn create_router(app_state: Arc<AppState>) -> Router {
         .route(
             "/v1/api/guests/me",
             get(get_me_handler)
+                .patch(update_me_handler)
+                .route_layer(middleware::from_fn_with_state(app_state.clone(), auth)),
+        )
+        .route(
+            "/v1/api/guests/me/payment-methods",
+            get(list_payment_methods_handler)
+                .post(add_payment_method_handler)
+                .route_layer(middleware::from_fn(require_mfa))
+                .route_layer(middleware::from_fn_with_state(app_state.clone(), auth)),
+        )
+        .route(
+            "/v1/api/guests/me/payment-methods/:id",
+            delete(delete_payment_method_handler)
+                .route_layer(middleware::from_fn(require_mfa))
+                .route_layer(middleware::from_fn_with_state(app_state.clone(), auth)),
+        )
+        .route(
+            "/v1/api/guests/me/loyalty",
+            get(get_loyalty_handler)
                 .route_layer(middleware::from_fn_with_state(app_state.clone(), auth)),
         )
         .route(
@@ -36,6 +122,55 @@ pub fn create_router(app_state: Arc<AppState>) -> Router {
             get(booking_list_handler)
                 .route_layer(middleware::from_fn_with_state(app_state.clone(), auth)),
         )
+        .route(
+            "/v1/api/guest/room-types",
+            get(room_type_list_handler)
+                .route_layer(middleware::from_fn_with_state(app_state.clone(), auth)),
+        )
+        .route(
+            "/v1/api/guest/availability",
+            get(availability_handler)
+                .route_layer(middleware::from_fn_with_state(app_state.clone(), auth)),
+        )
+        .route(
+            "/v1/api/guest/waitlist",
+            get(my_waitlist_handler)
+                .post(join_waitlist_handler)
+                .route_layer(middleware::from_fn_with_state(app_state.clone(), auth)),
+        )
+        .route(
+            "/v1/api/guest/waitlist/:id",
+            delete(leave_waitlist_handler)
+                .route_layer(middleware::from_fn_with_state(app_state.clone(), auth)),
+        )
+        .route(
+            "/v1/api/guest/notifications",
+            get(notification_list_handler)
+                .route_layer(middleware::from_fn_with_state(app_state.clone(), auth)),
+        )
+        .route(
+            "/v1/api/guest/rate-plans",
+            get(rate_plan_list_handler)
+                .route_layer(middleware::from_fn_with_state(app_state.clone(), auth)),
+        )
+        .route(
+            "/v1/api/guest/gift-vouchers",
+            get(list_gift_vouchers_handler)
+                .route_layer(middleware::from_fn_with_state(app_state.clone(), auth)),
+        )
+        .route(
+            "/v1/api/guest/groups",
+            get(group_list_handler)
+                .post(create_group_handler)
+                .route_layer(middleware::from_fn_with_state(app_state.clone(), auth)),
+        )
+        .route(
+            "/v1/api/guest/groups/:id",
+            get(get_group_handler)
+                .patch(update_group_handler)
+                .delete(cancel_group_handler)
+                .route_layer(middleware::from_fn_with_state(app_state.clone(), auth)),
+        )
         .route(
             "/v1/api/guest/booking/create",
             post(create_booking_handler)
@@ -48,6 +183,227 @@ pub fn create_router(app_state: Arc<AppState>) -> Router {
                 .delete(delete_booking_handler)
                 .route_layer(middleware::from_fn_with_state(app_state.clone(), auth)),
         )
-        .with_state(app_state)
+        .route(
+            "/v1/api/guest/booking/:id/quote",
+            get(booking_quote_handler)
+                .route_layer(middleware::from_fn_with_state(app_state.clone(), auth)),
+        )
+        .route(
+            "/v1/api/guest/booking/:id/folio",
+            get(get_folio_handler)
+                .route_layer(middleware::from_fn_with_state(app_state.clone(), auth)),
+        )
+        .route(
+            "/v1/api/guest/booking/:id/invoice",
+            get(get_invoice_handler)
+                .route_layer(middleware::from_fn_with_state(app_state.clone(), auth)),
+        )
+        .merge(staff_router(app_state.clone()))
+        .merge(housekeeping_router(app_state.clone()))
+        .merge(admin_router(app_state.clone()))
         .fallback(handler_404)
+        .route_layer(middleware::from_fn(track_metrics))
+        .layer(middleware::from_fn(echo_request_id))
+        .layer(
+            TraceLayer::new_for_http()
+                .make_span_with(make_request_span)
+                .on_response(log_response),
+        )
+        .layer(PropagateRequestIdLayer::x_request_id())
+        .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
+        .with_state(app_state)
+}
+
+// Construct the router for the staff endpoints, every path needs a staff account
+fn staff_router(app_state: Arc<AppState>) -> Router<Arc<AppState>> {
+    Router::new()
+        .route(
+            "/v1/api/staff/front-desk/:list",
+            get(front_desk_list_handler),
+        )
+        .route("/v1/api/staff/business-date", get(business_date_handler))
+        .route("/v1/api/staff/waitlist", get(waitlist_handler))
+        .route(
+            "/v1/api/staff/night-audits",
+            get(night_audit_list_handler).post(run_night_audit_handler),
+        )
+        .route("/v1/api/staff/booking/:id/check-in", post(check_in_handler))
+        .route(
+            "/v1/api/staff/booking/:id/room-suggestions",
+            get(room_suggestions_handler),
+        )
+        .route("/v1/api/staff/booking/:id/room", put(assign_room_handler))
+        .route(
+            "/v1/api/staff/booking/:id/check-out",
+            post(check_out_handler),
+        )
+        .route(
+            "/v1/api/staff/booking/:id/folio",
+            get(staff_get_folio_handler),
+        )
+        .route(
+            "/v1/api/staff/booking/:id/folio/charges",
+            post(post_charge_handler),
+        )
+        .route(
+            "/v1/api/staff/booking/:id/folio/charges/:line_id/void",
+            post(void_charge_handler),
+        )
+        .route(
+            "/v1/api/staff/booking/:id/invoice",
+            get(staff_get_invoice_handler).post(staff_issue_invoice_handler),
+        )
+        .route(
+            "/v1/api/staff/booking/:id/payments",
+            post(record_payment_handler),
+        )
+        .route(
+            "/v1/api/staff/gift-vouchers",
+            post(staff_sell_gift_voucher_handler),
+        )
+        .route(
+            "/v1/api/staff/gift-vouchers/:code",
+            get(staff_get_gift_voucher_handler),
+        )
+        .route(
+            "/v1/api/staff/rooms/:id/status",
+            put(set_room_status_handler),
+        )
+        .route(
+            "/v1/api/staff/rooms/:id/history",
+            get(room_status_history_handler),
+        )
+        .route(
+            "/v1/api/staff/maintenance/tickets",
+            get(list_maintenance_tickets_handler).post(create_maintenance_ticket_handler),
+        )
+        .route(
+            "/v1/api/staff/maintenance/tickets/:id",
+            get(get_maintenance_ticket_handler).patch(update_maintenance_ticket_handler),
+        )
+        .route(
+            "/v1/api/staff/maintenance/tickets/:id/attachments",
+            post(upload_maintenance_attachment_handler),
+        )
+        .route(
+            "/v1/api/staff/maintenance/tickets/:id/attachments/:attachment_id",
+            get(download_maintenance_attachment_handler),
+        )
+        .route(
+            "/v1/api/staff/housekeeping/tasks",
+            get(list_housekeeping_tasks_handler).post(create_housekeeping_task_handler),
+        )
+        .route(
+            "/v1/api/staff/housekeeping/tasks/:id",
+            patch(update_housekeeping_task_handler),
+        )
+        .route_layer(middleware::from_fn(require_staff))
+        .route_layer(middleware::from_fn_with_state(app_state, auth))
+}
+
+// Construct the router for the housekeeping endpoints, used by housekeepers from their phones
+fn housekeeping_router(app_state: Arc<AppState>) -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/v1/api/housekeeping/rooms", get(room_board_handler))
+        .route(
+            "/v1/api/housekeeping/rooms/:id/cleaned",
+            post(room_cleaned_handler),
+        )
+        .route(
+            "/v1/api/housekeeping/rooms/:id/inspected",
+            post(room_inspected_handler),
+        )
+        .route(
+            "/v1/api/housekeeping/tasks",
+            get(my_housekeeping_tasks_handler),
+        )
+        .route_layer(middleware::from_fn(require_housekeeping))
+        .route_layer(middleware::from_fn_with_state(app_state, auth))
+}
+
+// Construct the router for the admin endpoints, every path needs an admin account
+fn admin_router(app_state: Arc<AppState>) -> Router<Arc<AppState>> {
+    Router::new()
+        // Booking revenue and cancellations are only for admins to see
+        .route("/metrics", get(metrics_handler))
+        .route(
+            "/v1/api/admin/tax-rules",
+            get(list_tax_rules_handler).post(create_tax_rule_handler),
+        )
+        .route(
+            "/v1/api/admin/tax-rules/:id",
+            patch(update_tax_rule_handler),
+        )
+        .route("/v1/api/admin/room-types", post(create_room_type_handler))
+        .route(
+            "/v1/api/admin/room-types/:id",
+            patch(update_room_type_handler),
+        )
+        .route(
+            "/v1/api/admin/promo-codes",
+            get(list_promo_codes_handler).post(create_promo_code_handler),
+        )
+        .route(
+            "/v1/api/admin/promo-codes/:id",
+            patch(update_promo_code_handler),
+        )
+        .route(
+            "/v1/api/admin/rate-plans",
+            get(list_rate_plans_handler).post(create_rate_plan_handler),
+        )
+        .route(
+            "/v1/api/admin/rate-plans/:id",
+            patch(update_rate_plan_handler),
+        )
+        .route(
+            "/v1/api/admin/pricing-rules",
+            get(list_pricing_rules_handler).post(create_pricing_rule_handler),
+        )
+        .route(
+            "/v1/api/admin/pricing-rules/:id",
+            patch(update_pricing_rule_handler),
+        )
+        .route(
+            "/v1/api/admin/price-adjustments",
+            get(list_price_adjustments_handler),
+        )
+        .route(
+            "/v1/api/admin/stay-restrictions",
+            get(list_stay_restrictions_handler).post(create_stay_restriction_handler),
+        )
+        .route(
+            "/v1/api/admin/stay-restrictions/:id",
+            patch(update_stay_restriction_handler).delete(delete_stay_restriction_handler),
+        )
+        .route(
+            "/v1/api/admin/exchange-rates",
+            get(list_exchange_rates_handler),
+        )
+        .route(
+            "/v1/api/admin/exchange-rates/:currency",
+            put(put_exchange_rate_handler).delete(delete_exchange_rate_handler),
+        )
+        .route(
+            "/v1/api/admin/rooms",
+            get(list_rooms_handler).post(create_room_handler),
+        )
+        .route("/v1/api/admin/rooms/:id", patch(update_room_handler))
+        .route(
+            "/v1/api/admin/allotment-blocks",
+            get(list_allotment_blocks_handler).post(create_allotment_block_handler),
+        )
+        .route(
+            "/v1/api/admin/allotment-blocks/:id/pickup",
+            get(allotment_pickup_handler),
+        )
+        .route(
+            "/v1/api/admin/allotment-blocks/:id/release",
+            post(release_allotment_block_handler),
+        )
+        .route("/v1/api/admin/reports/kpi", get(kpi_report_handler))
+        .route("/v1/api/admin/reports/pace", get(pace_report_handler))
+        .route("/v1/api/admin/reports/pickup", get(pickup_report_handler))
+        .route_layer(middleware::from_fn(require_admin))
+        .route_layer(middleware::from_fn(require_staff))
+        .route_layer(middleware::from_fn_with_state(app_state, auth))
 }
@@ -1,6 +1,6 @@
 use bigdecimal::BigDecimal;
-use chrono::NaiveDate;
-use serde::{Deserialize, Serialize};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Deserializer, Serialize};
 
 #[derive(Debug, Deserialize, Default)]
 pub struct FilterOptions {
@@ -8,11 +8,72 @@ pub struct FilterOptions {
     pub limit: Option<usize>,
 }
 
+// Output of the invoice download, one of `pdf` (default), `html` or `json`
+#[derive(Debug, Deserialize, Default)]
+pub struct InvoiceOptions {
+    pub format: Option<String>,
+}
+
+// Day of a front desk list, defaults to today, and its output, `json` (default) or `csv`
+#[derive(Debug, Deserialize, Default)]
+pub struct FrontDeskListOptions {
+    pub date: Option<NaiveDate>,
+    pub format: Option<String>,
+}
+
+// Business dates of the night audits to list, both included
+#[derive(Debug, Deserialize, Default)]
+pub struct NightAuditOptions {
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+}
+
+// Nights to report on, both included, grouped by `day` (default), `week` or `month`
+// The output is `json` (default) or `csv`
+#[derive(Debug, Deserialize)]
+pub struct KpiReportOptions {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub period: Option<String>,
+    pub room_type_id: Option<i32>,
+    // One row per room type and period rather than one per period
+    pub by_room_type: Option<bool>,
+    pub format: Option<String>,
+}
+
+// Stay date to see the booking pace of, over the days before it, 90 by default
+#[derive(Debug, Deserialize)]
+pub struct PaceReportOptions {
+    pub stay_date: NaiveDate,
+    pub room_type_id: Option<i32>,
+    pub days_before: Option<i32>,
+}
+
+// Stay dates to forecast, both included, with the room nights booked over the last `pickup_days` (7 by default)
+// The output is `json` (default) or `csv`
+#[derive(Debug, Deserialize)]
+pub struct PickupReportOptions {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub room_type_id: Option<i32>,
+    pub pickup_days: Option<i32>,
+    pub format: Option<String>,
+}
+
+// Currency to show a quote in, defaults to the preferred currency of the guest
+#[derive(Debug, Deserialize, Default)]
+pub struct QuoteOptions {
+    pub currency: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TokenClaims {
     pub sub: String,
     pub iat: usize,
     pub exp: usize,
+    // Set on the short lived token issued between the password and the second factor
+    #[serde(default)]
+    pub mfa_pending: bool,
 }
 
 // TODO: Validation
@@ -32,13 +93,222 @@ pub struct LoginGuestSchema {
     pub password: String,
 }
 
+// A missing or null currency clears the preference
+#[derive(Debug, Deserialize)]
+pub struct UpdateGuestSchema {
+    pub preferred_currency: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ConfirmMfaSchema {
+    pub code: String,
+}
+
+// Only what identifies the card to the guest, the card itself stays with the processor
+#[derive(Debug, Deserialize)]
+pub struct CreatePaymentMethodSchema {
+    pub brand: String,
+    pub last_four: String,
+    pub exp_month: i32,
+    pub exp_year: i32,
+}
+
+// Either a code from the authenticator app or one of the recovery codes
+#[derive(Debug, Deserialize)]
+pub struct LoginMfaSchema {
+    pub mfa_token: String,
+    pub code: Option<String>,
+    pub recovery_code: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CreateBookingSchema {
     pub checkin_date: NaiveDate,
     pub checkout_date: NaiveDate,
     pub num_adults: i32,
     pub num_children: i32,
-    pub booking_amount: BigDecimal,
+    // Required unless the group or block picked up from gives it
+    pub room_type_id: Option<i32>,
+    // Defaults to the flexible rate plan, without deposit
+    pub rate_plan_id: Option<i32>,
+    pub promo_code: Option<String>,
+    // Loyalty points to take off the booking amount
+    pub redeem_points: Option<i32>,
+    // Picks up a room in the block held for a reservation group
+    pub group_code: Option<String>,
+    // Picks up a room in an allotment block held for a corporate or event group
+    pub block_code: Option<String>,
+    #[serde(flatten)]
+    pub preferences: RoomPreferencesSchema,
+    pub special_requests: Option<String>,
+}
+
+// Room the guest would like, the front desk tries to honour it when assigning a room
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct RoomPreferencesSchema {
+    pub preferred_floor: Option<i32>,
+    pub accessible_room: Option<bool>,
+    pub near_elevator: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AvailabilityOptions {
+    pub checkin_date: NaiveDate,
+    pub checkout_date: NaiveDate,
+    pub num_adults: Option<i32>,
+    pub num_children: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct JoinWaitlistSchema {
+    pub room_type_id: i32,
+    pub checkin_date: NaiveDate,
+    pub checkout_date: NaiveDate,
+    pub num_adults: Option<i32>,
+    pub num_children: Option<i32>,
+}
+
+// Waitlist entries in the order they are offered rooms, `waiting` and `offered` ones by default
+#[derive(Debug, Deserialize, Default)]
+pub struct WaitlistOptions {
+    pub status: Option<String>,
+    pub room_type_id: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CheckInSchema {
+    // Room given to the guest, a room is assigned automatically without it
+    pub room_id: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AssignRoomSchema {
+    // Overrides the room suggested for the booking
+    pub room_id: Option<i32>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct RoomBoardOptions {
+    pub status: Option<String>,
+    pub floor: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetRoomStatusSchema {
+    pub status: String,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateHousekeepingTaskSchema {
+    pub room_id: i32,
+    pub task_type: String,
+    pub assigned_to: Option<i32>,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateHousekeepingTaskSchema {
+    pub assigned_to: Option<i32>,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct MaintenanceTicketOptions {
+    pub status: Option<String>,
+    pub room_id: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateMaintenanceTicketSchema {
+    pub room_id: i32,
+    pub title: String,
+    pub description: Option<String>,
+    // Defaults to normal
+    pub priority: Option<String>,
+    pub assigned_to: Option<i32>,
+    // Out of order tickets take the room out of inventory for the dates given
+    pub out_of_order: Option<bool>,
+    pub out_of_order_from: Option<NaiveDate>,
+    pub out_of_order_until: Option<NaiveDate>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateMaintenanceTicketSchema {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub priority: Option<String>,
+    pub status: Option<String>,
+    pub assigned_to: Option<i32>,
+    // Repairs taking longer keep the room out of order for longer
+    pub out_of_order_until: Option<NaiveDate>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AttachmentOptions {
+    pub file_name: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateRoomSchema {
+    pub room_number: String,
+    pub room_type_id: i32,
+    pub floor: i32,
+    pub accessible: Option<bool>,
+    pub near_elevator: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateRoomSchema {
+    pub room_type_id: Option<i32>,
+    pub floor: Option<i32>,
+    pub active: Option<bool>,
+    pub accessible: Option<bool>,
+    pub near_elevator: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateAllotmentBlockSchema {
+    pub code: String,
+    pub name: String,
+    pub room_type_id: i32,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub rooms: i32,
+    pub cutoff_date: NaiveDate,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateGroupSchema {
+    pub name: String,
+    pub checkin_date: NaiveDate,
+    pub checkout_date: NaiveDate,
+    // Defaults to split billing, each guest paying their own room
+    pub billing_type: Option<String>,
+    pub room_type_id: Option<i32>,
+    // Size of the block, at least the rooms booked by the lead guest
+    pub rooms_held: Option<i32>,
+    pub rate_plan_id: Option<i32>,
+    pub rooms: Vec<GroupRoomSchema>,
+}
+
+// One of the rooms the lead guest books when creating the group
+#[derive(Debug, Deserialize)]
+pub struct GroupRoomSchema {
+    pub num_adults: i32,
+    pub num_children: i32,
+    // Defaults to the room type of the group
+    pub room_type_id: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateGroupSchema {
+    pub name: Option<String>,
+    pub billing_type: Option<String>,
+    pub rooms_held: Option<i32>,
+    // Moving the group dates moves every booking in it
+    pub checkin_date: Option<NaiveDate>,
+    pub checkout_date: Option<NaiveDate>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -47,5 +317,215 @@ pub struct UpdateBookingSchema {
     pub checkout_date: Option<NaiveDate>,
     pub num_adults: Option<i32>,
     pub num_children: Option<i32>,
-    pub booking_amount: Option<BigDecimal>,
+    #[serde(flatten)]
+    pub preferences: RoomPreferencesSchema,
+    pub special_requests: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PostChargeSchema {
+    pub line_type: String,
+    pub description: String,
+    pub quantity: Option<i32>,
+    pub unit_price: BigDecimal,
+    pub tax_amount: Option<BigDecimal>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VoidChargeSchema {
+    pub reason: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RecordPaymentSchema {
+    pub amount: BigDecimal,
+    // Payments are taken in the currency of the booking, given to double check it
+    pub currency: Option<String>,
+    pub method: String,
+    pub reference: Option<String>,
+    // Required when paying with a gift voucher
+    pub voucher_code: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PurchaseGiftVoucherSchema {
+    pub amount: BigDecimal,
+    pub recipient_name: Option<String>,
+    pub recipient_email: Option<String>,
+    pub message: Option<String>,
+    // How the front desk took the payment for the voucher
+    pub method: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateTaxRuleSchema {
+    pub name: String,
+    pub kind: String,
+    pub rate: BigDecimal,
+    pub line_types: Option<Vec<String>>,
+    pub exempt_children: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateTaxRuleSchema {
+    pub name: Option<String>,
+    pub rate: Option<BigDecimal>,
+    pub line_types: Option<Vec<String>>,
+    pub exempt_children: Option<bool>,
+    pub active: Option<bool>,
+}
+
+// Occupancy is in percent of the rooms of the type that can be sold for the night
+// A positive adjustment raises the rate, a negative one discounts it
+#[derive(Debug, Deserialize)]
+pub struct CreatePricingRuleSchema {
+    pub name: String,
+    pub room_type_id: Option<i32>,
+    pub occupancy_above: Option<BigDecimal>,
+    pub occupancy_below: Option<BigDecimal>,
+    pub max_days_before_arrival: Option<i32>,
+    pub adjustment_percent: BigDecimal,
+    pub floor_price: Option<BigDecimal>,
+    pub ceiling_price: Option<BigDecimal>,
+    pub priority: Option<i32>,
+}
+
+// Conditions and bounds set to null are removed, those left out are kept
+#[derive(Debug, Deserialize)]
+pub struct UpdatePricingRuleSchema {
+    pub name: Option<String>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub occupancy_above: Option<Option<BigDecimal>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub occupancy_below: Option<Option<BigDecimal>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub max_days_before_arrival: Option<Option<i32>>,
+    pub adjustment_percent: Option<BigDecimal>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub floor_price: Option<Option<BigDecimal>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub ceiling_price: Option<Option<BigDecimal>>,
+    pub priority: Option<i32>,
+    pub active: Option<bool>,
+}
+
+// Adjustments made to the nights from `from` to `to`, both included, or to one booking
+#[derive(Debug, Deserialize, Default)]
+pub struct PriceAdjustmentOptions {
+    pub booking_id: Option<i32>,
+    pub pricing_rule_id: Option<i32>,
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+}
+
+// Dates from `start_date` to `end_date` are both included, without a room type it applies to every room type
+#[derive(Debug, Deserialize)]
+pub struct CreateStayRestrictionSchema {
+    pub room_type_id: Option<i32>,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub min_stay: Option<i32>,
+    pub max_stay: Option<i32>,
+    pub closed_to_arrival: Option<bool>,
+    pub closed_to_departure: Option<bool>,
+    pub stop_sell: Option<bool>,
+    pub reason: Option<String>,
+}
+
+// Stay lengths can't be removed once set, delete the restriction and create a new one instead
+#[derive(Debug, Deserialize)]
+pub struct UpdateStayRestrictionSchema {
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+    pub min_stay: Option<i32>,
+    pub max_stay: Option<i32>,
+    pub closed_to_arrival: Option<bool>,
+    pub closed_to_departure: Option<bool>,
+    pub stop_sell: Option<bool>,
+    pub reason: Option<String>,
+}
+
+// Restrictions set on some of the dates from `from` to `to`, both included
+#[derive(Debug, Deserialize, Default)]
+pub struct StayRestrictionOptions {
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    pub room_type_id: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExchangeRateSchema {
+    pub rate: BigDecimal,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateRatePlanSchema {
+    pub code: String,
+    pub name: String,
+    pub deposit_type: String,
+    pub deposit_value: Option<BigDecimal>,
+    pub deposit_due_hours: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateRatePlanSchema {
+    pub name: Option<String>,
+    pub deposit_type: Option<String>,
+    pub deposit_value: Option<BigDecimal>,
+    pub deposit_due_hours: Option<i32>,
+    pub active: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateRoomTypeSchema {
+    pub code: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub base_rate: BigDecimal,
+    pub max_occupancy: i32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateRoomTypeSchema {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub base_rate: Option<BigDecimal>,
+    pub max_occupancy: Option<i32>,
+    pub active: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreatePromoCodeSchema {
+    pub code: String,
+    pub description: Option<String>,
+    pub discount_type: String,
+    pub discount_value: BigDecimal,
+    pub valid_from: Option<DateTime<Utc>>,
+    pub valid_until: Option<DateTime<Utc>>,
+    pub min_nights: Option<i32>,
+    pub max_uses: Option<i32>,
+    pub max_uses_per_guest: Option<i32>,
+    pub room_type_ids: Option<Vec<i32>>,
+}
+
+// Limits and windows can't be removed once set, deactivate the code and create a new one instead
+#[derive(Debug, Deserialize)]
+pub struct UpdatePromoCodeSchema {
+    pub description: Option<String>,
+    pub valid_from: Option<DateTime<Utc>>,
+    pub valid_until: Option<DateTime<Utc>>,
+    pub min_nights: Option<i32>,
+    pub max_uses: Option<i32>,
+    pub max_uses_per_guest: Option<i32>,
+    pub room_type_ids: Option<Vec<i32>>,
+    pub active: Option<bool>,
+}
+
+// Tell a field left out (`None`) from one set to null (`Some(None)`)
+fn nullable<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
 }
@@ -0,0 +1,73 @@
+use bigdecimal::{BigDecimal, Zero};
+use serde::Serialize;
+use sqlx::PgExecutor;
+
+use crate::models::TaxRule;
+
+// Ways a tax rule can compute its amount
+pub const TAX_KINDS: &[&str] = &["percentage", "per_person_per_night"];
+
+// Tax computed by one rule for one charge
+#[derive(Debug, Serialize, Clone)]
+pub struct TaxLine {
+    pub tax_rule_id: i32,
+    pub name: String,
+    pub taxable_amount: BigDecimal,
+    pub amount: BigDecimal,
+}
+
+// A charge taxes are computed for, with the stay details per person taxes need
+pub struct Taxable<'a> {
+    pub line_type: &'a str,
+    pub net_amount: &'a BigDecimal,
+    pub nights: i32,
+    pub num_adults: i32,
+    pub num_children: i32,
+}
+
+// Load the rules currently in force
+pub async fn active_tax_rules<'e>(
+    executor: impl PgExecutor<'e>,
+) -> Result<Vec<TaxRule>, sqlx::Error> {
+    sqlx::query_as!(
+        TaxRule,
+        "select * from tax_rule where active = true order by id"
+    )
+    .fetch_all(executor)
+    .await
+}
+
+// Apply every rule matching the type of the charge, rules adding nothing are left out
+pub fn compute_taxes(rules: &[TaxRule], taxable: &Taxable) -> Vec<TaxLine> {
+    rules
+        .iter()
+        .filter(|rule| rule.line_types.iter().any(|t| t == taxable.line_type))
+        .filter_map(|rule| {
+            let amount = match rule.kind.as_str() {
+                "percentage" => taxable.net_amount * &rule.rate / BigDecimal::from(100),
+                "per_person_per_night" => {
+                    // Children don't count towards the persons when the rule exempts them
+                    let persons = if rule.exempt_children {
+                        taxable.num_adults
+                    } else {
+                        taxable.num_adults + taxable.num_children
+                    };
+                    &rule.rate * BigDecimal::from(persons) * BigDecimal::from(taxable.nights)
+                }
+                _ => return None,
+            };
+
+            let amount = amount.round(2).with_scale(2);
+            if amount.is_zero() {
+                return None;
+            }
+
+            Some(TaxLine {
+                tax_rule_id: rule.id,
+                name: rule.name.to_owned(),
+                taxable_amount: taxable.net_amount.with_scale(2),
+                amount,
+            })
+        })
+        .collect()
+}
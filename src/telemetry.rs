@@ -0,0 +1,174 @@
+use std::time::{Duration, Instant};
+
+use axum::{
+    body::{to_bytes, Body},
+    extract::MatchedPath,
+    http::{
+        header::{CONTENT_LENGTH, CONTENT_TYPE},
+        Request,
+    },
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use bigdecimal::{BigDecimal, ToPrimitive};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+use sqlx::{Pool, Postgres};
+use tracing::Span;
+
+// Header carrying the request id, an incoming value is kept as is
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+const REQUEST_DURATION_SECONDS: &str = "http_request_duration_seconds";
+
+// Latency buckets in seconds, from 5ms up to 10s
+const REQUEST_DURATION_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+// Install the global prometheus recorder and return the handle used to render it
+pub fn setup_metrics_recorder() -> PrometheusHandle {
+    PrometheusBuilder::new()
+        .set_buckets_for_metric(
+            Matcher::Full(REQUEST_DURATION_SECONDS.to_string()),
+            REQUEST_DURATION_BUCKETS,
+        )
+        .unwrap()
+        .install_recorder()
+        .unwrap()
+}
+
+// Middleware to count requests and measure their latency per route
+pub async fn track_metrics(req: Request<Body>, next: Next) -> impl IntoResponse {
+    let start = Instant::now();
+
+    // Use the route template instead of the raw uri to keep the label cardinality low
+    let path = match req.extensions().get::<MatchedPath>() {
+        Some(matched_path) => matched_path.as_str().to_owned(),
+        None => req.uri().path().to_owned(),
+    };
+    let method = req.method().to_string();
+
+    let response = next.run(req).await;
+
+    let latency = start.elapsed().as_secs_f64();
+    let labels = [
+        ("method", method),
+        ("path", path),
+        ("status", response.status().as_u16().to_string()),
+    ];
+
+    metrics::counter!("http_requests_total", &labels).increment(1);
+    metrics::histogram!(REQUEST_DURATION_SECONDS, &labels).record(latency);
+
+    response
+}
+
+// Request id set on the request by the request id layer
+fn request_id<B>(req: &Request<B>) -> Option<&str> {
+    req.headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+}
+
+// Create the span every request is handled in, the guest id is filled in by the auth middleware
+pub fn make_request_span<B>(req: &Request<B>) -> Span {
+    let route = match req.extensions().get::<MatchedPath>() {
+        Some(matched_path) => matched_path.as_str(),
+        None => req.uri().path(),
+    };
+
+    tracing::info_span!(
+        "request",
+        request_id = request_id(req).unwrap_or_default(),
+        method = %req.method(),
+        route,
+        guest_id = tracing::field::Empty,
+        status = tracing::field::Empty,
+    )
+}
+
+// Log the outcome of every request once the response is ready
+pub fn log_response<B>(response: &Response<B>, latency: Duration, span: &Span) {
+    span.record("status", response.status().as_u16());
+    tracing::info!(
+        latency_ms = latency.as_millis() as u64,
+        "finished processing request"
+    );
+}
+
+// Middleware to add the request id to json error responses so support can find the logs
+pub async fn echo_request_id(req: Request<Body>, next: Next) -> Response {
+    let request_id = request_id(&req).map(str::to_owned);
+    let response = next.run(req).await;
+
+    let Some(request_id) = request_id else {
+        return response;
+    };
+
+    let is_error = response.status().is_client_error() || response.status().is_server_error();
+    let is_json = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .is_some_and(|value| value.as_bytes().starts_with(b"application/json"));
+
+    if !is_error || !is_json {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(_) => return Response::from_parts(parts, Body::empty()),
+    };
+
+    let body = match serde_json::from_slice::<serde_json::Value>(&bytes) {
+        Ok(serde_json::Value::Object(mut error_response)) => {
+            error_response.insert("request_id".to_string(), request_id.into());
+            Body::from(serde_json::Value::Object(error_response).to_string())
+        }
+        _ => Body::from(bytes),
+    };
+
+    // The body length changed, let hyper compute it again
+    parts.headers.remove(CONTENT_LENGTH);
+
+    Response::from_parts(parts, body)
+}
+
+// Record the current utilisation of the database connection pool
+pub fn record_pool_metrics(pool: &Pool<Postgres>) {
+    let size = pool.size() as f64;
+    let idle = pool.num_idle() as f64;
+
+    metrics::gauge!("db_pool_connections").set(size);
+    metrics::gauge!("db_pool_idle_connections").set(idle);
+    metrics::gauge!("db_pool_active_connections").set(size - idle);
+    metrics::gauge!("db_pool_max_connections").set(pool.options().get_max_connections() as f64);
+}
+
+// Record the outcome of a login attempt, `result` is either "success" or "failure"
+pub fn record_login(result: &'static str) {
+    metrics::counter!("guest_logins_total", "result" => result).increment(1);
+}
+
+// Record a request rejected by the auth rate limiter, `scope` tells which limit was hit
+pub fn record_rate_limited(scope: &'static str) {
+    metrics::counter!("rate_limited_requests_total", "scope" => scope).increment(1);
+}
+
+// Record a newly created booking and the revenue it brings in
+pub fn record_booking_created(amount: &BigDecimal) {
+    metrics::counter!("bookings_created_total").increment(1);
+    metrics::counter!("booking_revenue_cents_total").increment(to_cents(amount));
+}
+
+// Record a cancelled booking and the revenue it took away
+pub fn record_booking_cancelled(amount: &BigDecimal) {
+    metrics::counter!("bookings_cancelled_total").increment(1);
+    metrics::counter!("booking_cancelled_revenue_cents_total").increment(to_cents(amount));
+}
+
+// Counters only accept integers, so money is tracked in cents
+fn to_cents(amount: &BigDecimal) -> u64 {
+    (amount * BigDecimal::from(100)).to_u64().unwrap_or(0)
+}
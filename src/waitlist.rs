@@ -0,0 +1,154 @@
+use chrono::{Duration, NaiveDate, Utc};
+use sqlx::{PgConnection, Pool, Postgres};
+
+use crate::{availability::available_rooms, models::WaitlistEntry};
+
+pub const WAITLIST_STATUSES: &[&str] = &["waiting", "offered", "booked", "expired", "cancelled"];
+
+pub const WAITLIST_STATUS_WAITING: &str = "waiting";
+pub const WAITLIST_STATUS_OFFERED: &str = "offered";
+pub const WAITLIST_STATUS_BOOKED: &str = "booked";
+pub const WAITLIST_STATUS_EXPIRED: &str = "expired";
+pub const WAITLIST_STATUS_CANCELLED: &str = "cancelled";
+
+pub const NOTIFICATION_WAITLIST_OFFER: &str = "waitlist_offer";
+
+// Offer the rooms freed on the dates guests are waiting for, in the order they joined the waitlist
+// Offers not taken up in time are expired first, their rooms go to the next guests
+pub async fn offer_freed_rooms(
+    db: &Pool<Postgres>,
+    hold_hours: i64,
+) -> Result<Vec<WaitlistEntry>, sqlx::Error> {
+    let mut tx = db.begin().await?;
+
+    // Guests still waiting once their arrival date has passed won't come anymore
+    sqlx::query!(
+        "update waitlist_entry set status = $1, updated_at = now()
+        where (status = $2 and hold_expires_at <= now())
+            or (status = $3 and checkin_date < (select business_date from business_date))",
+        WAITLIST_STATUS_EXPIRED,
+        WAITLIST_STATUS_OFFERED,
+        WAITLIST_STATUS_WAITING
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let waiting = sqlx::query_as!(
+        WaitlistEntry,
+        "select * from waitlist_entry where status = $1 order by created_at, id for update",
+        WAITLIST_STATUS_WAITING
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let mut offered = Vec::new();
+    for entry in waiting {
+        // Lock the room type so the room can't be sold while it is being offered
+        let room_type_name = sqlx::query_scalar!(
+            "select name from room_type where id = $1 for update",
+            entry.room_type_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let available = available_rooms(
+            &mut tx,
+            entry.room_type_id,
+            entry.checkin_date,
+            entry.checkout_date,
+        )
+        .await?;
+        if available < 1 {
+            continue;
+        }
+
+        let hold_expires_at = Utc::now() + Duration::hours(hold_hours);
+        let entry = sqlx::query_as!(
+            WaitlistEntry,
+            "update waitlist_entry set
+            status = $1,
+            offered_at = now(),
+            hold_expires_at = $2,
+            updated_at = now()
+            where id = $3
+            returning *",
+            WAITLIST_STATUS_OFFERED,
+            hold_expires_at,
+            entry.id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            "insert into notification (guest_id, kind, message, waitlist_entry_id)
+            values ($1, $2, $3, $4)",
+            entry.guest_id,
+            NOTIFICATION_WAITLIST_OFFER,
+            format!(
+                "A {} is available from {} to {}, it is held for you until {} UTC",
+                room_type_name,
+                entry.checkin_date,
+                entry.checkout_date,
+                hold_expires_at.format("%Y-%m-%d %H:%M")
+            ),
+            entry.id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        offered.push(entry);
+    }
+
+    tx.commit().await?;
+
+    Ok(offered)
+}
+
+// Room held for the guest from the waitlist that covers the stay, the entry row stays locked
+pub async fn held_for_guest(
+    conn: &mut PgConnection,
+    guest_id: i32,
+    room_type_id: i32,
+    checkin_date: NaiveDate,
+    checkout_date: NaiveDate,
+) -> Result<Option<WaitlistEntry>, sqlx::Error> {
+    sqlx::query_as!(
+        WaitlistEntry,
+        "select * from waitlist_entry
+        where guest_id = $1
+            and room_type_id = $2
+            and status = $3
+            and hold_expires_at > now()
+            and checkin_date <= $4
+            and checkout_date >= $5
+        order by id
+        limit 1
+        for update",
+        guest_id,
+        room_type_id,
+        WAITLIST_STATUS_OFFERED,
+        checkin_date,
+        checkout_date
+    )
+    .fetch_optional(conn)
+    .await
+}
+
+// The guest booked the room held for them
+pub async fn mark_booked(
+    conn: &mut PgConnection,
+    waitlist_entry_id: i32,
+    booking_id: i32,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        "update waitlist_entry set status = $1, booking_id = $2, updated_at = now()
+        where id = $3",
+        WAITLIST_STATUS_BOOKED,
+        booking_id,
+        waitlist_entry_id
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
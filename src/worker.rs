@@ -0,0 +1,208 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use serde::Serialize;
+
+use chrono::Utc;
+
+use crate::{
+    allotment::release_blocks_past_cutoff,
+    business_date::current_business_date,
+    deposit::expire_overdue_deposits,
+    night_audit::{run_night_audit, AuditError},
+    waitlist::offer_freed_rooms,
+    AppState,
+};
+
+// Last heartbeat of a background worker and how often it is expected to beat
+struct Heartbeat {
+    last_beat: Option<Instant>,
+    max_interval: Duration,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WorkerStatus {
+    pub name: &'static str,
+    pub healthy: bool,
+    pub seconds_since_last_beat: Option<u64>,
+    pub max_interval_seconds: u64,
+}
+
+// Keep track of the heartbeats of every registered background worker
+#[derive(Default)]
+pub struct Heartbeats {
+    workers: Mutex<HashMap<&'static str, Heartbeat>>,
+}
+
+impl Heartbeats {
+    // Register a worker that is expected to beat at least once every `max_interval`
+    pub fn register(&self, name: &'static str, max_interval: Duration) {
+        self.workers.lock().unwrap().insert(
+            name,
+            Heartbeat {
+                last_beat: None,
+                max_interval,
+            },
+        );
+    }
+
+    // Record that the worker is still alive
+    pub fn beat(&self, name: &'static str) {
+        if let Some(heartbeat) = self.workers.lock().unwrap().get_mut(name) {
+            heartbeat.last_beat = Some(Instant::now());
+        }
+    }
+
+    // Status of every registered worker, a worker that never beat is unhealthy
+    pub fn statuses(&self) -> Vec<WorkerStatus> {
+        let workers = self.workers.lock().unwrap();
+
+        let mut statuses: Vec<WorkerStatus> = workers
+            .iter()
+            .map(|(name, heartbeat)| {
+                let elapsed = heartbeat.last_beat.map(|beat| beat.elapsed());
+                WorkerStatus {
+                    name,
+                    healthy: elapsed.is_some_and(|elapsed| elapsed <= heartbeat.max_interval),
+                    seconds_since_last_beat: elapsed.map(|elapsed| elapsed.as_secs()),
+                    max_interval_seconds: heartbeat.max_interval.as_secs(),
+                }
+            })
+            .collect();
+
+        statuses.sort_by_key(|status| status.name);
+        statuses
+    }
+}
+
+const DEPOSIT_EXPIRY_WORKER: &str = "deposit_expiry";
+
+// Periodically expire the bookings whose deposit wasn't paid in time
+pub fn spawn_deposit_expiry(app_state: Arc<AppState>) {
+    let interval = Duration::from_secs(app_state.env.deposit_expiry_interval_secs);
+
+    // A few missed runs in a row mean the worker is stuck
+    app_state
+        .heartbeats
+        .register(DEPOSIT_EXPIRY_WORKER, interval * 3);
+
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+
+            match expire_overdue_deposits(&app_state.db).await {
+                Ok(booking_ids) => {
+                    for booking_id in booking_ids {
+                        tracing::info!(booking_id, "booking expired, deposit not paid in time");
+                    }
+                    app_state.heartbeats.beat(DEPOSIT_EXPIRY_WORKER);
+                }
+                Err(e) => tracing::error!("Failed to expire overdue deposits: {}", e),
+            }
+        }
+    });
+}
+
+const ALLOTMENT_RELEASE_WORKER: &str = "allotment_release";
+
+// Periodically release the rooms of allotment blocks that weren't picked up by their cutoff date
+pub fn spawn_allotment_release(app_state: Arc<AppState>) {
+    let interval = Duration::from_secs(app_state.env.allotment_release_interval_secs);
+
+    app_state
+        .heartbeats
+        .register(ALLOTMENT_RELEASE_WORKER, interval * 3);
+
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+
+            match release_blocks_past_cutoff(&app_state.db).await {
+                Ok(block_ids) => {
+                    for allotment_block_id in block_ids {
+                        tracing::info!(allotment_block_id, "allotment block released at cutoff");
+                    }
+                    app_state.heartbeats.beat(ALLOTMENT_RELEASE_WORKER);
+                }
+                Err(e) => tracing::error!("Failed to release allotment blocks: {}", e),
+            }
+        }
+    });
+}
+
+const NIGHT_AUDIT_WORKER: &str = "night_audit";
+
+// Periodically close the business dates the calendar has moved past, when the night audit wasn't run by staff
+pub fn spawn_night_audit(app_state: Arc<AppState>) {
+    let interval = Duration::from_secs(app_state.env.night_audit_interval_secs);
+
+    app_state
+        .heartbeats
+        .register(NIGHT_AUDIT_WORKER, interval * 3);
+
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+
+            match audit_past_days(&app_state).await {
+                Ok(()) => app_state.heartbeats.beat(NIGHT_AUDIT_WORKER),
+                Err(AuditError::Rejected(message)) => {
+                    tracing::warn!("Night audit skipped: {}", message)
+                }
+                Err(AuditError::Database(e)) => {
+                    tracing::error!("Failed to run the night audit: {}", e)
+                }
+            }
+        }
+    });
+}
+
+const WAITLIST_WORKER: &str = "waitlist";
+
+// Periodically expire the waitlist offers not taken up in time and offer the rooms freed since the last run
+pub fn spawn_waitlist_offers(app_state: Arc<AppState>) {
+    let interval = Duration::from_secs(app_state.env.waitlist_interval_secs);
+
+    app_state.heartbeats.register(WAITLIST_WORKER, interval * 3);
+
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+
+            match offer_freed_rooms(&app_state.db, app_state.env.waitlist_hold_hours).await {
+                Ok(entries) => {
+                    for entry in entries {
+                        tracing::info!(
+                            waitlist_entry_id = entry.id,
+                            "room offered to the waitlist"
+                        );
+                    }
+                    app_state.heartbeats.beat(WAITLIST_WORKER);
+                }
+                Err(e) => tracing::error!("Failed to offer rooms to the waitlist: {}", e),
+            }
+        }
+    });
+}
+
+// Audit one day after the other until the business date catches up with the calendar
+async fn audit_past_days(app_state: &AppState) -> Result<(), AuditError> {
+    while current_business_date(&app_state.db).await? < Utc::now().date_naive() {
+        let audit = run_night_audit(&app_state.db, None).await?;
+        tracing::info!(
+            business_date = %audit.business_date,
+            room_charges_posted = audit.room_charges_posted,
+            no_shows = audit.no_shows,
+            "night audit completed"
+        );
+    }
+
+    Ok(())
+}